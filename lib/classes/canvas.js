@@ -4,11 +4,13 @@
 
 "use strict"
 
-const {fileURLToPath} = require('url'),
-      {RustClass, core, inspect, argc, REPR} = require('./neon'),
+const fs = require('fs'),
+      {fileURLToPath} = require('url'),
+      {RustClass, neon, core, inspect, argc, REPR} = require('./neon'),
       {Image, ImageData, pixelSize, getSharp} = require('./imagery'),
       {Path2D} = require('./path'),
-      {toSkMatrix} = require('./geometry')
+      {toSkMatrix, toCoords} = require('./geometry'),
+      {fromSpec} = require('./filter')
 
 class Canvas extends RustClass{
   #contexts
@@ -28,6 +30,16 @@ class Canvas extends RustClass{
 
   get engine(){ return JSON.parse(this.prop('engine_status')) }
 
+  // the bitmap cache shared by all canvases' pages
+  static get cacheStats(){
+    // JSON can't represent Infinity, so the lack of a limit is serialized as null
+    let stats = JSON.parse(neon.Canvas.cacheStats.get(null))
+    return {...stats, limit:stats.limit ?? Infinity}
+  }
+  static get cacheLimit(){ return neon.Canvas.cacheLimit.get(null) }
+  static set cacheLimit(bytes){ neon.Canvas.cacheLimit.set(null, bytes) }
+  static purgeCache(){ neon.Canvas.purgeCache(null) }
+
   get width(){ return this.prop('width') }
   set width(w){
     this.prop('width', !Number.isNaN(+w) && +w>=0 ? w : 300)
@@ -67,23 +79,25 @@ class Canvas extends RustClass{
   toDataURLSync(){ _deprecated('Canvas.toDataURLSync()'); this.toURLSync(...arguments) }
 
   toFile(filename, opts={}){
-    let {pages, padding, pattern, ...rest} = exportOptions(this, {filename}, opts),
+    if (isWritable(filename)) return toWritable(this, filename, opts)
+
+    let {pages, padding, pattern, signal, progress, ...rest} = exportOptions(this, {filename}, opts),
         args = [pages.map(core), pattern, padding, rest]
-    return this.ƒ("save", ...args)
+    return monitor(signal, progress, (...task) => this.ƒ("save", ...args, ...task))
   }
 
   toFileSync(filename, opts={}){
-    let {pages, padding, pattern, ...rest} = exportOptions(this, {filename}, opts)
+    let {pages, padding, pattern, signal, progress, ...rest} = exportOptions(this, {filename}, opts)
     this.ƒ("saveSync", pages.map(core), pattern, padding, rest)
   }
 
   toBuffer(extension="png", opts={}){
-    let {pages, ...rest} = exportOptions(this, {extension}, opts)
-    return this.ƒ("toBuffer", pages.map(core), rest)
+    let {pages, signal, progress, ...rest} = exportOptions(this, {extension}, opts)
+    return monitor(signal, progress, (...task) => this.ƒ("toBuffer", pages.map(core), rest, ...task))
   }
 
   toBufferSync(extension="png", opts={}){
-    let {pages, ...rest} = exportOptions(this, {extension}, opts)
+    let {pages, signal, progress, ...rest} = exportOptions(this, {extension}, opts)
     return this.ƒ("toBufferSync", pages.map(core), rest)
   }
 
@@ -129,14 +143,30 @@ class CanvasGradient extends RustClass{
   constructor(style, ...coords){
     super(CanvasGradient)
     style = (style || "").toLowerCase()
-    if (['linear', 'radial', 'conic'].includes(style)) this.init(style, ...coords)
-    else throw new Error(`Function is not a constructor (use CanvasRenderingContext2D's "createConicGradient", "createLinearGradient", and "createRadialGradient" methods instead)`)
+    if (['linear', 'radial', 'conic', 'mesh', 'css'].includes(style)) this.init(style, ...coords)
+    else throw new Error(`Function is not a constructor (use CanvasRenderingContext2D's "createConicGradient", "createCSSGradient", "createLinearGradient", "createMeshGradient", and "createRadialGradient" methods instead)`)
   }
 
   addColorStop(offset, color){
     this.ƒ('addColorStop', ...arguments)
   }
 
+  addPatch(points, colors){
+    argc(arguments, 2)
+    this.ƒ('addPatch', toCoords(points), Array.from(colors ?? []))
+  }
+
+  get spread(){ return this.prop('spread') }
+  set spread(mode){ this.prop('spread', mode) }
+
+  get interpolation(){ return this.prop('interpolation') }
+  set interpolation(spec){ this.prop('interpolation', spec) }
+
+  get dither(){ return this.prop('dither') }
+  set dither(flag){ this.prop('dither', !!flag) }
+
+  setTransform(matrix) { this.ƒ('setTransform', toSkMatrix.apply(null, arguments)) }
+
   [REPR](depth, options) {
     return `CanvasGradient (${this.ƒ("repr")})`
   }
@@ -186,6 +216,65 @@ class CanvasTexture extends RustClass{
   }
 }
 
+class CanvasShader extends RustClass{
+  constructor(source){
+    super(CanvasShader)
+    argc(arguments, 1)
+    this.alloc(String(source))
+  }
+
+  get uniforms(){ return JSON.parse(this.prop('uniforms')) }
+
+  setUniform(name, value){
+    argc(arguments, 2)
+    // images & canvases are sampled as repeating patterns
+    if (value instanceof Image || value instanceof ImageData || value instanceof Canvas){
+      value = new CanvasPattern(value, value, 'repeat')
+    }
+
+    let isShader = value instanceof CanvasPattern || value instanceof CanvasGradient || value instanceof CanvasShader
+    value = isShader ? core(value)
+          : typeof value=='number' ? [value]
+          : typeof value=='string' ? value
+          : value?.[Symbol.iterator] ? Array.from(value)
+          : value
+    this.ƒ('setUniform', String(name), value)
+    return this
+  }
+
+  setTransform(matrix) { this.ƒ('setTransform', toSkMatrix.apply(null, arguments)) }
+
+  [REPR](depth, options) {
+    return `CanvasShader (${this.ƒ("repr")})`
+  }
+}
+
+class CanvasFilter extends RustClass{
+  constructor(primitives){
+    super(CanvasFilter)
+    argc(arguments, 1)
+
+    // images & canvases are drawn once (at their natural size) rather than being tiled
+    let sources = [],
+        addSource = (src) => {
+          if (src instanceof Image || src instanceof ImageData || src instanceof Canvas){
+            src = new CanvasPattern(src, src, 'no-repeat')
+          }
+          if (!(src instanceof CanvasPattern || src instanceof CanvasGradient || src instanceof CanvasShader)){
+            throw new TypeError("Expected an Image, Canvas, ImageData, CanvasGradient, CanvasPattern, or CanvasShader for `image`")
+          }
+          return sources.push(core(src)) - 1
+        }
+
+    primitives = [primitives].flat().map(spec => fromSpec(spec, addSource))
+    this.alloc(JSON.stringify(primitives), sources)
+  }
+
+  [REPR](depth, options) {
+    return `CanvasFilter (${this.ƒ("repr")})`
+  }
+}
+
 
 //
 // Mime type <-> File extension mappings
@@ -230,7 +319,7 @@ function exportOptions(canvas, {filename='', extension=''}, opts){
   if (typeof opts=='number') opts = {quality:opts}
 
   // unpack common export options
-  let {page, quality, matte, density, msaa, outline, downsample, colorType} = opts
+  let {page, quality, matte, density, msaa, outline, downsample, colorType, signal, progress, perPage} = opts
 
   // only allow format overrides in toFile()
   let imageFormat = !!filename ? opts.format : undefined
@@ -253,12 +342,14 @@ function exportOptions(canvas, {filename='', extension=''}, opts){
   if (!ext) throw new Error(`Cannot determine image format (use a filename extension or 'format' argument)`)
   if (!format) throw new Error(`Unsupported file format "${ext}" (expected ${expected})`)
 
-  let padding, isSequence, pattern = filename.replace(/{(\d*)}/g, (_, width) => {
+  // page numbers can be `{}`, `{4}`, `{page}`, or `{page:4}` (and `{index}` counts from zero)
+  let padding, isSequence, pattern = filename.replace(/{(?:(page|index)(?::(\d+))?|(\d*))}/g, (_, token, width, digits) => {
     isSequence = true
-    width = parseInt(width, 10)
+    width = parseInt(width || digits, 10)
     padding = isFinite(width) ? width : isFinite(padding) ? padding : -1
-    return "{}"
+    return token=='index' ? "{index}" : "{}"
   })
+  if (pattern.includes("{label}")) isSequence = true
 
   // allow negative indexing if a specific page is specified
   let idx = page > 0 ? page - 1
@@ -274,6 +365,30 @@ function exportOptions(canvas, {filename='', extension=''}, opts){
         : isSequence || format=='pdf' ? pages
         : pages.slice(-1) // default to the 'current' context
 
+  // per-page overrides can be an array (parallel to `pages`) or a function returning one entry per page
+  if (perPage!==undefined){
+    if (typeof perPage!='function' && !Array.isArray(perPage)){
+      throw new TypeError("Expected an array or function for `perPage`")
+    }
+
+    perPage = pages.map((ctx, i) => {
+      let entry = typeof perPage=='function' ? perPage(ctx, i) : perPage[i]
+      if (entry===undefined || entry===null) return {}
+      if (typeof entry=='string') return {label:entry}
+      if (typeof entry!='object') throw new TypeError(`Expected an object or label string for \`perPage[${i}]\``)
+
+      let {label, format:fmt, density:dpr, matte:bg} = entry
+      if (fmt!==undefined){
+        fmt = fromMime(toMime(fmt) || fmt)
+        if (!fmt) throw new Error(`Unsupported file format "${entry.format}" for page ${i+1} (expected ${expected})`)
+      }
+      if (dpr!==undefined && (!Number.isInteger(dpr) || dpr<1)){
+        throw new TypeError(`Expected a non-negative integer for \`density\` on page ${i+1}`)
+      }
+      return {label: label===undefined ? undefined : String(label), format:fmt, density:dpr, matte:bg}
+    })
+  }
+
   // inherit text settings from the canvas (since they can't be changed on a per-render basis due to glyph caching)
   const {textContrast, textGamma} = canvas.engine
 
@@ -302,16 +417,99 @@ function exportOptions(canvas, {filename='', extension=''}, opts){
     pixelSize(colorType) // throw an error if invalid
   }
 
+  if (signal!==undefined && typeof signal?.aborted!='boolean'){
+    throw new TypeError("Expected an AbortSignal for `signal`")
+  }
+
+  if (progress!==undefined && typeof progress!='function'){
+    throw new TypeError("Expected a function for `progress`")
+  }
+
   // default to false, otherwise detect truthy
   downsample = !!downsample
   outline = !!outline
 
   return {
     filename, pattern, format, mime, pages, padding, quality, matte,
-    density, msaa, outline, textContrast, textGamma, downsample, colorType
+    density, msaa, outline, textContrast, textGamma, downsample, colorType,
+    signal, progress, perPage
   }
 }
 
+// connect an AbortSignal and/or progress callback to an async export
+function monitor(signal, progress, run, task){
+  if (!signal && !progress && !task) return run()
+  if (signal?.aborted) return Promise.reject(signal.reason)
+
+  task = task || neon.Canvas.exportTask(null)
+  let cancel = () => neon.Canvas.cancelExport(null, task)
+
+  // treat an abort as a failure even if it arrives after the last page was already rendered
+  signal?.addEventListener('abort', cancel, {once:true})
+  return run(task, progress)
+    .then(result => { if (signal?.aborted) throw signal.reason; return result })
+    .catch(err => { throw signal?.aborted ? signal.reason : err })
+    .finally(() => signal?.removeEventListener('abort', cancel))
+}
+
+// file descriptors & Writable streams can be used in place of a filename
+const isWritable = target => Number.isInteger(target) || typeof target?.write=='function'
+
+function toWritable(canvas, target, opts={}){
+  let {format, end=true} = typeof opts=='object' ? opts : {},
+      {pages, signal, progress, ...rest} = exportOptions(canvas, {extension:format || ''}, opts),
+      isStream = !Number.isInteger(target),
+      task = neon.Canvas.exportTask(null),
+      failure
+
+  // halt the export if the destination stops accepting data (or the caller aborts)
+  let waiting,
+      release = ok => { let ready = waiting; waiting = null; ready?.(ok) },
+      fail = err => {
+        failure = failure || err
+        neon.Canvas.cancelExport(null, task)
+        release(false)
+      },
+      abort = () => fail(signal.reason),
+      closed = () => fail(new Error("The stream was closed before the export was complete"))
+
+  // each chunk must be acknowledged before the encoder produces the next one, so wait for
+  // a stream to 'drain' whenever its write() reports that its buffer is full
+  let write = isStream ? (chunk, ready) => {
+    if (!failure && (target.destroyed || target.writableEnded)) closed()
+    if (failure || target.write(chunk) !== false || !target.once) return ready(!failure)
+    waiting = ready
+    target.once('drain', () => release(!failure))
+  } : (chunk, ready) => {
+    try{
+      for (let offset=0; offset < chunk.length;) offset += fs.writeSync(target, chunk, offset)
+    }catch(err){ fail(err) }
+    ready(!failure)
+  }
+
+  if (isStream){
+    target.on?.('error', fail)
+    target.on?.('close', closed)
+  }
+  signal?.addEventListener('abort', abort, {once:true})
+  return monitor(signal, progress, (...args) => canvas.ƒ("toStream", pages.map(core), rest, write, ...args), task)
+    .catch(err => { throw failure || err })
+    .then(() => {
+      if (failure) throw failure
+      if (isStream) target.off?.('close', closed) // closing is expected from here on
+      if (isStream && end) return new Promise((resolve, reject) =>
+        target.end(err => err ? reject(err) : resolve())
+      )
+    })
+    .finally(() => {
+      signal?.removeEventListener('abort', abort)
+      if (isStream){
+        target.off?.('error', fail)
+        target.off?.('close', closed)
+      }
+    })
+}
+
 // emit a deprecation warning, once per API per process
 let _warnings = {
   "Canvas.saveAs()": "Canvas.toFile()",
@@ -324,4 +522,4 @@ function _deprecated(oldAPI){
   delete _warnings[oldAPI]
 }
 
-module.exports = {Canvas, CanvasGradient, CanvasPattern, CanvasTexture, getSharp}
+module.exports = {Canvas, CanvasGradient, CanvasPattern, CanvasTexture, CanvasShader, CanvasFilter, getSharp}
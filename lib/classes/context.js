@@ -5,8 +5,8 @@
 "use strict"
 
 const {RustClass, core, wrap, inspect, argc, REPR} = require('./neon'),
-      {Canvas, CanvasGradient, CanvasPattern, CanvasTexture} = require('./canvas'),
-      {fromSkMatrix, toSkMatrix} = require('./geometry'),
+      {Canvas, CanvasGradient, CanvasPattern, CanvasTexture, CanvasShader, CanvasFilter} = require('./canvas'),
+      {fromSkMatrix, toSkMatrix, fromSkM44, toSkM44, toCoords} = require('./geometry'),
       {Image, ImageData} = require('./imagery'),
       {TextMetrics} = require('./typography'),
       {Path2D} = require('./path'),
@@ -14,6 +14,46 @@ const {RustClass, core, wrap, inspect, argc, REPR} = require('./neon'),
 
 const toString = val => typeof val=='string' ? val : new String(val).toString()
 
+const toRect = rect => rect && typeof rect=='object' && !Array.isArray(rect)
+  ? [rect.x, rect.y, rect.width, rect.height]
+  : rect
+
+const isRect = rect => rect==null
+  || Array.isArray(rect) && rect.length==4 && rect.every(n => typeof n=='number')
+  || typeof rect=='object' && 'width' in rect
+
+// flatten a quad (four corners clockwise from the upper-left) or a grid (an array of rows of points)
+// into a list of coordinates in row-major order plus the number of columns
+const toWarpGrid = pts => {
+  let rows = Array.from(pts ?? [])
+  if (rows.length > 1 && rows.every(row => row && typeof row[0]=='object')){
+    let cols = rows[0].length
+    if (rows.some(row => row.length != cols)) throw new TypeError("Expected every row of the grid to have the same number of points")
+    return [toCoords(rows.flatMap(row => Array.from(row))), cols]
+  }
+
+  let coords = toCoords(rows)
+  if (coords.length != 8) return [coords, 0]
+  let [x1, y1, x2, y2, x3, y3, x4, y4] = coords
+  return [[x1, y1, x2, y2, x4, y4, x3, y3], 2]
+}
+
+const toImageSource = image => {
+  if (image instanceof Canvas){
+    return core(image.getContext('2d'))
+  }else if (image instanceof Image){
+    if (image.complete) return core(image)
+    else throw Error("Image has not completed loading: listen for `load` event or await `decode()` first")
+  }else if (image instanceof ImageData){
+    return image
+  }else if (image instanceof Promise) {
+    throw Error("Promise has not yet resolved: `await` image loading before drawing")
+  }else{
+    let nonimage = inspect(image, {depth:1})
+    throw Error(`Expected an Image or a Canvas argument (got: ${nonimage})`)
+  }
+}
+
 class CanvasRenderingContext2D extends RustClass{
   #canvas
 
@@ -35,8 +75,26 @@ class CanvasRenderingContext2D extends RustClass{
   save(){ this.ƒ('save') }
   restore(){ this.ƒ('restore') }
 
-  get currentTransform(){ return fromSkMatrix( this.prop('currentTransform') ) }
-  set currentTransform(matrix){ this.setTransform(matrix) }
+  beginLayer({filter, alpha, compositeOperation}={}){
+    filter = filter===undefined ? null
+           : filter instanceof CanvasShader || filter instanceof CanvasFilter ? core(filter)
+           : css.filter(filter)
+    this.ƒ('beginLayer', filter, alpha, compositeOperation)
+  }
+  endLayer(){ this.ƒ('endLayer') }
+
+  applyFilter(filter, ...region){
+    argc(arguments, 1)
+    filter = filter instanceof CanvasShader || filter instanceof CanvasFilter ? core(filter) : css.filter(filter)
+    let [rect] = region
+    region = rect instanceof Path2D ? [core(rect)]
+           : region.length == 1 ? toRect(rect) ?? []
+           : region
+    this.ƒ('applyFilter', filter, ...region)
+  }
+
+  get currentTransform(){ return fromSkM44( this.prop('currentTransform') ) }
+  set currentTransform(matrix){ this.setTransform3D(matrix) }
 
   resetTransform(){ this.ƒ('resetTransform')}
   getTransform(){ return this.currentTransform }
@@ -47,6 +105,13 @@ class CanvasRenderingContext2D extends RustClass{
   scale(x, y){ this.ƒ('scale', ...arguments)}
   rotate(angle){ this.ƒ('rotate', ...arguments)}
 
+  setTransform3D(matrix){ this.prop('currentTransform', toSkM44.apply(null, arguments)) }
+  transform3D(matrix){ this.ƒ('transform', toSkM44.apply(null, arguments)) }
+  translate3d(x, y, z){ this.ƒ('translate3d', ...arguments)}
+  scale3d(x, y, z){ this.ƒ('scale3d', ...arguments)}
+  rotate3d(x, y, z, angle){ this.ƒ('rotate3d', ...arguments)}
+  perspective(distance){ this.ƒ('perspective', ...arguments)}
+
   createProjection(quad, basis){
     return fromSkMatrix(this.ƒ("createProjection", [quad].flat(), [basis].flat()))
   }
@@ -90,6 +155,20 @@ class CanvasRenderingContext2D extends RustClass{
     return this.ƒ('clip', ...arguments)
   }
 
+  clipOut(path, rule){
+    if (path instanceof Path2D) arguments[0] = core(path)
+    return this.ƒ('clipOut', ...arguments)
+  }
+
+  resetClip(){ this.ƒ('resetClip') }
+  getClipBounds(){ return this.ƒ('getClipBounds') }
+  isPointInClip(x, y){ return this.ƒ('isPointInClip', ...arguments) }
+
+  mask(source, {mode="alpha", transform}={}){
+    argc(arguments, 1)
+    this.ƒ('mask', toImageSource(source), mode, transform ? toSkMatrix(transform) : null)
+  }
+
   isPointInPath(path, x, y, rule){
     if (path instanceof Path2D) arguments[0] = core(path)
     return this.ƒ('isPointInPath', ...arguments)
@@ -111,18 +190,38 @@ class CanvasRenderingContext2D extends RustClass{
   createConicGradient(startAngle, x, y){
     return new CanvasGradient("Conic", ...arguments)
   }
+  createMeshGradient(){
+    return new CanvasGradient("Mesh")
+  }
+  createCSSGradient(css, x=0, y=0, width=this.canvas.width, height=this.canvas.height){
+    argc(arguments, 1)
+    return new CanvasGradient("CSS", toString(css), x, y, width, height)
+  }
 
   createTexture(spacing, options){
     return new CanvasTexture(...arguments)
   }
 
+  createShader(source){
+    return new CanvasShader(...arguments)
+  }
+
   // -- fill & stroke ---------------------------------------------------------
   fillRect(x, y, width, height){ this.ƒ('fillRect', ...arguments) }
   strokeRect(x, y, width, height){ this.ƒ('strokeRect', ...arguments) }
   clearRect(x, y, width, height){ this.ƒ('clearRect', ...arguments) }
+  drawMesh(positions, {colors, texCoords, indices, mode='triangles'}={}){
+    argc(arguments, 1)
+    this.ƒ('drawMesh', toCoords(positions), colors ? Array.from(colors) : null,
+           toCoords(texCoords), indices ? Array.from(indices) : null, mode)
+  }
+  drawPatch(points, {colors, texCoords}={}){
+    argc(arguments, 1)
+    this.ƒ('drawPatch', toCoords(points), colors ? Array.from(colors) : null, toCoords(texCoords))
+  }
 
   set fillStyle(style){
-    let isShader = style instanceof CanvasPattern || style instanceof CanvasGradient || style instanceof CanvasTexture,
+    let isShader = style instanceof CanvasPattern || style instanceof CanvasGradient || style instanceof CanvasTexture || style instanceof CanvasShader,
         [ref, val] = isShader ? [style, core(style)] : [null, style]
     this.ref('fill', ref)
     this.prop('fillStyle', val)
@@ -134,7 +233,7 @@ class CanvasRenderingContext2D extends RustClass{
   }
 
   set strokeStyle(style){
-    let isShader = style instanceof CanvasPattern || style instanceof CanvasGradient || style instanceof CanvasTexture,
+    let isShader = style instanceof CanvasPattern || style instanceof CanvasGradient || style instanceof CanvasTexture || style instanceof CanvasShader,
         [ref, val] = isShader ? [style, core(style)] : [null, style]
     this.ref('stroke', ref)
     this.prop('strokeStyle', val)
@@ -160,14 +259,22 @@ class CanvasRenderingContext2D extends RustClass{
   set lineJoin(style){         this.prop("lineJoin", style) }
   get lineWidth(){      return this.prop("lineWidth") }
   set lineWidth(width){        this.prop("lineWidth", width) }
+  get lineWidthProfile(){ return this.prop("lineWidthProfile") }
+  set lineWidthProfile(profile){ this.prop("lineWidthProfile", profile) }
   get miterLimit(){     return this.prop("miterLimit") }
   set miterLimit(limit){       this.prop("miterLimit", limit) }
+  get strokeAlign(){    return this.prop("strokeAlign") }
+  set strokeAlign(mode){       this.prop("strokeAlign", mode) }
 
   // -- imagery ---------------------------------------------------------------
   get imageSmoothingEnabled(){ return this.prop("imageSmoothingEnabled")}
   set imageSmoothingEnabled(flag){    this.prop("imageSmoothingEnabled", !!flag)}
   get imageSmoothingQuality(){ return this.prop("imageSmoothingQuality")}
   set imageSmoothingQuality(level){   this.prop("imageSmoothingQuality", level)}
+  get imageResampling(){       return this.prop("imageResampling")}
+  set imageResampling(mode){          this.prop("imageResampling", mode)}
+  get antialias(){             return this.prop("antialias")}
+  set antialias(mode){                this.prop("antialias", mode)}
 
   createImageData(width, height, settings){
     argc(arguments, 2, 3)
@@ -199,19 +306,41 @@ class CanvasRenderingContext2D extends RustClass{
   }
 
   drawImage(image, ...coords){
-    if (image instanceof Canvas){
-      this.ƒ('drawImage', core(image.getContext('2d')), ...coords)
-    }else if (image instanceof Image){
-      if (image.complete) this.ƒ('drawImage', core(image), ...coords)
-      else throw Error("Image has not completed loading: listen for `load` event or await `decode()` first")
-    }else if (image instanceof ImageData){
-      this.ƒ('drawImage', image, ...coords)
-    }else if (image instanceof Promise) {
-      throw Error("Promise has not yet resolved: `await` image loading before drawing")
-    }else{
-      let nonimage = inspect(image, {depth:1})
-      throw Error(`Expected an Image or a Canvas argument (got: ${nonimage})`)
-    }
+    this.ƒ('drawImage', toImageSource(image), ...coords)
+  }
+
+  drawImageNine(image, centerRect, dstRect){
+    argc(arguments, 3)
+    this.ƒ('drawImageNine', toImageSource(image), ...toRect(centerRect), ...toRect(dstRect))
+  }
+
+  drawImageLattice(image, xDivs, yDivs, dstRect, {flags, colors}={}){
+    argc(arguments, 4)
+    this.ƒ('drawImageLattice', toImageSource(image), Array.from(xDivs), Array.from(yDivs), ...toRect(dstRect),
+           flags ? Array.from(flags) : null, colors ? Array.from(colors) : null)
+  }
+
+  drawImageWarped(image, src, dst){
+    argc(arguments, 3)
+    let [srcCoords, srcCols] = isRect(src) ? [toRect(src), null] : toWarpGrid(src),
+        [dstCoords, dstCols] = toWarpGrid(dst)
+    this.ƒ('drawImageWarped', toImageSource(image), srcCoords, srcCols, dstCoords, dstCols)
+  }
+
+  drawAtlas(image, sprites, transforms, colors, blend='modulate'){
+    argc(arguments, 3)
+    sprites = Array.from(sprites, toRect).flat()
+
+    transforms = Array.from(transforms, xform => {
+      if (!xform || typeof xform!='object' || Array.isArray(xform)) return xform
+      // convert {x, y, scale, rotation, anchorX, anchorY} to an RSXform
+      let {x=0, y=0, scale=1, rotation=0, anchorX=0, anchorY=0} = xform,
+          scos = Math.cos(rotation) * scale,
+          ssin = Math.sin(rotation) * scale
+      return [scos, ssin, x - scos * anchorX + ssin * anchorY, y - ssin * anchorX - scos * anchorY]
+    }).flat()
+
+    this.ƒ('drawAtlas', toImageSource(image), sprites, transforms, colors ? Array.from(colors) : null, blend)
   }
 
   drawCanvas(image, ...coords){
@@ -282,15 +411,24 @@ class CanvasRenderingContext2D extends RustClass{
   set shadowOffsetX(x){       this.prop("shadowOffsetX", x) }
   get shadowOffsetY(){ return this.prop("shadowOffsetY") }
   set shadowOffsetY(y){       this.prop("shadowOffsetY", y) }
-  get filter(){        return this.prop('filter') }
-  set filter(str){            this.prop('filter', css.filter(str)) }
+  get shadows(){       return this.prop("shadows") }
+  set shadows(list){          this.prop("shadows", css.shadows(list)) }
+  get filter(){
+    let filter = this.prop('filter')
+    return filter===null ? this.ref('filter') : filter
+  }
+  set filter(filter){
+    let isObject = filter instanceof CanvasShader || filter instanceof CanvasFilter
+    this.prop('filter', isObject ? core(filter) : css.filter(filter))
+    this.ref('filter', isObject ? filter : null)
+  }
 
   [REPR](depth, options) {
     let props = [ "canvas", "currentTransform", "fillStyle", "strokeStyle", "font", "fontStretch", "fontVariant",
                   "direction", "textAlign", "textBaseline", "textWrap", "letterSpacing", "wordSpacing", "globalAlpha",
-                  "globalCompositeOperation", "imageSmoothingEnabled", "imageSmoothingQuality", "filter",
-                  "shadowBlur", "shadowColor", "shadowOffsetX", "shadowOffsetY", "lineCap", "lineDashOffset",
-                  "lineJoin", "lineWidth", "miterLimit" ]
+                  "globalCompositeOperation", "imageSmoothingEnabled", "imageSmoothingQuality", "imageResampling", "antialias", "filter",
+                  "shadowBlur", "shadowColor", "shadowOffsetX", "shadowOffsetY", "shadows", "lineCap", "lineDashOffset",
+                  "lineJoin", "lineWidth", "miterLimit", "strokeAlign" ]
     let info = {}
     if (depth > 0 ){
       for (var prop of props){
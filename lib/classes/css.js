@@ -9,6 +9,7 @@
 //    https://www.w3.org/TR/css-fonts-3/#font-size-prop
 
 var splitBy = require('string-split-by'),
+    {FilterLibrary} = require('./filter'),
     m, cache = {font:{}, variant:{}};
 
 const styleRE = /^(normal|italic|oblique)$/,
@@ -245,6 +246,7 @@ function parseCornerRadii(r){
 
 var plainFilterRE = /(blur|hue-rotate|brightness|contrast|grayscale|invert|opacity|saturate|sepia)\((.*?)\)/,
     shadowFilterRE = /drop-shadow\((.*)\)/,
+    urlFilterRE = /^url\(\s*(['"]?)#(.+?)\1\s*\)$/,
     percentValueRE = /^(\+|-)?\d+%$/,
     angleValueRE = /([\d\.]+)(deg|g?rad|turn)/;
 
@@ -263,6 +265,14 @@ function parseFilter(str){
         filters[kind] = [...dims, color]
         canonical.push(`${kind}(${lengths.join(' ')} ${color.replace(/ /g,'')})`)
       }
+    }else if (m = urlFilterRE.exec(spec)){
+      // references to svg filters registered with the FilterLibrary are passed along as json
+      let id = m[2],
+          primitives = FilterLibrary.lookup(id)
+      if (primitives){
+        filters[`url(#${id})`] = primitives
+        canonical.push(`url(#${id})`)
+      }
     }else if (m = plainFilterRE.exec(spec)){
       let [kind, arg] = m.slice(1)
       let val = kind=='blur' ? parseSize(arg)
@@ -280,6 +290,44 @@ function parseFilter(str){
        : null
 }
 
+// -- Box Shadows -------------------------------------------------------------------------
+//    https://developer.mozilla.org/en-US/docs/Web/CSS/box-shadow
+
+const shadowLengthRE = /^-?(\d+\.?\d*|\.\d+)(px|pt|pc|in|cm|mm|q)?$/
+
+function parseShadows(value){
+  let list = value===null || value===undefined ? []
+           : typeof value=='string' ? (value.trim()=='none' ? [] : splitBy(value.trim(), /\s*,\s*/))
+           : Array.isArray(value) ? value
+           : [value]
+
+  let shadows = list.map(spec => typeof spec=='string' ? parseShadow(spec) : normalizeShadow(spec))
+  return shadows.every(s => !!s) ? shadows : null
+}
+
+function parseShadow(str){
+  let inset = false, lengths = [], color = []
+  for (let token of splitBy(str.trim(), /\s+/)){
+    if (token=='inset') inset = true
+    else if (shadowLengthRE.test(token)) lengths.push(isNaN(token) ? parseSize(token) : parseFloat(token))
+    else color.push(token)
+  }
+  if (lengths.length < 2 || lengths.length > 4 || color.length > 1) return null
+
+  let [offsetX, offsetY, blur=0, spread=0] = lengths
+  return normalizeShadow({offsetX, offsetY, blur, spread, color:color[0], inset})
+}
+
+function normalizeShadow(spec){
+  if (!spec || typeof spec!='object') return null
+  let {offsetX=0, offsetY=0, blur=0, spread=0, color='black', inset=false} = spec,
+      dims = [offsetX, offsetY, blur, spread].map(n => +n)
+  if (!dims.every(isFinite) || dims[2] < 0) return null
+
+  let [x, y, b, s] = dims
+  return {offsetX:x, offsetY:y, blur:b, spread:s, color:String(color), inset:!!inset}
+}
+
 function parsePercentage(str){
   return percentValueRE.test(str.trim()) ? parseInt(str, 10) / 100
        : !isNaN(str) ? parseFloat(str)
@@ -388,6 +436,7 @@ module.exports = {
   stretch:parseStretch,
   decoration:parseTextDecoration,
   filter:parseFilter,
+  shadows:parseShadows,
 
   // path & context
   radii:parseCornerRadii,
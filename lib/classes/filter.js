@@ -0,0 +1,341 @@
+//
+// Filter graph definitions (from SVG markup for `url(#id)` references or from CanvasFilter nodes)
+//
+
+"use strict"
+
+const fs = require('fs')
+
+// -- Markup parsing --------------------------------------------------------------------
+//    a minimal xml reader that's just thorough enough for <filter> elements & their children
+
+const tagRE = /<(\/?)([\w:.-]+)((?:\s+[^\s=\/>]+(?:\s*=\s*(?:"[^"]*"|'[^']*'|[^\s>]+))?)*)\s*(\/?)>/g,
+      attrRE = /([^\s=\/>]+)(?:\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s>]+)))?/g,
+      entities = {amp:'&', lt:'<', gt:'>', quot:'"', apos:"'"},
+      decode = str => str.replace(/&(amp|lt|gt|quot|apos);/g, (_, name) => entities[name])
+
+function parseMarkup(svg){
+  let root = {name:'', attrs:{}, children:[]},
+      stack = [root],
+      markup = svg.replace(/<!--[\s\S]*?-->|<!\[CDATA\[[\s\S]*?\]\]>|<[?!][^>]*>/g, ''),
+      m
+
+  while (m = tagRE.exec(markup)){
+    let [, closing, tag, attrText, selfClosing] = m,
+        name = tag.replace(/^.*:/, '')
+
+    if (closing){
+      let idx = stack.findLastIndex(elt => elt.name == name)
+      if (idx > 0) stack.length = idx
+    }else{
+      let attrs = {}, a
+      while (a = attrRE.exec(attrText)){
+        let [, key, ...vals] = a
+        attrs[key.replace(/^.*:/, '')] = decode(vals.find(v => v !== undefined) ?? '')
+      }
+
+      // treat inline style declarations (e.g., `style="flood-color:red"`) like presentation attributes
+      for (let decl of (attrs.style || '').split(';')){
+        let [key, ...val] = decl.split(':')
+        if (key.trim() && val.length) attrs[key.trim()] = val.join(':').trim()
+      }
+
+      let elt = {name, attrs, children:[]}
+      stack.at(-1).children.push(elt)
+      if (!selfClosing) stack.push(elt)
+    }
+  }
+  return root
+}
+
+function findFilters(elt, found=[]){
+  for (let child of elt.children){
+    if (child.name == 'filter') found.push(child)
+    else findFilters(child, found)
+  }
+  return found
+}
+
+// -- Primitive conversion --------------------------------------------------------------
+//    attribute defaults & formulæ from: https://www.w3.org/TR/filter-effects-1/
+
+const num = (val, fallback=0) => isFinite(parseFloat(val)) ? parseFloat(val) : fallback,
+      nums = val => (val || '').trim().split(/[\s,]+/).filter(Boolean).map(Number).filter(isFinite),
+      pair = (val, fallback=0) => { let [x=fallback, y=x] = nums(val); return [x, y] }
+
+const IDENTITY = [
+  1, 0, 0, 0, 0,
+  0, 1, 0, 0, 0,
+  0, 0, 1, 0, 0,
+  0, 0, 0, 1, 0
+]
+
+function colorMatrix(type='matrix', values){
+  let [s=1] = nums(values),
+      cos = Math.cos(s * Math.PI / 180),
+      sin = Math.sin(s * Math.PI / 180)
+
+  switch (type){
+    case 'saturate': return [
+      0.213 + 0.787*s, 0.715 - 0.715*s, 0.072 - 0.072*s, 0, 0,
+      0.213 - 0.213*s, 0.715 + 0.285*s, 0.072 - 0.072*s, 0, 0,
+      0.213 - 0.213*s, 0.715 - 0.715*s, 0.072 + 0.928*s, 0, 0,
+      0,               0,               0,               1, 0
+    ]
+    case 'hueRotate':
+      if (!values) [cos, sin] = [1, 0]
+      return [
+        0.213 + cos*0.787 - sin*0.213, 0.715 - cos*0.715 - sin*0.715, 0.072 - cos*0.072 + sin*0.928, 0, 0,
+        0.213 - cos*0.213 + sin*0.143, 0.715 + cos*0.285 + sin*0.140, 0.072 - cos*0.072 - sin*0.283, 0, 0,
+        0.213 - cos*0.213 - sin*0.787, 0.715 - cos*0.715 + sin*0.715, 0.072 + cos*0.928 + sin*0.072, 0, 0,
+        0,                             0,                             0,                             1, 0
+      ]
+    case 'luminanceToAlpha': return [
+      0,      0,      0,      0, 0,
+      0,      0,      0,      0, 0,
+      0,      0,      0,      0, 0,
+      0.2125, 0.7154, 0.0721, 0, 0
+    ]
+    default:
+      let matrix = nums(values)
+      return matrix.length == 20 ? matrix : IDENTITY
+  }
+}
+
+function transferFunction(elt){
+  let {type='identity', tableValues, slope, intercept, amplitude, exponent, offset} = elt ? elt.attrs : {}
+  switch (type){
+    case 'table': case 'discrete': return {type, values:nums(tableValues)}
+    case 'linear': return {type, slope:num(slope, 1), intercept:num(intercept)}
+    case 'gamma': return {type, amplitude:num(amplitude, 1), exponent:num(exponent, 1), offset:num(offset)}
+    default: return {type:'identity'}
+  }
+}
+
+function lightSource(elt){
+  let light = elt.children.find(({name}) => /^fe(Distant|Point|Spot)Light$/.test(name))
+  if (!light) return {type:'distant', azimuth:0, elevation:0}
+
+  let {azimuth, elevation, x, y, z, pointsAtX, pointsAtY, pointsAtZ, specularExponent, limitingConeAngle} = light.attrs
+  switch (light.name){
+    case 'feDistantLight': return {type:'distant', azimuth:num(azimuth), elevation:num(elevation)}
+    case 'fePointLight': return {type:'point', x:num(x), y:num(y), z:num(z)}
+    case 'feSpotLight': return {
+      type:'spot', x:num(x), y:num(y), z:num(z),
+      pointsAt:[num(pointsAtX), num(pointsAtY), num(pointsAtZ)],
+      specularExponent:num(specularExponent, 1),
+      limitingConeAngle:isFinite(parseFloat(limitingConeAngle)) ? Math.abs(parseFloat(limitingConeAngle)) : null,
+    }
+  }
+}
+
+function toPrimitive({name, attrs, children}){
+  let effect
+  switch (name){
+    case 'feBlend':
+      let mode = attrs.mode || 'normal'
+      effect = {type:'blend', mode:mode=='normal' ? 'source-over' : mode}
+      break
+
+    case 'feColorMatrix':
+      effect = {type:'colorMatrix', values:colorMatrix(attrs.type, attrs.values)}
+      break
+
+    case 'feComponentTransfer':
+      let funcs = ['R', 'G', 'B', 'A'].map(c => children.findLast(({name}) => name == `feFunc${c}`))
+      effect = {type:'componentTransfer', funcs:funcs.map(transferFunction)}
+      break
+
+    case 'feComposite':
+      effect = {type:'composite', operator:attrs.operator || 'over', k:[attrs.k1, attrs.k2, attrs.k3, attrs.k4].map(k => num(k))}
+      break
+
+    case 'feConvolveMatrix':
+      let order = pair(attrs.order, 3).map(Math.floor),
+          kernel = nums(attrs.kernelMatrix),
+          sum = kernel.reduce((a, b) => a + b, 0)
+      effect = {
+        type:'convolveMatrix', order, kernel,
+        divisor:num(attrs.divisor, sum || 1),
+        bias:num(attrs.bias),
+        target:[num(attrs.targetX, Math.floor(order[0] / 2)), num(attrs.targetY, Math.floor(order[1] / 2))],
+        edgeMode:attrs.edgeMode || 'duplicate',
+        preserveAlpha:attrs.preserveAlpha == 'true',
+      }
+      break
+
+    case 'feDiffuseLighting':
+      effect = {
+        type:'diffuseLighting', light:lightSource({children}),
+        surfaceScale:num(attrs.surfaceScale, 1),
+        diffuseConstant:num(attrs.diffuseConstant, 1),
+        color:attrs['lighting-color'] || 'white',
+      }
+      break
+
+    case 'feSpecularLighting':
+      effect = {
+        type:'specularLighting', light:lightSource({children}),
+        surfaceScale:num(attrs.surfaceScale, 1),
+        specularConstant:num(attrs.specularConstant, 1),
+        specularExponent:num(attrs.specularExponent, 1),
+        color:attrs['lighting-color'] || 'white',
+      }
+      break
+
+    case 'feDisplacementMap':
+      effect = {
+        type:'displacementMap', scale:num(attrs.scale),
+        xChannel:attrs.xChannelSelector || 'A',
+        yChannel:attrs.yChannelSelector || 'A',
+      }
+      break
+
+    case 'feDropShadow':
+      effect = {
+        type:'dropShadow', dx:num(attrs.dx, 2), dy:num(attrs.dy, 2), stdDeviation:pair(attrs.stdDeviation, 2),
+        color:attrs['flood-color'] || 'black', opacity:num(attrs['flood-opacity'], 1),
+      }
+      break
+
+    case 'feFlood':
+      effect = {type:'flood', color:attrs['flood-color'] || 'black', opacity:num(attrs['flood-opacity'], 1)}
+      break
+
+    case 'feGaussianBlur':
+      effect = {type:'gaussianBlur', stdDeviation:pair(attrs.stdDeviation), edgeMode:attrs.edgeMode || 'none'}
+      break
+
+    case 'feMerge':
+      let inputs = children.filter(({name}) => name == 'feMergeNode').map(({attrs}) => attrs.in ?? null)
+      effect = {type:'merge', inputs}
+      break
+
+    case 'feMorphology':
+      effect = {type:'morphology', operator:attrs.operator || 'erode', radius:pair(attrs.radius)}
+      break
+
+    case 'feOffset':
+      effect = {type:'offset', dx:num(attrs.dx), dy:num(attrs.dy)}
+      break
+
+    case 'feTurbulence':
+      effect = {
+        type:'turbulence', baseFrequency:pair(attrs.baseFrequency),
+        numOctaves:Math.max(0, Math.floor(num(attrs.numOctaves, 1))),
+        seed:num(attrs.seed), fractalNoise:attrs.type == 'fractalNoise',
+      }
+      break
+
+    default:
+      return null // unsupported primitives (e.g., feImage & feTile) pass their input through unchanged
+  }
+
+  return withInputs(effect, attrs)
+}
+
+function withInputs(effect, {x, y, width, height, ...attrs}){
+  let subregion = [x, y, width, height].map(parseFloat)
+  return {
+    ...effect, in:attrs.in ?? null, in2:attrs.in2 ?? null, result:attrs.result ?? null,
+    subregion:subregion.slice(2).every(isFinite) ? subregion.map(n => isFinite(n) ? n : 0) : null,
+  }
+}
+
+// -- Programmatic definitions ----------------------------------------------------------
+//    CanvasFilter nodes are plain objects whose `name` is a primitive type (minus the `fe`
+//    prefix) and whose keys mirror the svg attributes (with light sources, transfer funcs,
+//    & merge inputs given as nested values rather than child elements)
+
+const attrNames = {floodColor:'flood-color', floodOpacity:'flood-opacity', lightingColor:'lighting-color'},
+      attrValue = val => Array.isArray(val) ? val.flat().join(' ') : String(val)
+
+function toAttrs(spec){
+  let attrs = {}
+  for (let [key, val] of Object.entries(spec)){
+    if (val != null) attrs[attrNames[key] ?? key] = attrValue(val)
+  }
+  return attrs
+}
+
+function fromSpec(spec, addSource){
+  if (!spec || typeof spec != 'object') throw new TypeError("Expected an object describing a filter primitive")
+  let {name, light, inputs, funcR, funcG, funcB, funcA, ...props} = spec,
+      children = []
+
+  switch (name){
+    case 'image':
+      // draws an image, canvas, or shader (which CanvasFilter passes in by index)
+      let {image, ...attrs} = props
+      return withInputs({type:'source', index:addSource(image)}, toAttrs(attrs))
+
+    case 'emboss':
+      let {strength=1, ...rest} = props,
+          k = num(strength, 1)
+      name = 'convolveMatrix'
+      props = {...rest, order:3, kernelMatrix:[-2*k, -k, 0, -k, 1, k, 0, k, 2*k], preserveAlpha:true}
+      break
+
+    case 'convolveMatrix':
+      // 2D kernels can imply their own `order`
+      let {kernelMatrix} = props
+      if (Array.isArray(kernelMatrix?.[0]) && props.order === undefined){
+        props.order = [kernelMatrix[0].length, kernelMatrix.length]
+      }
+      break
+
+    case 'componentTransfer':
+      Object.entries({R:funcR, G:funcG, B:funcB, A:funcA}).forEach(([c, func]) => {
+        if (func) children.push({name:`feFunc${c}`, attrs:toAttrs(func), children:[]})
+      })
+      break
+
+    case 'merge':
+      for (let input of inputs ?? []) children.push({name:'feMergeNode', attrs:input==null ? {} : {in:String(input)}, children:[]})
+      break
+
+    case 'diffuseLighting': case 'specularLighting':
+      if (light){
+        let {type='distant', pointsAt:[pointsAtX, pointsAtY, pointsAtZ]=[], ...attrs} = light,
+            kind = type[0].toUpperCase() + type.slice(1)
+        children.push({name:`fe${kind}Light`, attrs:toAttrs({pointsAtX, pointsAtY, pointsAtZ, ...attrs}), children:[]})
+      }
+      break
+  }
+
+  let primitive = typeof name == 'string' && toPrimitive({name:`fe${name[0].toUpperCase()}${name.slice(1)}`, attrs:toAttrs(props), children})
+  if (!primitive) throw new TypeError(`Unsupported filter primitive: ${JSON.stringify(name)}`)
+  return primitive
+}
+
+// -- Library ---------------------------------------------------------------------------
+
+class FilterLibrary{
+  #filters = new Map()
+
+  get ids(){ return [...this.#filters.keys()] }
+
+  has(id){ return this.#filters.has(id) }
+
+  use(svg){
+    if (Buffer.isBuffer(svg)) svg = svg.toString('utf8')
+    if (typeof svg != 'string') throw new TypeError("Expected SVG markup or the path to an SVG file")
+    if (!svg.trim().startsWith('<')) svg = fs.readFileSync(svg, 'utf8')
+
+    let ids = []
+    for (let {attrs, children} of findFilters(parseMarkup(svg))){
+      if (!attrs.id) continue
+      let primitives = children.map(toPrimitive).filter(Boolean)
+      this.#filters.set(attrs.id, JSON.stringify(primitives))
+      ids.push(attrs.id)
+    }
+    return ids
+  }
+
+  reset(){ this.#filters.clear() }
+
+  // used by css.filter() to resolve url(#id) references
+  lookup(id){ return this.#filters.get(id) }
+}
+
+module.exports = {FilterLibrary:new FilterLibrary(), fromSpec}
@@ -870,6 +870,9 @@ for (let propertyName of [
 // Helpers to reconcile Skia and DOMMatrix’s disagreement about row/col orientation
 //
 
+// flatten a list of [x, y] pairs or {x, y} objects into an array of coordinates
+const toCoords = pts => Array.from(pts ?? [], pt => pt && typeof pt=='object' && !Array.isArray(pt) ? [pt.x, pt.y] : pt).flat()
+
 function toSkMatrix() {
   if (arguments.length != 1 && arguments.length < 6){
      throw new TypeError("not enough arguments")
@@ -892,4 +895,24 @@ function fromSkMatrix(skMatrix){
   ])
 }
 
-module.exports = {DOMPoint, DOMMatrix, DOMRect, toSkMatrix, fromSkMatrix}
+function toSkM44() {
+  if (arguments.length != 1 && arguments.length < 6){
+     throw new TypeError("not enough arguments")
+  }
+  try {
+    const m = new DOMMatrix(...arguments);
+    return [m.m11, m.m21, m.m31, m.m41,
+            m.m12, m.m22, m.m32, m.m42,
+            m.m13, m.m23, m.m33, m.m43,
+            m.m14, m.m24, m.m34, m.m44];
+  }catch(e){
+    throw new TypeError(`Invalid transform matrix argument(s): `+e);
+  }
+}
+
+function fromSkM44(skM44){
+  // transpose from skia's row-major order to DOMMatrix's column-major order
+  return new DOMMatrix([0, 1, 2, 3].flatMap(col => [0, 1, 2, 3].map(row => skM44[row * 4 + col])))
+}
+
+module.exports = {DOMPoint, DOMMatrix, DOMRect, toSkMatrix, fromSkMatrix, toSkM44, fromSkM44, toCoords}
@@ -89,6 +89,7 @@ class Path2D extends RustClass{
   unwind(){               return Path2D.effect("unwind", this) }
   round(radius){          return Path2D.effect("round", this, ...arguments) }
   offset(dx, dy){         return Path2D.effect("offset", this, ...arguments) }
+  outline(width, profile, lineCap){ return Path2D.effect("outline", this, ...arguments) }
 
   transform(matrix){
     return Path2D.effect("transform", this, toSkMatrix.apply(null, arguments))
@@ -314,6 +314,39 @@ export interface ExportOptions extends RenderOptions {
 export interface SaveOptions extends ExportOptions {
   /** Image format to use (either as a file extension or a mime-type string) */
   format?: ExportFormat
+
+  /** Labels and option overrides for each file in an image sequence */
+  perPage?: (PageOptions | string | undefined)[] | ((page: CanvasRenderingContext2D, index: number) => PageOptions | string | undefined)
+}
+
+export interface PageOptions {
+  /** Value to substitute for `{label}` in the filename */
+  label?: string
+
+  /** Format for this page's file (overriding the shared `format`) */
+  format?: ExportFormat
+
+  /** Density for this page's file (overriding the shared `density`) */
+  density?: number
+
+  /** Background color for this page's file (overriding the shared `matte`) */
+  matte?: string
+}
+
+export interface StreamOptions extends SaveOptions {
+  /** Image format to use (required since there's no filename to infer it from) */
+  format: ExportFormat
+
+  /** Whether to call `end()` on the stream once the export is complete (defaults to true) */
+  end?: boolean
+}
+
+export interface MonitorOptions {
+  /** Cancels the export (rejecting its Promise with the signal's `reason`) once aborted */
+  signal?: AbortSignal
+
+  /** Called each time a page has finished rendering */
+  progress?: (done: number, total: number) => void
 }
 
 export interface EngineDetails {
@@ -325,6 +358,23 @@ export interface EngineDetails {
   error?: string
 }
 
+export interface CacheStats {
+  /** Number of pages with a cached bitmap */
+  entries: number
+  /** Total size of all cached bitmaps (in bytes) */
+  bytes: number
+  /** The current memory budget (or `Infinity` if unlimited) */
+  limit: number
+  /** Number of renders that were able to reuse a cached bitmap */
+  hits: number
+  /** Number of renders that had to start from scratch */
+  misses: number
+  /** Bitmaps residing in GPU memory */
+  gpu: { entries: number, bytes: number }
+  /** Bitmaps residing in main memory */
+  cpu: { entries: number, bytes: number }
+}
+
 export interface TextOptions{
   /** Amount of additional contrast to add when rendering text (defaults to 0) */
   textContrast?: number
@@ -336,6 +386,12 @@ export interface TextOptions{
 /** [Skia Canvas Docs](https://skia-canvas.org/api/canvas) */
 export class Canvas {
   static contexts: WeakMap<Canvas, readonly CanvasRenderingContext2D[]>
+  /** [Skia Canvas Docs](https://skia-canvas.org/api/canvas#canvascachestats) */
+  static readonly cacheStats: CacheStats
+  /** [Skia Canvas Docs](https://skia-canvas.org/api/canvas#canvascachelimit) */
+  static cacheLimit: number
+  /** [Skia Canvas Docs](https://skia-canvas.org/api/canvas#canvaspurgecache) */
+  static purgeCache(): void
   /**
    * Gets or sets the height of a canvas element on a document.
    *
@@ -369,11 +425,13 @@ export class Canvas {
   /** @deprecated Use {@link Canvas.toFile()} instead */
   saveAs(filename: string, options?: SaveOptions): Promise<void>
   /** [Skia Canvas Docs](https://skia-canvas.org/api/canvas#tofile): toFile() */
-  toFile(filename: string, options?: SaveOptions): Promise<void>
+  toFile(filename: string | URL, options?: SaveOptions & MonitorOptions): Promise<void>
+  /** PDFs are streamed page by page; other formats are encoded in full, then written in chunks */
+  toFile(destination: number | NodeJS.WritableStream, options: StreamOptions & MonitorOptions): Promise<void>
   /** [Skia Canvas Docs](https://skia-canvas.org/api/canvas#tobuffer) */
-  toBuffer(format: ExportFormat, options?: ExportOptions): Promise<Buffer>
+  toBuffer(format: ExportFormat, options?: ExportOptions & MonitorOptions): Promise<Buffer>
   /** [Skia Canvas Docs](https://skia-canvas.org/api/canvas#tourl) */
-  toURL(format: ExportFormat, options?: ExportOptions): Promise<string>
+  toURL(format: ExportFormat, options?: ExportOptions & MonitorOptions): Promise<string>
   /** [Skia Canvas Docs](https://skia-canvas.org/api/canvas#tosharp) */
   toSharp(options?: RenderOptions): Sharp
 
@@ -427,8 +485,26 @@ interface CanvasGradient {
    * [MDN Reference](https://developer.mozilla.org/docs/Web/API/CanvasGradient/addColorStop)
    */
   addColorStop(offset: number, color: string): void;
+
+  /** [Skia Canvas Docs](https://skia-canvas.org/api/context#createmeshgradient) */
+  addPatch(points: MeshPoints, colors: string[]): void
+
+  /** [Skia Canvas Docs](https://skia-canvas.org/api/context#canvasgradient-options) */
+  spread: GradientSpread
+  /** [Skia Canvas Docs](https://skia-canvas.org/api/context#canvasgradient-options) */
+  interpolation: GradientInterpolation | `in ${GradientInterpolation}`
+  /** [Skia Canvas Docs](https://skia-canvas.org/api/context#canvasgradient-options) */
+  dither: boolean
+
+  setTransform(transform: Matrix): void;
+  setTransform(a: number, b: number, c: number, d: number, e: number, f: number): void
 }
 
+export type GradientSpread = "pad" | "repeat" | "reflect" | "decal"
+type PolarColorSpace = "lch" | "oklch" | "hsl" | "hwb"
+export type GradientInterpolation = "srgb" | "srgb-linear" | "lab" | "oklab" | PolarColorSpace
+  | `${PolarColorSpace} ${"shorter" | "longer" | "increasing" | "decreasing"} hue`
+
 declare var CanvasGradient: {
   prototype: CanvasGradient;
   new(): CanvasGradient;
@@ -437,6 +513,60 @@ declare var CanvasGradient: {
 
 export class CanvasTexture {}
 
+/** [Skia Canvas Docs](https://skia-canvas.org/api/context#createshader) */
+export class CanvasShader {
+  constructor(source: string)
+  /** Names of the shader's uniforms mapped to their SkSL types */
+  readonly uniforms: {[name: string]: string}
+  setUniform(name: string, value: ShaderUniformValue): this
+  setTransform(transform: Matrix): void
+  setTransform(a: number, b: number, c: number, d: number, e: number, f: number): void
+}
+
+type ShaderUniformValue = number | number[] | Float32Array | string | CanvasShader | CanvasPattern | CanvasGradient | Image | ImageData | Canvas
+
+/** [Skia Canvas Docs](https://skia-canvas.org/api/filter-library#canvasfilter) */
+export class CanvasFilter {
+  constructor(primitives: CanvasFilterPrimitive | CanvasFilterPrimitive[])
+}
+
+type FilterInput = "SourceGraphic" | "SourceAlpha" | (string & {})
+type FilterLength = number | [number, number]
+type FilterLightSource =
+  | { type: "distant", azimuth?: number, elevation?: number }
+  | { type: "point", x?: number, y?: number, z?: number }
+  | { type: "spot", x?: number, y?: number, z?: number, pointsAt?: [number, number, number], specularExponent?: number, limitingConeAngle?: number }
+type FilterTransferFunction =
+  | { type: "identity" }
+  | { type: "table" | "discrete", tableValues: number[] }
+  | { type: "linear", slope?: number, intercept?: number }
+  | { type: "gamma", amplitude?: number, exponent?: number, offset?: number }
+
+type CanvasFilterPrimitive = {
+  in?: FilterInput, in2?: FilterInput, result?: string,
+  x?: number, y?: number, width?: number, height?: number,
+} & (
+  | { name: "blend", mode?: "normal" | GlobalCompositeOperation }
+  | { name: "colorMatrix", type?: "matrix", values: number[] }
+  | { name: "colorMatrix", type: "saturate" | "hueRotate", values?: number }
+  | { name: "colorMatrix", type: "luminanceToAlpha" }
+  | { name: "componentTransfer", funcR?: FilterTransferFunction, funcG?: FilterTransferFunction, funcB?: FilterTransferFunction, funcA?: FilterTransferFunction }
+  | { name: "composite", operator?: "over" | "in" | "out" | "atop" | "xor" | "lighter" | "arithmetic", k1?: number, k2?: number, k3?: number, k4?: number }
+  | { name: "convolveMatrix", kernelMatrix: number[] | number[][], order?: FilterLength, divisor?: number, bias?: number, targetX?: number, targetY?: number, edgeMode?: "duplicate" | "wrap" | "none", preserveAlpha?: boolean }
+  | { name: "diffuseLighting", light?: FilterLightSource, surfaceScale?: number, diffuseConstant?: number, lightingColor?: string }
+  | { name: "displacementMap", scale?: number, xChannelSelector?: "R" | "G" | "B" | "A", yChannelSelector?: "R" | "G" | "B" | "A" }
+  | { name: "dropShadow", dx?: number, dy?: number, stdDeviation?: FilterLength, floodColor?: string, floodOpacity?: number }
+  | { name: "emboss", strength?: number }
+  | { name: "flood", floodColor?: string, floodOpacity?: number }
+  | { name: "gaussianBlur", stdDeviation?: FilterLength, edgeMode?: "duplicate" | "wrap" | "none" }
+  | { name: "image", image: Image | ImageData | Canvas | CanvasGradient | CanvasPattern | CanvasShader }
+  | { name: "merge", inputs: (FilterInput | null)[] }
+  | { name: "morphology", operator?: "erode" | "dilate", radius?: FilterLength }
+  | { name: "offset", dx?: number, dy?: number }
+  | { name: "specularLighting", light?: FilterLightSource, surfaceScale?: number, specularConstant?: number, specularExponent?: number, lightingColor?: string }
+  | { name: "turbulence", type?: "turbulence" | "fractalNoise", baseFrequency?: FilterLength, numOctaves?: number, seed?: number }
+)
+
 
 //
 // Context
@@ -531,9 +661,9 @@ interface CanvasDrawPath {
 
 interface CanvasFillStrokeStyles {
   /** [MDN Reference](https://developer.mozilla.org/docs/Web/API/CanvasRenderingContext2D/fillStyle) */
-  fillStyle: string | CanvasGradient | CanvasPattern | CanvasTexture;
+  fillStyle: string | CanvasGradient | CanvasPattern | CanvasTexture | CanvasShader;
   /** [MDN Reference](https://developer.mozilla.org/docs/Web/API/CanvasRenderingContext2D/strokeStyle) */
-  strokeStyle: string | CanvasGradient | CanvasPattern | CanvasTexture;
+  strokeStyle: string | CanvasGradient | CanvasPattern | CanvasTexture | CanvasShader;
   /** [MDN Reference](https://developer.mozilla.org/docs/Web/API/CanvasRenderingContext2D/createConicGradient) */
   createConicGradient(startAngle: number, x: number, y: number): CanvasGradient;
   /** [MDN Reference](https://developer.mozilla.org/docs/Web/API/CanvasRenderingContext2D/createLinearGradient) */
@@ -545,11 +675,20 @@ interface CanvasFillStrokeStyles {
 
   /** [Skia Canvas Docs](https://skia-canvas.org/api/context#createtexture) */
   createTexture(spacing: Offset, options?: CreateTextureOptions): CanvasTexture
+
+  /** [Skia Canvas Docs](https://skia-canvas.org/api/context#createmeshgradient) */
+  createMeshGradient(): CanvasGradient
+
+  /** [Skia Canvas Docs](https://skia-canvas.org/api/context#createcssgradient) */
+  createCSSGradient(css: string, x?: number, y?: number, width?: number, height?: number): CanvasGradient
+
+  /** [Skia Canvas Docs](https://skia-canvas.org/api/context#createshader) */
+  createShader(source: string): CanvasShader
 }
 
 interface CanvasFilters {
   /** [MDN Reference](https://developer.mozilla.org/docs/Web/API/CanvasRenderingContext2D/filter) */
-  filter: string;
+  filter: string | CanvasShader | CanvasFilter;
 }
 
 interface CanvasImageData {
@@ -711,6 +850,92 @@ interface CanvasTransform {
  * - [MDN Reference](https://developer.mozilla.org/docs/Web/API/CanvasRenderingContext2D)
  * - [Skia Canvas Docs](https://skia-canvas.org/api/context)
  */
+export interface CanvasLayerOptions {
+  /** CSS filter string applied to the layer as a whole (defaults to the context's current `filter`) */
+  filter?: string | CanvasShader | CanvasFilter
+
+  /** Opacity used when compositing the layer (defaults to the context's current `globalAlpha`) */
+  alpha?: number
+
+  /** Blend mode used when compositing the layer (defaults to the context's current `globalCompositeOperation`) */
+  compositeOperation?: GlobalCompositeOperation
+}
+
+export interface CanvasMaskOptions {
+  /** Whether the mask's opacity or its brightness determines how much of the drawing shows through (defaults to "alpha") */
+  mode?: "alpha" | "luminance"
+
+  /** Transform applied to the mask source (in addition to the context's current transform) */
+  transform?: Matrix
+}
+
+export interface CanvasShadow {
+  /** Horizontal & vertical displacement in pixels (unaffected by the current transform) */
+  offsetX?: number
+  offsetY?: number
+
+  /** Blur radius in pixels (defaults to 0) */
+  blur?: number
+
+  /** Distance to grow (or, if negative, shrink) the shadow's shape by before blurring (defaults to 0) */
+  spread?: number
+
+  /** CSS color string (defaults to "black") */
+  color?: string
+
+  /** Cast the shadow inward from the shape's edges rather than outward behind it */
+  inset?: boolean
+}
+
+type MeshPoints = number[] | Float32Array | [x: number, y: number][] | {x: number, y: number}[]
+
+/** Four corners (clockwise from the upper-left) or an array of rows of points */
+export type WarpGrid = MeshPoints | ([x: number, y: number][] | {x: number, y: number}[])[]
+
+export interface CanvasMeshOptions {
+  /** CSS colors for each vertex (interpolated across each triangle's face) */
+  colors?: string[]
+
+  /** Coordinates in the `fillStyle` pattern or gradient to map to each vertex */
+  texCoords?: MeshPoints
+
+  /** Vertex indices defining the triangles (if omitted, vertices are used in the order given) */
+  indices?: number[] | Uint16Array
+
+  /** How vertices are assembled into triangles (defaults to "triangles") */
+  mode?: "triangles" | "strip" | "fan"
+}
+
+type RectLike = [x: number, y: number, width: number, height: number] | {x: number, y: number, width: number, height: number}
+
+export type AtlasSprite = RectLike
+
+export interface CanvasLatticeOptions {
+  /** How each cell (in row-major order) should be drawn (defaults to "default" for every cell, or "color" for cells with a `colors` entry) */
+  flags?: ("default" | "transparent" | "color")[]
+
+  /** Solid colors to draw in place of the image for cells flagged as "color" */
+  colors?: (string | null)[]
+}
+
+export type AtlasTransform = [scos: number, ssin: number, tx: number, ty: number] | {
+  x?: number
+  y?: number
+  scale?: number
+  /** in radians */
+  rotation?: number
+  anchorX?: number
+  anchorY?: number
+}
+
+export interface CanvasPatchOptions {
+  /** CSS colors for each of the patch's corners (clockwise from the top-left) */
+  colors?: string[]
+
+  /** Coordinates in the `fillStyle` pattern or gradient to map to each corner */
+  texCoords?: MeshPoints
+}
+
 export interface CanvasRenderingContext2D extends CanvasCompositing, CanvasDrawImage, CanvasDrawPath, CanvasFillStrokeStyles, CanvasFilters, CanvasImageData, CanvasImageSmoothing, CanvasPath, CanvasPathDrawingStyles, CanvasRect, CanvasShadowStyles, CanvasState, CanvasText, CanvasTextDrawingStyles, CanvasTransform {
   /** [MDN Reference](https://developer.mozilla.org/docs/Web/API/CanvasRenderingContext2D/canvas) */
   readonly canvas: Canvas
@@ -720,12 +945,51 @@ export interface CanvasRenderingContext2D extends CanvasCompositing, CanvasDrawI
   textDecoration: string
   lineDashMarker: Path2D | null
   lineDashFit: "move" | "turn" | "follow"
+  lineWidthProfile: WidthProfile | null
+  strokeAlign: "center" | "inside" | "outside"
+  antialias: boolean | "auto"
+  imageResampling: "auto" | "nearest" | "linear" | "mipmap" | "mitchell" | "catmull-rom" | "lanczos3"
+  get shadows(): Required<CanvasShadow>[]
+  set shadows(list: string | (string | CanvasShadow)[] | null)
+
+  // grouped compositing (WHATWG canvas layers proposal)
+  beginLayer(options?: CanvasLayerOptions): void
+  endLayer(): void
+
+  // filter previously drawn content within a region (or the whole clip)
+  applyFilter(filter: string | CanvasShader | CanvasFilter, region?: Path2D | DOMRectInit): void
+  applyFilter(filter: string | CanvasShader | CanvasFilter, x: number, y: number, width: number, height: number): void
+
+  // soft masking (until the next restore())
+  mask(source: CanvasDrawable, options?: CanvasMaskOptions): void
+
+  // inverse clipping & clip queries
+  clipOut(fillRule?: CanvasFillRule): void
+  clipOut(path: Path2D, fillRule?: CanvasFillRule): void
+  resetClip(): void
+  getClipBounds(): Path2DBounds
+  isPointInClip(x: number, y: number): boolean
 
   // skia/chrome beziers & convenience methods
   get currentTransform(): DOMMatrix
   set currentTransform(matrix: Matrix)
   createProjection(quad: QuadOrRect, basis?: QuadOrRect): DOMMatrix
+
+  // 3D transforms (projected onto the canvas from the z=0 plane)
+  setTransform3D(transform: Matrix): void
+  transform3D(transform: Matrix): void
+  translate3d(x: number, y: number, z: number): void
+  scale3d(x: number, y: number, z: number): void
+  rotate3d(x: number, y: number, z: number, angle: number): void
+  perspective(distance: number): void
+
   conicCurveTo(cpx: number, cpy: number, x: number, y: number, weight: number): void
+  drawMesh(positions: MeshPoints, options?: CanvasMeshOptions): void
+  drawPatch(points: MeshPoints, options?: CanvasPatchOptions): void
+  drawImageNine(image: CanvasDrawable, centerRect: RectLike, dstRect: RectLike): void
+  drawImageLattice(image: CanvasDrawable, xDivs: number[], yDivs: number[], dstRect: RectLike, options?: CanvasLatticeOptions): void
+  drawImageWarped(image: CanvasDrawable, src: RectLike | WarpGrid | null, dst: WarpGrid): void
+  drawAtlas(image: CanvasDrawable, sprites: AtlasSprite[], transforms: AtlasTransform[], colors?: string[] | null, blend?: GlobalCompositeOperation | "modulate"): void
   // getContextAttributes(): CanvasRenderingContext2DSettings;
 
   // add optional maxWidth to work in conjunction with textWrap
@@ -748,6 +1012,9 @@ export interface Path2DBounds {
 
 export type Path2DEdge = [verb: string, ...args: number[]]
 
+/** Per-point pressure values or [t, width] stops, multiplied by the base line width */
+export type WidthProfile = number[] | [t: number, width: number][]
+
 /**
  * This Canvas 2D API interface is used to declare a path that can then be used on a CanvasRenderingContext2D object. The path methods of the CanvasRenderingContext2D interface are also present on this interface, which gives you the convenience of being able to retain and replay your path whenever desired.
  *
@@ -783,6 +1050,7 @@ interface Path2D extends CanvasPath {
 
   jitter(segmentLength: number, amount: number, seed?: number): Path2D
   offset(dx: number, dy: number): Path2D
+  outline(width: number, profile?: WidthProfile | null, lineCap?: CanvasLineCap): Path2D
   points(step?: number): readonly [x: number, y: number][]
   round(radius: number): Path2D
   simplify(rule?: "nonzero" | "evenodd"): Path2D
@@ -929,6 +1197,15 @@ interface FontLibrary {
 
 export const FontLibrary: FontLibrary
 
+interface FilterLibrary {
+  ids: readonly string[]
+  has(id: string): boolean
+  use(svg: string | Buffer): string[]
+  reset(): void
+}
+
+export const FilterLibrary: FilterLibrary
+
 //
 // Window & App
 //
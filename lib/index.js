@@ -4,19 +4,20 @@
 
 "use strict"
 
-const {Canvas, CanvasGradient, CanvasPattern, CanvasTexture} = require('./classes/canvas'),
+const {Canvas, CanvasGradient, CanvasPattern, CanvasTexture, CanvasShader, CanvasFilter} = require('./classes/canvas'),
       {Image, ImageData, loadImage, loadImageData} = require('./classes/imagery'),
       {DOMPoint, DOMMatrix, DOMRect} = require('./classes/geometry'),
       {TextMetrics, FontLibrary} = require('./classes/typography'),
+      {FilterLibrary} = require('./classes/filter'),
       {CanvasRenderingContext2D} = require('./classes/context'),
       {App, Window} = require('./classes/gui'),
       {Path2D} = require('./classes/path')
 
 module.exports = {
-  Canvas, CanvasGradient, CanvasPattern, CanvasTexture,
+  Canvas, CanvasGradient, CanvasPattern, CanvasTexture, CanvasShader, CanvasFilter,
   Image, ImageData, loadImage, loadImageData,
   Path2D, DOMPoint, DOMMatrix, DOMRect,
-  FontLibrary, TextMetrics,
+  FontLibrary, FilterLibrary, TextMetrics,
   CanvasRenderingContext2D,
   App, Window,
 }
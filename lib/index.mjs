@@ -5,20 +5,20 @@
 import skia_canvas from './index.js'
 
 const {
-  Canvas, CanvasGradient, CanvasPattern, CanvasTexture,
+  Canvas, CanvasGradient, CanvasPattern, CanvasTexture, CanvasShader, CanvasFilter,
   Image, ImageData, loadImage, loadImageData,
   Path2D, DOMPoint, DOMMatrix, DOMRect,
-  FontLibrary, TextMetrics,
+  FontLibrary, FilterLibrary, TextMetrics,
   CanvasRenderingContext2D,
   App, Window,
 } = skia_canvas
 
 export {
   skia_canvas as default,
-  Canvas, CanvasGradient, CanvasPattern, CanvasTexture,
+  Canvas, CanvasGradient, CanvasPattern, CanvasTexture, CanvasShader, CanvasFilter,
   Image, ImageData, loadImage, loadImageData,
   Path2D, DOMPoint, DOMMatrix, DOMRect,
-  FontLibrary, TextMetrics,
+  FontLibrary, FilterLibrary, TextMetrics,
   CanvasRenderingContext2D,
   App, Window,
 }
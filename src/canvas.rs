@@ -1,10 +1,12 @@
 #![allow(non_snake_case)]
 use std::cell::RefCell;
+use std::io::{BufWriter, Write};
 use neon::prelude::*;
 use skia_safe::SurfaceProps;
 use serde_json::json;
 use crate::utils::*;
-use crate::context::page::{ExportOptions, pages_arg};
+use crate::context::page::{ExportOptions, ExportTask, BoxedExportTask, ChunkWriter, PageCache, pages_arg, page_options_arg, export_task_arg};
+use crate::filter::purge_lanczos_cache;
 use crate::gpu;
 
 pub type BoxedCanvas = JsBox<RefCell<Canvas>>;
@@ -121,10 +123,58 @@ pub fn get_engine_status(mut cx: FunctionContext) -> JsResult<JsString> {
   Ok(cx.string(details.to_string()))
 }
 
+// -- page cache (shared by all canvases) ---------------------------------------------------------
+
+pub fn get_cacheStats(mut cx: FunctionContext) -> JsResult<JsString> {
+  Ok(cx.string(PageCache::stats().to_string()))
+}
+
+pub fn get_cacheLimit(mut cx: FunctionContext) -> JsResult<JsNumber> {
+  let limit = match PageCache::limit(){
+    usize::MAX => f64::INFINITY,
+    bytes => bytes as f64
+  };
+  Ok(cx.number(limit))
+}
+
+pub fn set_cacheLimit(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+  let limit = match opt_double_arg(&mut cx, 1){
+    Some(bytes) if bytes.is_finite() => {
+      if bytes < 0.0{
+        return cx.throw_range_error("Expected a non-negative number of bytes for `cacheLimit`")
+      }
+      bytes as usize
+    },
+    _ => usize::MAX // null, undefined, or Infinity mean no limit
+  };
+  PageCache::set_limit(limit);
+  Ok(cx.undefined())
+}
+
+pub fn purgeCache(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+  PageCache::purge();
+  purge_lanczos_cache();
+  Ok(cx.undefined())
+}
+
+// -- export monitoring ---------------------------------------------------------------------------
+
+pub fn exportTask(mut cx: FunctionContext) -> JsResult<BoxedExportTask> {
+  Ok(cx.boxed(ExportTask::default()))
+}
+
+pub fn cancelExport(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+  let task = cx.argument::<BoxedExportTask>(1)?;
+  task.cancel();
+  Ok(cx.undefined())
+}
+
 pub fn toBuffer(mut cx: FunctionContext) -> JsResult<JsPromise> {
   let this = cx.argument::<BoxedCanvas>(0)?;
   let options = export_options_arg(&mut cx, 2)?;
   let mut pages = pages_arg(&mut cx, 1, &options, &this)?;
+  let is_pdf = options.format=="pdf" && pages.len() > 1;
+  let task = export_task_arg(&mut cx, 3, if is_pdf{ pages.len() }else{ 1 })?;
 
   // ensure cached bitmaps are sendable to other thread
   pages.materialize(&this.borrow_mut().engine(), &options);
@@ -133,10 +183,10 @@ pub fn toBuffer(mut cx: FunctionContext) -> JsResult<JsPromise> {
   let (deferred, promise) = cx.promise();
   rayon::spawn_fifo(move || {
     let result = {
-      if options.format=="pdf" && pages.len() > 1 {
-        pages.as_pdf(options)
+      if is_pdf {
+        pages.as_pdf(options, &task)
       }else{
-        pages.first().encoded_as(options, pages.engine)
+        pages.as_image(options, &task)
       }
     };
 
@@ -150,16 +200,54 @@ pub fn toBuffer(mut cx: FunctionContext) -> JsResult<JsPromise> {
   Ok(promise)
 }
 
+pub fn toStream(mut cx: FunctionContext) -> JsResult<JsPromise> {
+  let this = cx.argument::<BoxedCanvas>(0)?;
+  let options = export_options_arg(&mut cx, 2)?;
+  let sink = cx.argument::<JsFunction>(3)?.root(&mut cx);
+  let mut pages = pages_arg(&mut cx, 1, &options, &this)?;
+  let is_pdf = options.format=="pdf";
+  let task = export_task_arg(&mut cx, 4, if is_pdf{ pages.len() }else{ 1 })?;
+
+  // ensure cached bitmaps are sendable to other thread
+  pages.materialize(&this.borrow_mut().engine(), &options);
+
+  let channel = cx.channel();
+  let (deferred, promise) = cx.promise();
+  let mut writer = BufWriter::with_capacity(1 << 16, ChunkWriter::new(channel.clone(), sink));
+  rayon::spawn_fifo(move || {
+    let result = {
+      if is_pdf {
+        pages.write_pdf_to(&mut writer, options, &task)
+      }else{
+        // other formats are encoded in full before being passed along in chunks
+        pages.as_image(options, &task).and_then(|data|
+          writer.write_all(&data).map_err(|why| why.to_string())
+        )
+      }
+    }.and_then(|_|
+      writer.flush().map_err(|why| why.to_string())
+    );
+
+    deferred.settle_with(&channel, move |mut cx| match result{
+      Err(msg) => cx.throw_error(msg),
+      _ => Ok(cx.undefined())
+    });
+  });
+
+  Ok(promise)
+}
+
 pub fn toBufferSync(mut cx: FunctionContext) -> JsResult<JsValue> {
   let this = cx.argument::<BoxedCanvas>(0)?;
   let options = export_options_arg(&mut cx, 2)?;
   let pages = pages_arg(&mut cx, 1, &options, &this)?;
 
+  let task = ExportTask::default();
   let encoded = {
     if options.format=="pdf" && pages.len() > 1 {
-      pages.as_pdf(options)
+      pages.as_pdf(options, &task)
     }else{
-      pages.first().encoded_as(options, pages.engine)
+      pages.as_image(options, &task)
     }
   };
 
@@ -179,20 +267,26 @@ pub fn save(mut cx: FunctionContext) -> JsResult<JsPromise> {
   let padding = opt_float_arg(&mut cx, 3).unwrap_or(-1.0);
   let options = export_options_arg(&mut cx, 4)?;
   let mut pages = pages_arg(&mut cx, 1, &options, &this)?;
+  let total = if sequence || options.format == "pdf" { pages.len() }else{ 1 };
+  let task = export_task_arg(&mut cx, 5, total)?;
+  let per_page = page_options_arg(&mut cx, 4, &options, pages.len())?;
 
   // ensure cached bitmaps are sendable to other thread
-  pages.materialize(&this.borrow_mut().engine(), &options);
+  match sequence{
+    true => pages.materialize_each(&this.borrow_mut().engine(), &per_page),
+    false => pages.materialize(&this.borrow_mut().engine(), &options)
+  }
 
   let channel = cx.channel();
   let (deferred, promise) = cx.promise();
   rayon::spawn_fifo(move || {
     let result = {
       if sequence {
-        pages.write_sequence(&name_pattern, padding, options)
+        pages.write_sequence(&name_pattern, padding, per_page, &task)
       } else if options.format == "pdf" {
-        pages.write_pdf(&name_pattern, options)
+        pages.write_pdf(&name_pattern, options, &task)
       } else {
-        pages.write_image(&name_pattern, options)
+        pages.write_image(&name_pattern, options, &task)
       }
     };
 
@@ -212,14 +306,16 @@ pub fn saveSync(mut cx: FunctionContext) -> JsResult<JsUndefined> {
   let padding = opt_float_arg(&mut cx, 3).unwrap_or(-1.0);
   let options = export_options_arg(&mut cx, 4)?;
   let pages = pages_arg(&mut cx, 1, &options, &this)?;
+  let per_page = page_options_arg(&mut cx, 4, &options, pages.len())?;
 
+  let task = ExportTask::default();
   let result = {
     if sequence {
-      pages.write_sequence(&name_pattern, padding, options)
+      pages.write_sequence(&name_pattern, padding, per_page, &task)
     } else if options.format == "pdf" {
-      pages.write_pdf(&name_pattern, options)
+      pages.write_pdf(&name_pattern, options, &task)
     } else {
-      pages.write_image(&name_pattern, options)
+      pages.write_image(&name_pattern, options, &task)
     }
   };
 
@@ -2,16 +2,17 @@
 use std::f32::consts::PI;
 use std::cell::RefCell;
 use neon::prelude::*;
-use skia_safe::{Matrix, PaintStyle, Point, RRect, Rect, Size, Path, PathDirection};
+use skia_safe::{Matrix, M44, V3, PaintStyle, Point, RRect, Rect, Size, Path, PathDirection, RSXform, BlendMode, Color, Contains, ClipOp};
+use skia_safe::canvas::lattice::RectType;
 use skia_safe::path::AddPathMode::{Extend};
 use skia_safe::textlayout::{TextDirection};
 use skia_safe::PaintStyle::{Fill, Stroke};
 
-use super::{Context2D, BoxedContext2D, Dye, page::ExportOptions};
+use super::{Context2D, BoxedContext2D, Dye, Antialias, page::ExportOptions};
 use crate::canvas::BoxedCanvas;
 use crate::path::Path2D;
 use crate::image::{BoxedImage, Content};
-use crate::filter::Filter;
+use crate::gradient::patch_args;
 use crate::typography::{
   font_arg, decoration_arg, font_features, from_width, to_width,
   from_text_align, to_text_align, from_text_baseline, to_text_baseline,
@@ -89,11 +90,56 @@ pub fn restore(mut cx: FunctionContext) -> JsResult<JsUndefined> {
   Ok(cx.undefined())
 }
 
+pub fn beginLayer(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+  let this = cx.argument::<BoxedContext2D>(0)?;
+  let mut this = this.borrow_mut();
+  let filter = opt_filter_arg(&mut cx, 1)?;
+  let alpha = opt_float_arg(&mut cx, 2).filter(|num| (0.0..=1.0).contains(num));
+  let blend = opt_string_arg(&mut cx, 3).and_then(|name| to_blend_mode(&name));
+
+  this.begin_layer(filter, alpha, blend);
+  Ok(cx.undefined())
+}
+
+pub fn endLayer(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+  let this = cx.argument::<BoxedContext2D>(0)?;
+  let mut this = this.borrow_mut();
+  match this.end_layer(){
+    true => Ok(cx.undefined()),
+    false => cx.throw_error("endLayer() called without a matching beginLayer()")
+  }
+}
+
+pub fn applyFilter(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+  let this = cx.argument::<BoxedContext2D>(0)?;
+  let mut this = this.borrow_mut();
+  let filter = match opt_filter_arg(&mut cx, 1)?{
+    Some(filter) => filter,
+    None => return cx.throw_type_error("Expected a filter string or CanvasShader for 1st arg")
+  };
+
+  // the region can be a Path2D, a rectangle's dimensions, or omitted to filter everything within the clip
+  let region = match opt_skpath_arg(&mut cx, 2){
+    Some(path) => Some(path),
+    None if cx.len() > 3 => {
+      let nums = float_args_or_bail_at(&mut cx, 2, &["x", "y", "width", "height"])?;
+      match nums.as_slice(){
+        [x, y, w, h] => Some(Path::rect(Rect::from_xywh(*x, *y, *w, *h), None)),
+        _ => None
+      }
+    },
+    None => None
+  };
+
+  this.apply_filter(filter, region);
+  Ok(cx.undefined())
+}
+
 pub fn transform(mut cx: FunctionContext) -> JsResult<JsUndefined> {
   let this = cx.argument::<BoxedContext2D>(0)?;
   let mut this = this.borrow_mut();
 
-  if let Some(matrix) = opt_matrix_arg(&mut cx, 1) {
+  if let Some(matrix) = opt_m44_arg(&mut cx, 1) {
     this.with_matrix(|ctm| ctm.pre_concat(&matrix) );
   }
   Ok(cx.undefined())
@@ -105,7 +151,7 @@ pub fn translate(mut cx: FunctionContext) -> JsResult<JsUndefined> {
 
   let xy = float_args_or_bail(&mut cx, &["x", "y"])?;
   if let [dx, dy] = xy.as_slice(){
-    this.with_matrix(|ctm| ctm.pre_translate((*dx, *dy)) );
+    this.with_matrix(|ctm| ctm.pre_translate(*dx, *dy, None) );
   }
   Ok(cx.undefined())
 }
@@ -116,7 +162,7 @@ pub fn scale(mut cx: FunctionContext) -> JsResult<JsUndefined> {
 
   let xy = float_args_or_bail(&mut cx, &["x", "y"])?;
   if let [m11, m22] = xy.as_slice(){
-    this.with_matrix(|ctm| ctm.pre_scale((*m11, *m22), None) );
+    this.with_matrix(|ctm| ctm.pre_scale(*m11, *m22) );
   }
   Ok(cx.undefined())
 }
@@ -127,7 +173,7 @@ pub fn rotate(mut cx: FunctionContext) -> JsResult<JsUndefined> {
 
   let radians = float_arg_or_bail(&mut cx, 1, "angle")?;
   let degrees = radians / PI * 180.0;
-  this.with_matrix(|ctm| ctm.pre_rotate(degrees, None) );
+  this.with_matrix(|ctm| ctm.pre_concat(&Matrix::rotate_deg(degrees).into()) );
   Ok(cx.undefined())
 }
 
@@ -135,7 +181,58 @@ pub fn resetTransform(mut cx: FunctionContext) -> JsResult<JsUndefined> {
   let this = cx.argument::<BoxedContext2D>(0)?;
   let mut this = this.borrow_mut();
 
-  this.with_matrix(|ctm| ctm.reset() );
+  this.with_matrix(|ctm| ctm.set_identity() );
+  Ok(cx.undefined())
+}
+
+pub fn translate3d(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+  let this = cx.argument::<BoxedContext2D>(0)?;
+  let mut this = this.borrow_mut();
+
+  let xyz = float_args_or_bail(&mut cx, &["x", "y", "z"])?;
+  if let [dx, dy, dz] = xyz.as_slice(){
+    this.with_matrix(|ctm| ctm.pre_translate(*dx, *dy, *dz) );
+  }
+  Ok(cx.undefined())
+}
+
+pub fn scale3d(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+  let this = cx.argument::<BoxedContext2D>(0)?;
+  let mut this = this.borrow_mut();
+
+  let xyz = float_args_or_bail(&mut cx, &["x", "y", "z"])?;
+  if let [m11, m22, m33] = xyz.as_slice(){
+    this.with_matrix(|ctm| ctm.pre_scale_xyz(*m11, *m22, *m33) );
+  }
+  Ok(cx.undefined())
+}
+
+pub fn rotate3d(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+  let this = cx.argument::<BoxedContext2D>(0)?;
+  let mut this = this.borrow_mut();
+
+  let nums = float_args_or_bail(&mut cx, &["x", "y", "z", "angle"])?;
+  if let [x, y, z, radians] = nums.as_slice(){
+    // like CSS's rotate3d(), a zero-length axis leaves the transform unchanged
+    let axis = V3::new(*x, *y, *z);
+    if axis.length() > 0.0 {
+      this.with_matrix(|ctm| ctm.pre_concat(&M44::rotate(axis, *radians)) );
+    }
+  }
+  Ok(cx.undefined())
+}
+
+pub fn perspective(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+  let this = cx.argument::<BoxedContext2D>(0)?;
+  let mut this = this.borrow_mut();
+
+  // distance from the viewer to the z=0 plane (with the vanishing point at the current origin)
+  let distance = float_arg_or_bail(&mut cx, 1, "distance")?;
+  if distance > 0.0 {
+    let mut matrix = M44::new_identity();
+    matrix.set_rc(3, 2, -1.0 / distance);
+    this.with_matrix(|ctm| ctm.pre_concat(&matrix) );
+  }
   Ok(cx.undefined())
 }
 
@@ -176,24 +273,21 @@ pub fn createProjection(mut cx: FunctionContext) -> JsResult<JsArray> {
 
 // -- ctm property ----------------------------------------------------------------------
 
-pub fn get_currentTransform(mut cx: FunctionContext) -> JsResult<JsArray> {
+pub fn get_currentTransform(mut cx: FunctionContext) -> JsResult<JsValue> {
   let this = cx.argument::<BoxedContext2D>(0)?;
   let this = this.borrow();
 
-  let array = JsArray::new(&mut cx, 9);
-  for i in 0..9 {
-    let num = cx.number(this.state.matrix[i as usize]);
-    array.set(&mut cx, i as u32, num)?;
-  }
-  Ok(array)
+  let mut terms = [0.0; 16];
+  this.state.matrix_3d.get_row_major(&mut terms);
+  floats_to_array(&mut cx, &terms)
 }
 
 pub fn set_currentTransform(mut cx: FunctionContext) -> JsResult<JsUndefined> {
   let this = cx.argument::<BoxedContext2D>(0)?;
   let mut this = this.borrow_mut();
 
-  if let Some(matrix) = opt_matrix_arg(&mut cx, 1){
-    this.with_matrix(|ctm| ctm.reset().pre_concat(&matrix) );
+  if let Some(matrix) = opt_m44_arg(&mut cx, 1){
+    this.with_matrix(|ctm| ctm.set_identity().pre_concat(&matrix) );
   }
   Ok(cx.undefined())
 }
@@ -422,7 +516,74 @@ pub fn clip(mut cx: FunctionContext) -> JsResult<JsUndefined> {
   }
   let rule = fill_rule_arg_or(&mut cx, shift, "nonzero")?;
 
-  this.clip_path(path, rule);
+  this.clip_path(path, rule, ClipOp::Intersect);
+  Ok(cx.undefined())
+}
+
+pub fn clipOut(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+  let this = cx.argument::<BoxedContext2D>(0)?;
+  let mut this = this.borrow_mut();
+
+  let mut shift = 1;
+  let path = opt_skpath_arg(&mut cx, 1);
+  if path.is_some() { shift += 1; }
+  else if cx.len() > 2{
+    return cx.throw_type_error("Expected a Path2D for 1st arg")
+  }
+  let rule = fill_rule_arg_or(&mut cx, shift, "nonzero")?;
+
+  this.clip_path(path, rule, ClipOp::Difference);
+  Ok(cx.undefined())
+}
+
+pub fn resetClip(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+  let this = cx.argument::<BoxedContext2D>(0)?;
+  this.borrow_mut().reset_clip();
+  Ok(cx.undefined())
+}
+
+pub fn getClipBounds(mut cx: FunctionContext) -> JsResult<JsObject> {
+  let this = cx.argument::<BoxedContext2D>(0)?;
+  let b = this.borrow().clip_bounds();
+
+  let js_object: Handle<JsObject> = cx.empty_object();
+  let left = cx.number(b.left);
+  let top = cx.number(b.top);
+  let right = cx.number(b.right);
+  let bottom = cx.number(b.bottom);
+  let width = cx.number(b.width());
+  let height = cx.number(b.height());
+
+  js_object.set(&mut cx, "left", left)?;
+  js_object.set(&mut cx, "top", top)?;
+  js_object.set(&mut cx, "right", right)?;
+  js_object.set(&mut cx, "bottom", bottom)?;
+  js_object.set(&mut cx, "width", width)?;
+  js_object.set(&mut cx, "height", height)?;
+  Ok(js_object)
+}
+
+pub fn isPointInClip(mut cx: FunctionContext) -> JsResult<JsBoolean> {
+  let this = cx.argument::<BoxedContext2D>(0)?;
+  let x = float_arg(&mut cx, 1, "x")?;
+  let y = float_arg(&mut cx, 2, "y")?;
+
+  Ok(cx.boolean(this.borrow().hit_test_clip((x, y))))
+}
+
+pub fn mask(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+  let this = cx.argument::<BoxedContext2D>(0)?;
+  let mode = string_arg_or(&mut cx, 2, "alpha");
+  let transform = opt_matrix_arg(&mut cx, 3).unwrap_or_default();
+
+  let luminance = match mode.as_str(){
+    "alpha" => false,
+    "luminance" => true,
+    _ => return cx.throw_type_error(format!("Expected mask mode to be \"alpha\" or \"luminance\" (got \"{}\")", mode))
+  };
+
+  let content = _image_content(&mut cx, 1, true);
+  this.borrow_mut().mask(&content, luminance, transform);
   Ok(cx.undefined())
 }
 
@@ -495,6 +656,53 @@ pub fn clearRect(mut cx: FunctionContext) -> JsResult<JsUndefined> {
   Ok(cx.undefined())
 }
 
+pub fn drawMesh(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+  let this = cx.argument::<BoxedContext2D>(0)?;
+  let positions = points_arg(&mut cx, 1)?;
+  let tex_coords = points_arg(&mut cx, 3)?;
+  let count = positions.len();
+
+  let colors = colors_arg(&mut cx, 2, "colors")?;
+
+  let mut indices = vec![];
+  if let Some(array) = cx.argument_opt(4).and_then(|arg| arg.downcast::<JsArray, _>(&mut cx).ok()){
+    let vals = array.to_vec(&mut cx)?;
+    for idx in floats_in(&mut cx, &vals){
+      if idx.fract() != 0.0 || idx < 0.0 || idx as usize >= count.min(u16::MAX as usize + 1){
+        return cx.throw_range_error(format!("Vertex indices must be integers between 0 and {} (got {})", count.saturating_sub(1), idx))
+      }
+      indices.push(idx as u16);
+    }
+    if indices.len() != vals.len(){
+      return cx.throw_type_error("Expected only numbers in `indices`")
+    }
+  }
+
+  let mode_name = string_arg_or(&mut cx, 5, "triangles");
+  let mode = match to_vertex_mode(&mode_name){
+    Some(mode) => mode,
+    None => return cx.throw_type_error(format!("Expected `mode` to be \"triangles\", \"strip\", or \"fan\" (got \"{}\")", mode_name))
+  };
+
+  for (name, len) in [("colors", colors.len()), ("texCoords", tex_coords.len())]{
+    if len > 0 && len != count{
+      return cx.throw_range_error(format!("Expected `{}` to have one entry per vertex ({}) but got {}", name, count, len))
+    }
+  }
+
+  if count >= 3{
+    this.borrow_mut().draw_mesh(mode, &positions, &colors, &tex_coords, &indices);
+  }
+  Ok(cx.undefined())
+}
+
+pub fn drawPatch(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+  let this = cx.argument::<BoxedContext2D>(0)?;
+  let patch = patch_args(&mut cx, 1)?;
+  this.borrow_mut().draw_patch(patch);
+  Ok(cx.undefined())
+}
+
 
 // fill & stoke properties --------------------------------------------------------------
 
@@ -580,6 +788,24 @@ pub fn get_lineDashFit(mut cx: FunctionContext) -> JsResult<JsString> {
   Ok(cx.string(fit))
 }
 
+pub fn set_lineWidthProfile(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+  let this = cx.argument::<BoxedContext2D>(0)?;
+  let profile = opt_width_profile_arg(&mut cx, 1)?;
+
+  this.borrow_mut().state.line_width_profile = profile;
+  Ok(cx.undefined())
+}
+
+pub fn get_lineWidthProfile(mut cx: FunctionContext) -> JsResult<JsValue> {
+  let this = cx.argument::<BoxedContext2D>(0)?;
+  let this = this.borrow();
+
+  match &this.state.line_width_profile{
+    Some(profile) => width_profile_to_array(&mut cx, profile),
+    None => Ok(cx.null().upcast())
+  }
+}
+
 pub fn getLineDash(mut cx: FunctionContext) -> JsResult<JsValue> {
   let this = cx.argument::<BoxedContext2D>(0)?;
   let this = this.borrow_mut();
@@ -635,6 +861,25 @@ pub fn set_lineCap(mut cx: FunctionContext) -> JsResult<JsUndefined> {
   Ok(cx.undefined())
 }
 
+pub fn get_strokeAlign(mut cx: FunctionContext) -> JsResult<JsString> {
+  let this = cx.argument::<BoxedContext2D>(0)?;
+  let this = this.borrow();
+
+  let name = from_stroke_align(this.state.stroke_align);
+  Ok(cx.string(name))
+}
+
+pub fn set_strokeAlign(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+  let this = cx.argument::<BoxedContext2D>(0)?;
+  let mut this = this.borrow_mut();
+  let name = string_arg(&mut cx, 1, "strokeAlign")?;
+
+  if let Some(mode) = to_stroke_align(&name){
+    this.state.stroke_align = mode;
+  }
+  Ok(cx.undefined())
+}
+
 pub fn get_lineDashOffset(mut cx: FunctionContext) -> JsResult<JsNumber> {
   let this = cx.argument::<BoxedContext2D>(0)?;
   let this = this.borrow_mut();
@@ -733,6 +978,24 @@ fn _layout_rects(cx: &mut FunctionContext, intrinsic:Size, nums:&[f32]) -> NeonR
   }
 }
 
+fn _image_content(cx: &mut FunctionContext, idx:usize, use_vector:bool) -> Content {
+  // accepts an Image, ImageData, or a canvas's context (whose content can be a bitmap or a picture)
+  let source = match cx.argument_opt(idx){
+    Some(source) => source,
+    None => return Content::default()
+  };
+
+  if let Ok(img) = source.downcast::<BoxedImage, _>(cx){
+    img.borrow().content.clone()
+  }else if let Ok(ctx) = source.downcast::<BoxedContext2D, _>(cx){
+    Content::from_context(&mut ctx.borrow_mut(), use_vector)
+  }else if let Ok(image_data) = image_data_arg(cx, idx){
+    Content::from_image_data(image_data)
+  }else{
+    Content::default()
+  }
+}
+
 pub fn drawImage(mut cx: FunctionContext) -> JsResult<JsUndefined> {
   let this = cx.argument::<BoxedContext2D>(0)?;
   let argc = cx.len() as usize;
@@ -740,17 +1003,7 @@ pub fn drawImage(mut cx: FunctionContext) -> JsResult<JsUndefined> {
   let arg_names = ["srcX", "srcY", "srcWidth", "srcHeight", "dstX", "dstY", "dstWidth", "dstHeight"];
   let nums = float_args_or_bail_at(&mut cx, 2, &arg_names[..argc-2])?;
 
-  let content = {
-    if let Ok(img) = source.downcast::<BoxedImage, _>(&mut cx){
-      img.borrow().content.clone()
-    }else if let Ok(ctx) = source.downcast::<BoxedContext2D, _>(&mut cx){
-      Content::from_context(&mut ctx.borrow_mut(), false)
-    }else if let Ok(image_data) = image_data_arg(&mut cx, 1){
-      Content::from_image_data(image_data)
-    }else{
-      Content::default()
-    }
-  };
+  let content = _image_content(&mut cx, 1, false);
 
   if let Content::Bitmap(img) = &content {
     let bounds_size = content.size();
@@ -789,6 +1042,197 @@ pub fn drawImage(mut cx: FunctionContext) -> JsResult<JsUndefined> {
   Ok(cx.undefined())
 }
 
+pub fn drawAtlas(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+  let this = cx.argument::<BoxedContext2D>(0)?;
+  let sprite_vals = cx.argument::<JsArray>(2)?.to_vec(&mut cx)?;
+  let xform_vals = cx.argument::<JsArray>(3)?.to_vec(&mut cx)?;
+  let colors = colors_arg(&mut cx, 4, "colors")?;
+  let blend_name = string_arg_or(&mut cx, 5, "modulate");
+
+  let content = _image_content(&mut cx, 1, false);
+
+  let sprites:Vec<Rect> = floats_in(&mut cx, &sprite_vals)
+    .chunks_exact(4)
+    .map(|r| Rect::from_xywh(r[0], r[1], r[2], r[3]))
+    .collect();
+  let xforms:Vec<RSXform> = floats_in(&mut cx, &xform_vals)
+    .chunks_exact(4)
+    .map(|t| RSXform::new(t[0], t[1], (t[2], t[3])))
+    .collect();
+
+  if sprite_vals.len() != sprites.len() * 4 || xform_vals.len() != xforms.len() * 4{
+    return cx.throw_type_error("Expected sprites as [x, y, width, height] and transforms as [scos, ssin, tx, ty] groups")
+  }else if sprites.len() != xforms.len(){
+    return cx.throw_range_error(format!("Expected one transform per sprite (got {} sprites and {} transforms)", sprites.len(), xforms.len()))
+  }else if !colors.is_empty() && colors.len() != sprites.len(){
+    return cx.throw_range_error(format!("Expected one color per sprite (got {} sprites and {} colors)", sprites.len(), colors.len()))
+  }
+
+  let blend = match blend_name.as_str(){
+    "modulate" => Some(BlendMode::Modulate),
+    name => to_blend_mode(name)
+  };
+  let blend = match blend{
+    Some(mode) => mode,
+    None => return cx.throw_type_error(format!("Unknown blend mode \"{}\"", blend_name))
+  };
+
+  if let Some(image) = content.to_bitmap(1.0){
+    this.borrow_mut().draw_atlas(&image, &xforms, &sprites, &colors, blend);
+  }
+  Ok(cx.undefined())
+}
+
+pub fn drawImageNine(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+  let this = cx.argument::<BoxedContext2D>(0)?;
+  let nums = float_args_at(&mut cx, 2, &["centerX", "centerY", "centerWidth", "centerHeight", "x", "y", "width", "height"])?;
+  let content = _image_content(&mut cx, 1, true);
+  let size = content.size();
+
+  let center = Rect::from_xywh(nums[0], nums[1], nums[2], nums[3]);
+  let dst = Rect::from_xywh(nums[4], nums[5], nums[6], nums[7]);
+  if !Rect::from_size(size).contains(center) || center.is_empty(){
+    return cx.throw_range_error(format!(
+      "The center rect must be non-empty and lie within the image's {}×{} bounds", size.width, size.height
+    ))
+  }
+
+  this.borrow_mut().draw_image_nine(&content, &center, &dst);
+  Ok(cx.undefined())
+}
+
+pub fn drawImageLattice(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+  let this = cx.argument::<BoxedContext2D>(0)?;
+  let x_vals = cx.argument::<JsArray>(2)?.to_vec(&mut cx)?;
+  let y_vals = cx.argument::<JsArray>(3)?.to_vec(&mut cx)?;
+  let nums = float_args_at(&mut cx, 4, &["x", "y", "width", "height"])?;
+  let content = _image_content(&mut cx, 1, true);
+  let size = content.size();
+
+  let x_divs = floats_in(&mut cx, &x_vals);
+  let y_divs = floats_in(&mut cx, &y_vals);
+  for (name, divs, vals, max) in [("xDivs", &x_divs, &x_vals, size.width), ("yDivs", &y_divs, &y_vals, size.height)]{
+    let ascending = divs.windows(2).all(|pair| pair[0] < pair[1]);
+    if divs.len() != vals.len() || !ascending || divs.iter().any(|div| *div < 0.0 || *div > max){
+      return cx.throw_range_error(format!("Expected `{}` to be a list of increasing numbers between 0 and {}", name, max))
+    }
+  }
+
+  // per-cell flags and colors (in row-major order)
+  let cell_count = (x_divs.len() + 1) * (y_divs.len() + 1);
+  let flags = match cx.argument_opt(8).and_then(|arg| arg.downcast::<JsArray, _>(&mut cx).ok()){
+    Some(array) => {
+      let vals = array.to_vec(&mut cx)?;
+      Some(strings_in(&mut cx, &vals))
+    },
+    None => None
+  };
+  if let Some(flags) = &flags{
+    if flags.len() != cell_count{
+      return cx.throw_range_error(format!("Expected `flags` to have one entry per cell ({}) but got {}", cell_count, flags.len()))
+    }
+  }
+
+  let mut colors:Vec<Option<Color>> = vec![];
+  if let Some(array) = cx.argument_opt(9).and_then(|arg| arg.downcast::<JsArray, _>(&mut cx).ok()){
+    for (i, val) in array.to_vec(&mut cx)?.into_iter().enumerate(){
+      if val.is_a::<JsNull, _>(&mut cx) || val.is_a::<JsUndefined, _>(&mut cx){
+        colors.push(None)
+      }else{
+        match color_in(&mut cx, val){
+          Some(color) => colors.push(Some(color)),
+          None => return cx.throw_type_error(format!("Expected a CSS color or null for entry {} in `colors`", i))
+        }
+      }
+    }
+    if colors.len() != cell_count{
+      return cx.throw_range_error(format!("Expected `colors` to have one entry per cell ({}) but got {}", cell_count, colors.len()))
+    }
+  }
+
+  let mut cells = vec![];
+  if flags.is_some() || !colors.is_empty(){
+    for i in 0..cell_count{
+      let color = colors.get(i).cloned().flatten();
+      let flag = match &flags{
+        Some(flags) => flags[i].as_str(),
+        None => if color.is_some(){ "color" }else{ "default" }
+      };
+      let rect_type = match (flag, color){
+        ("default", _) => RectType::Default,
+        ("transparent", _) => RectType::Transparent,
+        ("color", Some(_)) => RectType::FixedColor,
+        ("color", None) => return cx.throw_type_error(format!("Cell {} is flagged as \"color\" but has no entry in `colors`", i)),
+        (flag, _) => return cx.throw_type_error(format!("Expected \"default\", \"transparent\", or \"color\" for cell flags (got \"{}\")", flag))
+      };
+      cells.push((rect_type, color.unwrap_or(Color::TRANSPARENT)));
+    }
+  }
+
+  let dst = Rect::from_xywh(nums[0], nums[1], nums[2], nums[3]);
+  this.borrow_mut().draw_image_lattice(&content, &x_divs, &y_divs, &cells, &dst);
+  Ok(cx.undefined())
+}
+
+pub fn drawImageWarped(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+  let this = cx.argument::<BoxedContext2D>(0)?;
+  let src = points_arg(&mut cx, 2)?;
+  let src_cols = opt_float_arg(&mut cx, 3);
+  let dst = points_arg(&mut cx, 4)?;
+  let dst_cols = opt_float_arg(&mut cx, 5).unwrap_or(0.0);
+  let content = _image_content(&mut cx, 1, true);
+
+  let dst_cols = match _warp_grid_cols(&dst, dst_cols){
+    Some(cols) => cols,
+    None => return cx.throw_type_error(
+      "Expected `dst` to be a quad (4 points) or a grid (rows of points with at least 2 rows and 2 columns)"
+    )
+  };
+
+  // the src can be a quad or grid of image coordinates, or a crop rect (defaulting to the full image)
+  let (src, src_cols, extent) = match src_cols{
+    Some(cols) => match _warp_grid_cols(&src, cols){
+      Some(cols) => (src, cols, Rect::from_wh(1.0, 1.0)),
+      None => return cx.throw_type_error(
+        "Expected `src` to be a rect, a quad (4 points), or a grid (rows of points with at least 2 rows and 2 columns)"
+      )
+    },
+    None => {
+      let crop = match src.as_slice(){
+        [] => Rect::from_size(content.size()),
+        [origin, size] => Rect::from_xywh(origin.x, origin.y, size.x, size.y),
+        _ => return cx.throw_type_error("Expected `src` to be a rect, a quad (4 points), or a grid")
+      };
+      if crop.is_empty(){
+        return Ok(cx.undefined())
+      }
+
+      // trim any overdraw from the crop and only warp the corresponding fraction of the dst
+      let (crop, extent) = content.snap_rects_to_bounds(crop, Rect::from_wh(1.0, 1.0));
+      if crop.is_empty() || extent.is_empty(){
+        return Ok(cx.undefined())
+      }
+      let corners = vec![
+        Point::new(crop.left, crop.top), Point::new(crop.right, crop.top),
+        Point::new(crop.left, crop.bottom), Point::new(crop.right, crop.bottom),
+      ];
+      (corners, 2, extent)
+    }
+  };
+
+  this.borrow_mut().draw_image_warped(&content, &src, src_cols, &dst, dst_cols, &extent);
+  Ok(cx.undefined())
+}
+
+fn _warp_grid_cols(points:&[Point], cols:f32) -> Option<usize>{
+  // grids are passed as a flat list of points in row-major order along with their column count
+  let count = cols as usize;
+  match cols >= 2.0 && count as f32 == cols && points.len() % count == 0 && points.len() / count >= 2{
+    true => Some(count),
+    false => None
+  }
+}
+
 pub fn drawCanvas(mut cx: FunctionContext) -> JsResult<JsUndefined> {
   let argc = cx.len() as usize;
   let this = cx.argument::<BoxedContext2D>(0)?;
@@ -895,6 +1339,51 @@ pub fn set_imageSmoothingQuality(mut cx: FunctionContext) -> JsResult<JsUndefine
   Ok(cx.undefined())
 }
 
+pub fn get_imageResampling(mut cx: FunctionContext) -> JsResult<JsString> {
+  let this = cx.argument::<BoxedContext2D>(0)?;
+  let this = this.borrow_mut();
+  let mode = from_resampling(this.state.image_filter.resampling);
+  Ok(cx.string(mode))
+}
+
+pub fn set_imageResampling(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+  let this = cx.argument::<BoxedContext2D>(0)?;
+  let mut this = this.borrow_mut();
+  let name = string_arg(&mut cx, 1, "imageResampling")?;
+
+  if let Some(mode) = to_resampling(&name){
+    this.state.image_filter.resampling = mode;
+  }
+  Ok(cx.undefined())
+}
+
+// -- antialiasing -------------------------------------------------------------------
+
+pub fn get_antialias(mut cx: FunctionContext) -> JsResult<JsValue> {
+  let this = cx.argument::<BoxedContext2D>(0)?;
+  let this = this.borrow_mut();
+  Ok(match this.state.antialias{
+    Antialias::On => cx.boolean(true).upcast(),
+    Antialias::Off => cx.boolean(false).upcast(),
+    Antialias::Auto => cx.string("auto").upcast(),
+  })
+}
+
+pub fn set_antialias(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+  let this = cx.argument::<BoxedContext2D>(0)?;
+  let mut this = this.borrow_mut();
+  let mode = match (opt_bool_arg(&mut cx, 1), opt_string_arg(&mut cx, 1)){
+    (Some(true), _) => Antialias::On,
+    (Some(false), _) => Antialias::Off,
+    (_, Some(name)) if name == "auto" => Antialias::Auto,
+    _ => return Ok(cx.undefined())
+  };
+
+  this.state.antialias = mode;
+  this.state.paint.set_anti_alias(mode != Antialias::Off);
+  Ok(cx.undefined())
+}
+
 //
 // Typography
 //
@@ -1190,19 +1679,21 @@ pub fn set_globalCompositeOperation(mut cx: FunctionContext) -> JsResult<JsUndef
 
 // -- css3 filters ------------------------------------------------------------------
 
-pub fn get_filter(mut cx: FunctionContext) -> JsResult<JsString> {
+pub fn get_filter(mut cx: FunctionContext) -> JsResult<JsValue> {
   let this = cx.argument::<BoxedContext2D>(0)?;
   let this = this.borrow_mut();
-  Ok(cx.string(this.state.filter.to_string()))
+  match this.state.filter.is_object(){
+    true => Ok(cx.null().upcast()), // flag to the js context that it should use its cached shader/filter ref
+    false => Ok(cx.string(this.state.filter.to_string()).upcast())
+  }
 }
 
 pub fn set_filter(mut cx: FunctionContext) -> JsResult<JsUndefined> {
   let this = cx.argument::<BoxedContext2D>(0)?;
   let mut this = this.borrow_mut();
-  if !cx.argument::<JsValue>(1)?.is_a::<JsNull, _>(&mut cx) {
-    let (filter_text, specs) = filter_arg(&mut cx, 1)?;
-    if filter_text != this.state.filter.to_string() {
-      this.state.filter = Filter::new(&filter_text, &specs);
+  if let Some(filter) = opt_filter_arg(&mut cx, 1)? {
+    if filter.is_object() || filter.has_graph() || filter.to_string() != this.state.filter.to_string() {
+      this.state.filter = filter;
     }
   }
   Ok(cx.undefined())
@@ -1267,3 +1758,18 @@ pub fn set_shadowOffsetY(mut cx: FunctionContext) -> JsResult<JsUndefined> {
   this.state.shadow_offset.y = float_arg_or_bail(&mut cx, 1, "shadowOffsetY")?;
   Ok(cx.undefined())
 }
+
+pub fn get_shadows(mut cx: FunctionContext) -> JsResult<JsArray> {
+  let this = cx.argument::<BoxedContext2D>(0)?;
+  let this = this.borrow_mut();
+  shadows_to_array(&mut cx, &this.state.shadows)
+}
+
+pub fn set_shadows(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+  let this = cx.argument::<BoxedContext2D>(0)?;
+  let mut this = this.borrow_mut();
+  if let Some(shadows) = opt_shadows_arg(&mut cx, 1)?{
+    this.state.shadows = shadows;
+  }
+  Ok(cx.undefined())
+}
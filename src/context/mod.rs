@@ -2,15 +2,19 @@
 use std::cell::RefCell;
 use neon::prelude::*;
 use skia_safe::{
-  Canvas as SkCanvas, Paint, Path, PathOp, Image, Contains,
-  Rect, IRect, Point, Size, Color, Color4f, ColorSpace, PathFillType,
-  PaintStyle, BlendMode, ClipOp, PictureRecorder, Picture,
-  images, image_filters, dash_path_effect, path_1d_path_effect,
-  matrix::{ Matrix, TypeMask },
+  Canvas as SkCanvas, Paint, Path, PathOp, Image, Contains, Shader, ColorFilter, shaders,
+  ImageFilter as SkImageFilter, color_filters,
+  Rect, IRect, Point, Size, StrokeRec, Color, Color4f, ColorSpace, PathFillType,
+  PaintStyle, PaintJoin, BlendMode, ClipOp, PictureRecorder, Picture, FilterMode, TileMode,
+  SamplingOptions, MipmapMode,
+  images, image_filters, dash_path_effect, path_1d_path_effect, path::{self, Verb},
+  vertices::{self, VertexMode, BuilderFlags}, RSXform,
+  canvas::{SaveLayerRec, lattice::{Lattice, RectType}},
+  matrix::{ Matrix, TypeMask }, M44,
   textlayout::{ParagraphStyle, TextStyle, StrutStyle},
   canvas::SrcRectConstraint::Strict,
   path_utils::fill_path_with_paint,
-  font_style::{FontStyle, Width},
+  font_style::{FontStyle, Width}, font::Edging, image_filter::MapDirection,
 };
 
 pub mod api;
@@ -19,16 +23,20 @@ pub mod page;
 use crate::utils::*;
 use crate::font_library::FontLibrary;
 use crate::typography::{Typesetter, FontSpec, Baseline, Spacing, DecorationStyle};
-use crate::filter::{Filter, ImageFilter, FilterQuality};
-use crate::gradient::{CanvasGradient, BoxedCanvasGradient};
+use crate::filter::{Filter, ImageFilter, FilterQuality, Resampling, sampling_scale};
+use crate::gradient::{CanvasGradient, BoxedCanvasGradient, Patch, PATCH_DENSITY, rasterize_patches};
 use crate::pattern::{CanvasPattern, BoxedCanvasPattern};
 use crate::texture::{CanvasTexture, BoxedCanvasTexture};
-use crate::image::ImageData;
+use crate::shader::{CanvasShader, BoxedCanvasShader};
+use crate::image::{ImageData, Content};
+use crate::path::{WidthProfile, variable_outline};
 use crate::gpu::RenderingEngine;
 use page::{PageRecorder, Page, ExportOptions};
 
 const BLACK:Color = Color::BLACK;
 const TRANSPARENT:Color = Color::TRANSPARENT;
+const WARP_STEP:f32 = 8.0; // max length (in device pixels) of a warped image's mesh segments
+const WARP_MAX_STEPS:usize = 128; // keeps the vertex count within range of u16 indices
 
 pub type BoxedContext2D = JsBox<RefCell<Context2D>>;
 impl Finalize for Context2D {}
@@ -38,13 +46,17 @@ pub struct Context2D{
   recorder: RefCell<PageRecorder>,
   state: State,
   stack: Vec<State>,
+  layers: Vec<usize>,
   path: Path,
 }
 
 #[derive(Clone)]
 pub struct State{
   clip: Option<Path>,
+  clip_antialias: bool,
+  mask: Option<Shader>,
   matrix: Matrix,
+  matrix_3d: M44,
   paint: Paint,
 
   fill_style: Dye,
@@ -52,8 +64,12 @@ pub struct State{
   shadow_blur: f32,
   shadow_color: Color,
   shadow_offset: Point,
+  shadows: Vec<Shadow>,
 
   stroke_width: f32,
+  stroke_align: StrokeAlign,
+  antialias: Antialias,
+  line_width_profile: Option<WidthProfile>,
   line_dash_offset: f32,
   line_dash_list: Vec<f32>,
   line_dash_marker: Option<Path>,
@@ -94,12 +110,18 @@ impl Default for State {
 
     State {
       clip: None,
+      clip_antialias: true,
+      mask: None,
       matrix: Matrix::new_identity(),
+      matrix_3d: M44::new_identity(),
 
       paint,
       stroke_style: Dye::Color(BLACK),
       fill_style: Dye::Color(BLACK),
       stroke_width: 1.0,
+      stroke_align: StrokeAlign::Center,
+      antialias: Antialias::On,
+      line_width_profile: None,
       line_dash_offset: 0.0,
       line_dash_list: vec![],
       line_dash_marker: None,
@@ -107,12 +129,13 @@ impl Default for State {
 
       global_alpha: 1.0,
       global_composite_operation: BlendMode::SrcOver,
-      image_filter: ImageFilter{ smoothing:true, quality:FilterQuality::Low },
+      image_filter: ImageFilter{ smoothing:true, quality:FilterQuality::Low, resampling:Resampling::Auto },
       filter: Filter::default(),
 
       shadow_blur: 0.0,
       shadow_color: TRANSPARENT,
       shadow_offset: (0.0, 0.0).into(),
+      shadows: vec![],
 
       font: "10px sans-serif".to_string(),
       font_variant: "normal".to_string(),
@@ -184,6 +207,71 @@ impl State{
     }
   }
 
+  fn antialias_for(&self, path:&Path, matrix:&Matrix) -> bool{
+    match self.antialias{
+      Antialias::On => true,
+      Antialias::Off => false,
+      // shapes made solely of horizontal & vertical edges are snapped to the pixel grid in 'auto' mode
+      Antialias::Auto => !(matrix.rect_stays_rect() && is_rectilinear(path)),
+    }
+  }
+
+  pub fn text_edging(&self) -> Edging{
+    // glyphs are never rectilinear enough for 'auto' mode to snap them
+    match self.antialias{
+      Antialias::Off => Edging::Alias,
+      _ => Edging::AntiAlias
+    }
+  }
+
+  // when a width profile or non-centered alignment is in effect, strokes are drawn by filling their outline
+  fn stroke_outline(&self, path:&Path, paint:&Paint) -> Option<Path>{
+    // inside & outside strokes are twice as wide since half of their outline gets trimmed away
+    let width = match self.stroke_align{
+      StrokeAlign::Center => self.stroke_width,
+      _ => self.stroke_width * 2.0
+    };
+
+    let outline = match &self.line_width_profile{
+      Some(profile) => {
+        let (cap, join, miter) = (self.paint.stroke_cap(), self.paint.stroke_join(), self.paint.stroke_miter());
+        match (&self.line_dash_marker, self.line_dash_list.is_empty()){
+          (_, true) => variable_outline(path, width, profile, cap, join, miter, None),
+          (None, false) => {
+            let dash = (self.line_dash_list.as_slice(), self.line_dash_offset);
+            variable_outline(path, width, profile, cap, join, miter, Some(dash))
+          },
+          (Some(_), false) => {
+            // markers get stamped along the centerline first, then each one is outlined on its own
+            let stamped = paint.path_effect().and_then(|effect|
+              effect.filter_path(path, &StrokeRec::new_hairline(), path.bounds())
+            );
+            match stamped{
+              Some((markers, _)) => variable_outline(&markers, width, profile, cap, join, miter, None),
+              None => variable_outline(path, width, profile, cap, join, miter, None)
+            }
+          }
+        }
+      },
+      None if self.stroke_align == StrokeAlign::Center => return None,
+      None => {
+        let mut paint = paint.clone();
+        paint.set_style(PaintStyle::Stroke).set_stroke_width(width);
+
+        let mut outline = Path::default();
+        fill_path_with_paint(path, &paint, &mut outline, None, None);
+        outline
+      }
+    };
+
+    let aligned = match self.stroke_align{
+      StrokeAlign::Inside => outline.op(path, PathOp::Intersect),
+      StrokeAlign::Outside => outline.op(path, PathOp::Difference),
+      StrokeAlign::Center => None
+    };
+    Some(aligned.unwrap_or(outline))
+  }
+
 }
 
 impl Context2D{
@@ -195,6 +283,7 @@ impl Context2D{
       recorder: RefCell::new(PageRecorder::new(bounds)),
       path: Path::new(),
       stack: vec![],
+      layers: vec![],
       state: State::default(),
     }
   }
@@ -221,15 +310,18 @@ impl Context2D{
   }
 
   pub fn with_matrix<F>(&mut self, f:F)
-    where F:FnOnce(&mut Matrix) -> &Matrix
+    where F:FnOnce(&mut M44) -> &M44
   {
-    f(&mut self.state.matrix);
+    // the full 4×4 transform is retained for composing 3D operations, but since all drawing takes place
+    // on the z=0 plane, its 3×3 projection is all that's needed for paths, hit-testing, & bounds
+    f(&mut self.state.matrix_3d);
+    self.state.matrix = self.state.matrix_3d.to_m33();
     self.with_recorder(|mut recorder|{
-      recorder.set_matrix(self.state.matrix);
+      recorder.set_matrix(&self.state.matrix_3d);
     });
   }
 
-  pub fn render_to_canvas<F>(&self, paint:&Paint, f:F)
+  pub fn render_to_canvas<F>(&self, paint:&Paint, extent:Option<Rect>, f:F)
     where F:Fn(&SkCanvas, &Paint)
   {
     let render_shadow = |canvas:&SkCanvas, paint:&Paint|{
@@ -240,6 +332,26 @@ impl Context2D{
         f(canvas, &shadow_paint);
         canvas.restore();
       }
+
+      // the first shadow in the list is drawn on top, with outer shadows beneath the shape...
+      for shadow in self.state.shadows.iter().rev().filter(|s| !s.inset){
+        if let Some(shadow_paint) = self.paint_for_box_shadow(paint, shadow){
+          canvas.save();
+          canvas.set_matrix(&Matrix::translate(shadow.offset).into());
+          canvas.concat(&self.state.matrix);
+          f(canvas, &shadow_paint);
+          canvas.restore();
+        }
+      }
+    };
+
+    let render_inset = |canvas:&SkCanvas, paint:&Paint|{
+      // ...and inset shadows above it
+      for shadow in self.state.shadows.iter().rev().filter(|s| s.inset){
+        if let Some(shadow_paint) = self.paint_for_box_shadow(paint, shadow){
+          f(canvas, &shadow_paint);
+        }
+      }
     };
 
     match self.state.global_composite_operation{
@@ -258,6 +370,7 @@ impl Context2D{
           // draw normally
           layer.set_matrix(&self.state.matrix.into());
           f(layer, &layer_paint);
+          render_inset(layer, &layer_paint);
         }
 
         // transfer the picture contents to the canvas in a single operation, applying the blend
@@ -276,17 +389,45 @@ impl Context2D{
 
       },
       _ => {
-        self.with_canvas(|canvas| {
-          // draw the dropshadow (if applicable)
-          render_shadow(canvas, paint);
-          // draw with the normal paint
-          f(canvas, paint);
+        // let the recorder know which region of the canvas is affected (if it can be determined)
+        let area = extent.and_then(|rect| self.device_bounds(paint, rect));
+        self.with_recorder(|mut recorder|{
+          recorder.append_within(area, |canvas| {
+            // draw the dropshadow (if applicable)
+            render_shadow(canvas, paint);
+            // draw with the normal paint
+            f(canvas, paint);
+            render_inset(canvas, paint);
+          });
         });
       }
     };
 
   }
 
+  fn device_bounds(&self, paint:&Paint, local:Rect) -> Option<Rect>{
+    // find the canvas region touched when drawing the `local` rect (with its shadow) given
+    // the current transform & clip, or None if the paint's effects make it unknowable
+    let mut area = painted_bounds(paint, &local, &self.state.matrix)?;
+    if let Some(shadow_paint) = self.paint_for_shadow(paint){
+      let shadow_matrix = Matrix::concat(&Matrix::translate(self.state.shadow_offset), &self.state.matrix);
+      area.join(painted_bounds(&shadow_paint, &local, &shadow_matrix)?);
+    }
+    for shadow in self.state.shadows.iter().filter(|s| !s.inset){
+      if let Some(shadow_paint) = self.paint_for_box_shadow(paint, shadow){
+        let shadow_matrix = Matrix::concat(&Matrix::translate(shadow.offset), &self.state.matrix);
+        area.join(painted_bounds(&shadow_paint, &local, &shadow_matrix)?);
+      }
+    }
+
+    if let Some(clip) = &self.state.clip{
+      if !area.intersect(clip.bounds()){
+        area = Rect::new_empty();
+      }
+    }
+    Some(area)
+  }
+
   pub fn map_points(&self, coords:&[f32]) -> Vec<Point>{
     // treat the flat array of floats as x/y pairs
     coords
@@ -302,6 +443,7 @@ impl Context2D{
     self.bounds = Rect::from_size(dims);
     self.path = Path::default();
     self.stack = vec![];
+    self.layers = vec![];
     self.state = State::default();
 
     // erase any existing content
@@ -324,17 +466,122 @@ impl Context2D{
   }
 
   pub fn pop(&mut self){
+    // restore() can't reach past the state that was saved by beginLayer()
+    if self.layers.last() == Some(&self.stack.len()){
+      return
+    }
+
     // don't do anything if we're already back at the initial stack frame
     if let Some(old_state) = self.stack.pop(){
       self.state = old_state;
 
       self.with_recorder(|mut recorder|{
-        recorder.set_matrix(self.state.matrix);
-        recorder.set_clip(&self.state.clip);
+        recorder.set_matrix(&self.state.matrix_3d);
+        recorder.set_clip(&self.state.clip, self.state.clip_antialias);
+        recorder.set_mask(&self.state.mask);
       });
     }
   }
 
+  pub fn begin_layer(&mut self, filter:Option<Filter>, alpha:Option<f32>, blend:Option<BlendMode>){
+    // the group is composited using the current alpha, blend mode, filter, & shadow unless overridden
+    let mut paint = Paint::default();
+    let mut filter = filter.unwrap_or_else(|| self.state.filter.clone());
+    filter.mix_into(&mut paint, self.state.matrix, true)
+      .set_alpha_f(alpha.unwrap_or(self.state.global_alpha))
+      .set_blend_mode(blend.unwrap_or(self.state.global_composite_operation));
+
+    let base_filter = paint.image_filter();
+    let State {shadow_color, shadow_blur, shadow_offset, ..} = self.state;
+    if shadow_color.a() > 0 && !(shadow_blur == 0.0 && shadow_offset.is_zero()){
+      let sigma = shadow_blur / 2.0;
+      paint.set_image_filter(image_filters::drop_shadow(
+        shadow_offset, (sigma, sigma), shadow_color, ColorSpace::new_srgb(), paint.image_filter(), None
+      ));
+    }
+
+    if !self.state.shadows.is_empty(){
+      // the group is composited without a transform, so the shadows' dimensions can be used as-is
+      let box_shadow = |shadow:&Shadow| shadow.filter(
+        shadow.offset, Point::new(shadow.blur, shadow.blur) * 0.5,
+        Point::new(shadow.spread, shadow.spread), base_filter.clone()
+      );
+      let shadows = &self.state.shadows;
+      let layers = shadows.iter().rev().filter(|s| !s.inset).map(box_shadow)
+        .chain(std::iter::once(paint.image_filter()))
+        .chain(shadows.iter().rev().filter(|s| s.inset).map(box_shadow))
+        .collect::<Vec<_>>();
+      paint.set_image_filter(image_filters::merge(layers, None));
+    }
+
+    // save the current state, then reset the compositing settings for drawing within the layer
+    self.push();
+    self.layers.push(self.stack.len());
+    self.state.global_alpha = 1.0;
+    self.state.global_composite_operation = BlendMode::SrcOver;
+    self.state.paint.set_blend_mode(BlendMode::SrcOver);
+    self.state.filter = Filter::default();
+    self.state.shadow_color = TRANSPARENT;
+    self.state.shadow_blur = 0.0;
+    self.state.shadow_offset = (0.0, 0.0).into();
+    self.state.shadows = vec![];
+
+    self.with_recorder(|mut recorder|{
+      recorder.begin_layer(paint);
+    });
+  }
+
+  pub fn end_layer(&mut self) -> bool{
+    match self.layers.pop(){
+      Some(depth) => {
+        // discard any unbalanced save() calls made within the layer, then return to the state at beginLayer()
+        self.stack.truncate(depth);
+        self.pop();
+        self.with_recorder(|mut recorder|{
+          recorder.end_layer();
+        });
+        true
+      },
+      None => false
+    }
+  }
+
+  pub fn apply_filter(&mut self, mut filter:Filter, region:Option<Path>){
+    let mut paint = Paint::default();
+    let backdrop = match filter.mix_into(&mut paint, self.state.matrix, true).image_filter(){
+      Some(backdrop) => backdrop,
+      None => return
+    };
+
+    // the filtered backdrop replaces the existing pixels (rather than being composited on top of them)
+    let mut layer_paint = Paint::default();
+    layer_paint.set_blend_mode(BlendMode::Src);
+
+    // limit the effect to the region (if specified) in addition to the current clip
+    let clip = region.map(|path| {
+      let antialias = self.state.antialias_for(&path, &self.state.matrix);
+      (path, antialias)
+    });
+    // the layer's extent needs to include all the pixels the filter will sample, not just the ones it replaces
+    let area = clip.as_ref().map(|(path, _)| {
+      let bounds = self.state.matrix.map_rect(path.bounds()).0;
+      let reach = backdrop.filter_bounds(bounds.round_out(), &self.state.matrix, MapDirection::Reverse, None);
+      Rect::join2(Rect::from_irect(reach), bounds)
+    });
+
+    self.with_recorder(|mut recorder|{
+      recorder.append_backdrop(area, |canvas|{
+        canvas.save();
+        if let Some((path, antialias)) = &clip {
+          canvas.clip_path(path, ClipOp::Intersect, *antialias);
+        }
+        canvas.save_layer(&SaveLayerRec::default().backdrop(&backdrop).paint(&layer_paint));
+        canvas.restore();
+        canvas.restore();
+      });
+    });
+  }
+
   pub fn scoot(&mut self, point:Point){
     // update initial point if first drawing command isn't a moveTo
     if self.path.is_empty(){
@@ -356,18 +603,30 @@ impl Context2D{
       self.state.fill_style.is_opaque() &&
       self.state.global_alpha == 1.0 &&
       self.state.clip.is_none() &&
+      self.state.mask.is_none() &&
+      self.layers.is_empty() &&
       path.conservatively_contains_rect(self.bounds)
     {
-      // ...erase existing vector content layers (but preserve CTM & clip path)
+      // ...erase existing vector content layers (but preserve CTM & clip path). This is skipped within
+      // a beginLayer() group since the group still needs to be composited onto the existing content
       self.with_recorder(|mut recorder|{
         recorder.set_bounds(self.bounds);
-        recorder.set_matrix(self.state.matrix);
-        recorder.set_clip(&self.state.clip);
+        recorder.set_matrix(&self.state.matrix_3d);
+        recorder.set_clip(&self.state.clip, self.state.clip_antialias);
       });
     }
 
-    let paint = self.paint_for_drawing(style);
-    self.render_to_canvas(&paint, |canvas, paint| {
+    let mut paint = self.paint_for_drawing(style);
+    if style == PaintStyle::Stroke{
+      if let Some(outline) = self.state.stroke_outline(&path, &paint){
+        path = outline;
+        paint.set_style(PaintStyle::Fill).set_path_effect(None);
+      }
+    }
+    paint.set_anti_alias(self.state.antialias_for(&path, &self.state.matrix));
+
+    let extent = path.compute_tight_bounds();
+    self.render_to_canvas(&paint, Some(extent), |canvas, paint| {
       if let Some(tile) = self.state.texture(style){
         // SKIA PATH EFFECT BUG WORKAROUND:
         //
@@ -390,7 +649,7 @@ impl Context2D{
         if tile.use_clip(){
           // apply the user path as a clipping mask and fill the whole enclosing rect with tile pattern
           canvas.save();
-          canvas.clip_path(&stencil, Some(ClipOp::Intersect), Some(true));
+          canvas.clip_path(&stencil, Some(ClipOp::Intersect), Some(paint.is_anti_alias()));
           canvas.draw_path(&enclosing_frame, &tile_paint);
           canvas.restore();
         }else{
@@ -411,28 +670,94 @@ impl Context2D{
     });
   }
 
-  pub fn clip_path(&mut self, path: Option<Path>, rule:PathFillType){
+  pub fn clip_path(&mut self, path: Option<Path>, rule:PathFillType, op:ClipOp){
     let mut clip = match path{
       Some(path) => path.with_transform(&self.state.matrix),
       None => self.path.clone()
     };
     clip.set_fill_type(rule);
-
-    // update the clip with the intersection of the new path, unless it's larger than
-    // the canvas itself in which case the whole clip is discarded
+    let antialias = self.state.antialias_for(&clip, &Matrix::new_identity());
+    let had_clip = self.state.clip.is_some();
+
+    // update the clip with the intersection of the new path (or its complement when clipping out), unless
+    // the result is larger than the canvas itself in which case the whole clip is discarded
+    let path_op = match op{
+      ClipOp::Difference => PathOp::Difference,
+      _ => PathOp::Intersect
+    };
     self.state.clip = self.state.clip.as_ref()
       .unwrap_or(&Path::rect(self.bounds, None))
-      .op(&clip, PathOp::Intersect)
+      .op(&clip, path_op)
       .and_then(|path| match path.conservatively_contains_rect(self.bounds){
         true => None,
         false => Some(path),
       });
+    // the clip is drawn as a single path, so once any antialiased edges have been added it all is antialiased
+    self.state.clip_antialias = antialias || (had_clip && self.state.clip_antialias) || self.state.clip.is_none();
+
+    self.with_recorder(|mut recorder|{
+      recorder.set_clip(&self.state.clip, self.state.clip_antialias);
+    });
+  }
 
+  pub fn reset_clip(&mut self){
+    self.state.clip = None;
+    self.state.clip_antialias = true;
     self.with_recorder(|mut recorder|{
-      recorder.set_clip(&self.state.clip);
+      recorder.set_clip(&self.state.clip, self.state.clip_antialias);
     });
   }
 
+  pub fn clip_bounds(&self) -> Rect{
+    // the clip is stored in device coordinates, so map its bounds back into the current user space
+    let bounds = match &self.state.clip{
+      Some(clip) => clip.compute_tight_bounds(),
+      None => self.bounds
+    };
+    match self.state.matrix.invert(){
+      Some(inverse) => inverse.map_rect(bounds).0,
+      None => Rect::new_empty()
+    }
+  }
+
+  pub fn hit_test_clip(&self, point:impl Into<Point>) -> bool{
+    let point = point.into();
+    match &self.state.clip{
+      Some(clip) => clip.contains(point),
+      None => self.bounds.contains(point)
+    }
+  }
+
+  pub fn mask(&mut self, content:&Content, luminance:bool, transform:Matrix){
+    let decal = (TileMode::Decal, TileMode::Decal);
+    let sampling = self.state.image_filter.sampling(sampling_scale(&Matrix::concat(&self.state.matrix, &transform)));
+    let shader = match content{
+      Content::Bitmap(image) => image.to_shader(decal, sampling, None),
+      Content::Vector(pict, size) => Some(pict.to_shader(decal, FilterMode::Linear, None, Some(&Rect::from_size(*size)))),
+      _ => None
+    };
+
+    if let Some(mut shader) = shader{
+      // in luminance mode, brightness (rather than opacity) determines how much of the drawing shows through
+      if luminance{
+        shader = shader.with_color_filter(ColorFilter::luma());
+      }
+
+      // place the mask in device coordinates based on the current transform
+      shader = shader.with_local_matrix(&Matrix::concat(&self.state.matrix, &transform));
+
+      // successive masks are combined by multiplying their alpha values
+      self.state.mask = Some(match self.state.mask.take(){
+        Some(prev) => shaders::blend(BlendMode::SrcIn, prev, shader),
+        None => shader
+      });
+
+      self.with_recorder(|mut recorder|{
+        recorder.set_mask(&self.state.mask);
+      });
+    }
+  }
+
   pub fn hit_test_path(&mut self, path: &mut Path, point:impl Into<Point>, rule:Option<PathFillType>, style: PaintStyle) -> bool {
     let point = point.into();
     let point = self.in_local_coordinates(point.x, point.y);
@@ -447,7 +772,9 @@ impl Context2D{
         let scale = Matrix::scale((precision, precision));
 
         let mut traced_path = Path::default();
-        if fill_path_with_paint(path, &paint, &mut traced_path, None, Some(scale)){
+        if let Some(outline) = self.state.stroke_outline(path, &paint){
+          outline.contains(point)
+        }else if fill_path_with_paint(path, &paint, &mut traced_path, None, Some(scale)){
           traced_path.contains(point)
         }else{
           path.contains(point)
@@ -461,34 +788,236 @@ impl Context2D{
   }
 
   pub fn clear_rect(&mut self, rect:&Rect){
-    match self.state.matrix.map_rect(rect).0.contains(self.bounds){
+    match self.layers.is_empty() && self.state.matrix.map_rect(rect).0.contains(self.bounds){
 
-      // if rect fully encloses canvas, erase existing content (but preserve CTM & clip path)
+      // if rect fully encloses canvas (and isn't within a layer), erase existing content (but preserve CTM & clip path)
       true =>  self.with_recorder(|mut recorder|{
         recorder.set_bounds(self.bounds);
-        recorder.set_matrix(self.state.matrix);
-        recorder.set_clip(&self.state.clip);
+        recorder.set_matrix(&self.state.matrix_3d);
+        recorder.set_clip(&self.state.clip, self.state.clip_antialias);
+        recorder.set_mask(&self.state.mask);
       }),
 
       // otherwise, paint over the specified region but preserve overdrawn vectors
-      false => self.with_canvas(|canvas| {
+      false => {
         let mut paint = Paint::default();
-        paint.set_anti_alias(true)
+        paint.set_anti_alias(self.state.antialias_for(&Path::rect(*rect, None), &self.state.matrix))
              .set_style(PaintStyle::Fill)
              .set_blend_mode(BlendMode::Clear);
-        canvas.draw_rect(rect, &paint);
-      })
+        let area = painted_bounds(&paint, rect, &self.state.matrix);
+        self.with_recorder(|mut recorder|{
+          recorder.append_within(area, |canvas| {
+            canvas.draw_rect(rect, &paint);
+          });
+        })
+      }
+    }
+  }
+
+  pub fn draw_mesh(&mut self, mode:VertexMode, positions:&[Point], colors:&[Color], tex_coords:&[Point], indices:&[u16]){
+    let mut flags = BuilderFlags::empty();
+    flags.set(BuilderFlags::HAS_COLORS, !colors.is_empty());
+    flags.set(BuilderFlags::HAS_TEX_COORDS, !tex_coords.is_empty());
+
+    let mut builder = vertices::Builder::new(mode, positions.len(), indices.len(), flags);
+    builder.positions().copy_from_slice(positions);
+    if let Some(dst) = builder.colors(){ dst.copy_from_slice(colors) }
+    if let Some(dst) = builder.tex_coords(){ dst.copy_from_slice(tex_coords) }
+    if let Some(dst) = builder.indices(){ dst.copy_from_slice(indices) }
+    let mesh = builder.detach();
+
+    let mut paint = self.paint_for_drawing(PaintStyle::Fill);
+    if !colors.is_empty() && paint.shader().is_none(){
+      // per-vertex colors replace a flat fillStyle (but still respect globalAlpha)
+      paint.set_color(Color::WHITE).set_alpha_f(self.state.global_alpha);
+    }
+
+    // when both are present, vertex colors are multiplied with the fillStyle's gradient/pattern
+    self.render_to_canvas(&paint, Some(*mesh.bounds()), |canvas, paint| {
+      canvas.draw_vertices(&mesh, BlendMode::Modulate, paint);
+    });
+  }
+
+  pub fn draw_atlas(&mut self, image:&Image, xforms:&[RSXform], sprites:&[Rect], colors:&[Color], blend:BlendMode){
+    // draw all the sprites with a single paint & sampling setting
+    let paint = self.paint_for_image();
+    let sprite_scale = xforms.iter().map(|xform| xform.scos.hypot(xform.ssin)).fold(f32::INFINITY, f32::min);
+    let sampling = self.state.image_filter.sampling(sampling_scale(&self.state.matrix) * sprite_scale);
+    let colors = match colors.is_empty(){
+      true => None,
+      false => Some(colors)
+    };
+
+    let mut extent = Rect::new_empty();
+    for (xform, sprite) in xforms.iter().zip(sprites){
+      if let Some(quad) = Rect::from_bounds(&xform.to_quad(sprite.size())){
+        extent.join(quad);
+      }
     }
+
+    self.render_to_canvas(&paint, Some(extent), |canvas, paint| {
+      canvas.draw_atlas(image, xforms, sprites, colors, blend, sampling, None, paint);
+    });
+  }
+
+  pub fn draw_image_nine(&mut self, content:&Content, center:&Rect, dst_rect:&Rect){
+    self.render_image_grid(content, dst_rect, |canvas, image, scale, dst, filter_mode, paint|{
+      let center = Rect::from_ltrb(center.left * scale, center.top * scale, center.right * scale, center.bottom * scale);
+      canvas.draw_image_nine(image, center.round(), dst, filter_mode, Some(paint));
+    });
+  }
+
+  pub fn draw_image_lattice(&mut self, content:&Content, x_divs:&[f32], y_divs:&[f32], cells:&[(RectType, Color)], dst_rect:&Rect){
+    let (rect_types, colors):(Vec<RectType>, Vec<Color>) = cells.iter().cloned().unzip();
+    self.render_image_grid(content, dst_rect, |canvas, image, scale, dst, filter_mode, paint|{
+      let x_divs:Vec<i32> = x_divs.iter().map(|x| (x * scale).round() as i32).collect();
+      let y_divs:Vec<i32> = y_divs.iter().map(|y| (y * scale).round() as i32).collect();
+      let lattice = Lattice{
+        x_divs:&x_divs,
+        y_divs:&y_divs,
+        rect_types:(!cells.is_empty()).then_some(rect_types.as_slice()),
+        colors:(!cells.is_empty()).then_some(colors.as_slice()),
+        bounds:None,
+      };
+      canvas.draw_image_lattice(image, &lattice, dst, filter_mode, Some(paint));
+    });
+  }
+
+  pub fn draw_image_warped(&mut self, content:&Content, src:&[Point], src_cols:usize, dst:&[Point], dst_cols:usize, extent:&Rect){
+    // the src & dst grids are both treated as functions of (u, v) in the unit square; the image is
+    // drawn as a textured mesh sampling them at matching positions within the `extent`
+    let scale = self.raster_scale(content);
+    let image = match content.to_bitmap(scale){
+      Some(image) => image,
+      None => return
+    };
+
+    // subdivide finely enough that the piecewise-affine texture mapping isn't visible
+    let device_scale = self.state.matrix.decompose_scale(None)
+      .map(|size| size.width.max(size.height))
+      .unwrap_or(1.0);
+    let dst_rows = dst.len() / dst_cols;
+    let row_len = (0..dst_rows).map(|r| polyline_length((0..dst_cols).map(|c| dst[r * dst_cols + c]))).fold(0.0, f32::max);
+    let col_len = (0..dst_cols).map(|c| polyline_length((0..dst_rows).map(|r| dst[r * dst_cols + c]))).fold(0.0, f32::max);
+    let steps = |len:f32, span:f32, cells:usize| -> usize{
+      // cap the count first, then keep it a multiple of `cells` so vertices fall on the grid lines
+      let n = (len * span * device_scale / WARP_STEP).ceil().max(1.0) as usize;
+      (n.div_ceil(cells) * cells).min((WARP_MAX_STEPS / cells).max(1) * cells)
+    };
+    let nx = steps(row_len, extent.width(), dst_cols - 1);
+    let ny = steps(col_len, extent.height(), dst_rows - 1);
+
+    let mut positions = vec![];
+    let mut tex_coords = vec![];
+    for j in 0..=ny{
+      let t = j as f32 / ny as f32;
+      for i in 0..=nx{
+        let s = i as f32 / nx as f32;
+        let (u, v) = (extent.left + s * extent.width(), extent.top + t * extent.height());
+        positions.push(warp_point(dst, dst_cols, u, v));
+        tex_coords.push(warp_point(src, src_cols, s, t) * scale);
+      }
+    }
+
+    let mut indices:Vec<u16> = vec![];
+    for j in 0..ny{
+      for i in 0..nx{
+        let a = (j * (nx + 1) + i) as u16;
+        let b = a + (nx + 1) as u16;
+        indices.extend([a, a + 1, b, a + 1, b + 1, b]);
+      }
+    }
+
+    let mut builder = vertices::Builder::new(VertexMode::Triangles, positions.len(), indices.len(), BuilderFlags::HAS_TEX_COORDS);
+    builder.positions().copy_from_slice(&positions);
+    if let Some(dst) = builder.tex_coords(){ dst.copy_from_slice(&tex_coords) }
+    if let Some(dst) = builder.indices(){ dst.copy_from_slice(&indices) }
+    let mesh = builder.detach();
+
+    // the bitmap was rendered at `scale` pixels per unit, which the warp roughly preserves
+    let mut paint = self.paint_for_image();
+    let sampling = self.state.image_filter.sampling(sampling_scale(&self.state.matrix) / scale);
+    paint.set_shader(image.to_shader((TileMode::Decal, TileMode::Decal), sampling, None));
+    self.render_to_canvas(&paint, Some(*mesh.bounds()), |canvas, paint| {
+      canvas.draw_vertices(&mesh, BlendMode::Modulate, paint);
+    });
+  }
+
+  fn raster_scale(&self, content:&Content) -> f32{
+    // vector images are rasterized at the current device scale so they remain crisp
+    match content{
+      Content::Vector(..) => self.state.matrix.decompose_scale(None)
+        .map(|size| size.width.max(size.height).max(1.0))
+        .unwrap_or(1.0),
+      _ => 1.0
+    }
+  }
+
+  fn render_image_grid<F>(&mut self, content:&Content, dst_rect:&Rect, f:F)
+    where F:Fn(&SkCanvas, &Image, f32, &Rect, FilterMode, &Paint)
+  {
+    // vector images are rasterized at the device scale so their fixed regions remain crisp, then
+    // drawn with a compensating transform so each source unit still maps to one canvas unit
+    let scale = self.raster_scale(content);
+    let image = match content.to_bitmap(scale){
+      Some(image) => image,
+      None => return
+    };
+
+    let paint = self.paint_for_image();
+    let filter_mode = self.state.image_filter.filter_mode();
+    let dst = Rect::from_ltrb(dst_rect.left * scale, dst_rect.top * scale, dst_rect.right * scale, dst_rect.bottom * scale);
+    self.render_to_canvas(&paint, Some(*dst_rect), |canvas, paint| {
+      canvas.save();
+      canvas.scale((1.0 / scale, 1.0 / scale));
+      f(canvas, &image, scale, &dst, filter_mode, paint);
+      canvas.restore();
+    });
+  }
+
+  pub fn draw_patch(&mut self, patch:Patch){
+    let mut patch_paint = Paint::default();
+    patch_paint.set_anti_alias(true);
+    self.state.fill_style.mix_into(&mut patch_paint, 1.0, self.state.image_filter, &self.state.matrix);
+    if patch.colors.is_some() && patch_paint.shader().is_none(){
+      patch_paint.set_color(Color::WHITE);
+    }
+
+    // PDF & SVG can't represent patches, so a bitmap version (at the current device resolution) is
+    // drawn first and then replaced by the actual patch within the same layer. Vector devices skip
+    // the patch and keep the bitmap while raster output draws the patch at the export's full density
+    let scale = self.state.matrix.decompose_scale(None)
+      .map(|size| size.width.max(size.height))
+      .unwrap_or(1.0);
+    let (image, bounds) = match rasterize_patches(&[patch.clone()], &patch_paint, scale * PATCH_DENSITY){
+      Some(raster) => raster,
+      None => return
+    };
+    let outline = patch.outline();
+    patch_paint.set_blend_mode(BlendMode::Src);
+
+    // the layer is composited like an image so the alpha, blend mode, filter, & shadows all apply
+    let paint = self.paint_for_image();
+    self.render_to_canvas(&paint, Some(bounds), |canvas, paint| {
+      canvas.save_layer(&SaveLayerRec::default().bounds(&bounds).paint(paint));
+      canvas.save();
+      canvas.clip_path(&outline, None, true);
+      canvas.draw_image_rect(&image, None, bounds, &Paint::default());
+      canvas.restore();
+      canvas.draw_patch(&patch.cubics, patch.colors.as_ref(), patch.tex_coords.as_ref(), BlendMode::Modulate, &patch_paint);
+      canvas.restore();
+    });
   }
 
   pub fn draw_picture(&mut self, picture:&Picture, src_rect:&Rect, dst_rect:&Rect){
     let paint = self.paint_for_image();
+    let antialias = self.state.antialias_for(&Path::rect(*dst_rect, None), &self.state.matrix);
     let mag = Point::new(dst_rect.width()/src_rect.width(), dst_rect.height()/src_rect.height());
     let mut matrix = Matrix::new_identity();
     matrix.pre_scale( (mag.x, mag.y), None )
       .pre_translate((dst_rect.x()/mag.x - src_rect.x(), dst_rect.y()/mag.y - src_rect.y()));
 
-    self.render_to_canvas(&paint, |canvas, paint| {
+    self.render_to_canvas(&paint, Some(*dst_rect), |canvas, paint| {
       // only use paint if we need it for alpha, blend, shadow, or effect since otherwise
       // the SVG exporter will omit the picture altogether
       let paint = match (paint.as_blend_mode(), paint.alpha(), paint.image_filter()) {
@@ -496,17 +1025,34 @@ impl Context2D{
         _ => Some(paint)
       };
       canvas.save();
-      canvas.clip_rect(dst_rect, ClipOp::Intersect, true);
+      canvas.clip_rect(dst_rect, ClipOp::Intersect, antialias);
       canvas.draw_picture(&picture, Some(&matrix), paint);
       canvas.restore();
     });
   }
 
   pub fn draw_image(&mut self, image:&Image, src_rect:&Rect, dst_rect:&Rect){
-    let paint = self.paint_for_image();
-    self.render_to_canvas(&paint, |canvas, paint| {
-      let sampling = self.state.image_filter.sampling();
-      canvas.draw_image_rect_with_sampling_options(image, Some((src_rect, Strict)), dst_rect, sampling, paint);
+    let mut paint = self.paint_for_image();
+    paint.set_anti_alias(self.state.antialias_for(&Path::rect(*dst_rect, None), &self.state.matrix));
+
+    // lanczos resampling is done up-front (and only for axis-aligned drawing)
+    let resized = match self.state.matrix.is_scale_translate(){
+      true => self.state.image_filter.downscale(image, src_rect, self.state.matrix.map_rect(dst_rect).0.size()),
+      false => None
+    };
+
+    self.render_to_canvas(&paint, Some(*dst_rect), |canvas, paint| {
+      match &resized {
+        Some(resized) => {
+          let sampling = SamplingOptions::new(FilterMode::Linear, MipmapMode::None);
+          canvas.draw_image_rect_with_sampling_options(resized, None, dst_rect, sampling, paint);
+        },
+        None => {
+          let zoom = (dst_rect.width() / src_rect.width()).abs().min((dst_rect.height() / src_rect.height()).abs());
+          let sampling = self.state.image_filter.sampling(sampling_scale(&self.state.matrix) * zoom);
+          canvas.draw_image_rect_with_sampling_options(image, Some((src_rect, Strict)), dst_rect, sampling, paint);
+        }
+      }
     });
   }
 
@@ -536,13 +1082,15 @@ impl Context2D{
     let info = image_data.image_info();
     if let Some(bitmap) = images::raster_from_data(&info, image_data.buffer, info.min_row_bytes()) {
       self.push(); // cache matrix & clip in self.state
-      self.with_canvas(|canvas| {
-        let paint = Paint::default();
-        let mut eraser = Paint::default();
-        canvas.restore_to_count(1); // discard current matrix & clip
-        eraser.set_blend_mode(BlendMode::Clear);
-        canvas.draw_image_rect(&bitmap, Some((src_rect, Strict)), dst_rect, &eraser);
-        canvas.draw_image_rect(&bitmap, Some((src_rect, Strict)), dst_rect, &paint);
+      self.with_recorder(|mut recorder|{
+        recorder.append_within(Some(dst_rect.with_outset((1.0, 1.0))), |canvas| {
+          let paint = Paint::default();
+          let mut eraser = Paint::default();
+          canvas.restore_to_count(1); // discard current matrix & clip
+          eraser.set_blend_mode(BlendMode::Clear);
+          canvas.draw_image_rect(&bitmap, Some((src_rect, Strict)), dst_rect, &eraser);
+          canvas.draw_image_rect(&bitmap, Some((src_rect, Strict)), dst_rect, &paint);
+        });
       });
       self.pop(); // restore discarded matrix & clip
     }
@@ -580,13 +1128,17 @@ impl Context2D{
     let mut typesetter = Typesetter::new(&self.state, text, width);
     let origin = Point::new(x, y);
 
-    if self.state.texture(style).is_some(){
-      // if dye is a texture, convert text to path first
+    let custom_stroke = style == PaintStyle::Stroke && (
+      self.state.stroke_align != StrokeAlign::Center || self.state.line_width_profile.is_some()
+    );
+
+    if self.state.texture(style).is_some() || custom_stroke{
+      // if dye is a texture (or the stroke needs to be outlined by hand to honor its alignment or
+      // width profile), convert text to path first
       self.draw_path(Some(typesetter.path(origin)), style, None);
     }else{
-      self.render_to_canvas(&paint, |canvas, paint| {
-        let (paragraph, offset) = typesetter.layout(paint);
-        paragraph.paint(canvas, origin + offset);
+      self.render_to_canvas(&paint, None, |canvas, paint| {
+        typesetter.paint(canvas, paint, origin);
       });
     }
   }
@@ -602,7 +1154,7 @@ impl Context2D{
   pub fn paint_for_drawing(&mut self, style:PaintStyle) -> Paint{
     let mut paint = self.state.paint.clone();
     self.state.filter.mix_into(&mut paint, self.state.matrix, false);
-    self.state.dye(style).mix_into(&mut paint, self.state.global_alpha, self.state.image_filter);
+    self.state.dye(style).mix_into(&mut paint, self.state.global_alpha, self.state.image_filter, &self.state.matrix);
     paint.set_style(style);
 
     if style==PaintStyle::Stroke && !self.state.line_dash_list.is_empty(){
@@ -641,40 +1193,183 @@ impl Context2D{
   }
 
   pub fn paint_for_shadow(&self, base_paint:&Paint) -> Option<Paint> {
-    let State {shadow_color, mut shadow_blur, shadow_offset, ..} = self.state;
+    let State {shadow_color, shadow_blur, shadow_offset, ..} = self.state;
     if shadow_color.a() == 0 || (shadow_blur == 0.0 && shadow_offset.is_zero()){
       return None
     }
 
     // Per spec, sigma is exactly half the blur radius:
     // https://www.w3.org/TR/css-backgrounds-3/#shadow-blur
-    shadow_blur *= 0.5;
-    let mut sigma = Point::new(shadow_blur, shadow_blur);
-    // Apply scaling from the current transform matrix to blur radius, if there is any of either.
-    if self.state.matrix.get_type().contains(TypeMask::SCALE) && !almost_zero(shadow_blur) {
+    let sigma = self.local_radius(shadow_blur * 0.5);
+    let mut paint = base_paint.clone();
+    paint.set_image_filter(image_filters::drop_shadow_only((0.0, 0.0), (sigma.x, sigma.y), shadow_color, ColorSpace::new_srgb(), None, None));
+    Some(paint)
+  }
+
+  pub fn paint_for_box_shadow(&self, base_paint:&Paint, shadow:&Shadow) -> Option<Paint> {
+    if shadow.color.a() == 0{
+      return None
+    }
+
+    // outer shadows are offset by the caller's matrix, but inset shadows need to shift the
+    // shape's 'hole' from within the filter (where the offset is subject to the transform)
+    let offset = match shadow.inset{
+      true => self.state.matrix.invert()?.map_vector(shadow.offset),
+      false => Point::default(),
+    };
+    let sigma = self.local_radius(shadow.blur * 0.5);
+    let spread = self.local_radius(shadow.spread);
+    let mut paint = base_paint.clone();
+    paint.set_image_filter(shadow.filter(offset, sigma, spread, None));
+    Some(paint)
+  }
+
+  fn local_radius(&self, radius:f32) -> Point {
+    // shadow dimensions are in device pixels, so remove any scaling applied by the current transform
+    let mut local = Point::new(radius, radius);
+    // Apply scaling from the current transform matrix to the radius, if there is any of either.
+    if self.state.matrix.get_type().contains(TypeMask::SCALE) && !almost_zero(radius) {
       // Decompose the matrix to just the scaling factors (matrix.scale_x/y() methods just return M11/M22 values)
       if let Some(scale) = self.state.matrix.decompose_scale(None) {
         if almost_zero(scale.width) {
-          sigma.x = 0.0;
+          local.x = 0.0;
         } else {
-          sigma.x /= scale.width as f32;
+          local.x /= scale.width as f32;
         }
         if almost_zero(scale.height) {
-          sigma.y = 0.0;
+          local.y = 0.0;
         } else {
-          sigma.y /= scale.height as f32;
+          local.y /= scale.height as f32;
         }
       }
     }
-    let mut paint = base_paint.clone();
-    paint.set_image_filter(image_filters::drop_shadow_only((0.0, 0.0), (sigma.x, sigma.y), shadow_color, ColorSpace::new_srgb(), None, None));
-    Some(paint)
+    local
+  }
+
+}
+
+fn warp_point(grid:&[Point], cols:usize, u:f32, v:f32) -> Point{
+  // bilinear interpolation within whichever cell of the grid contains (u, v)
+  let rows = grid.len() / cols;
+  let x = u.clamp(0.0, 1.0) * (cols - 1) as f32;
+  let y = v.clamp(0.0, 1.0) * (rows - 1) as f32;
+  let (i, j) = ((x as usize).min(cols - 2), (y as usize).min(rows - 2));
+  let (s, t) = (x - i as f32, y - j as f32);
+
+  let (p00, p10) = (grid[j * cols + i], grid[j * cols + i + 1]);
+  let (p01, p11) = (grid[(j + 1) * cols + i], grid[(j + 1) * cols + i + 1]);
+  let top = p00 + (p10 - p00) * s;
+  let bottom = p01 + (p11 - p01) * s;
+  top + (bottom - top) * t
+}
+
+fn polyline_length(points:impl Iterator<Item=Point>) -> f32{
+  let points:Vec<Point> = points.collect();
+  points.windows(2).map(|pair| Point::distance(pair[0], pair[1])).sum()
+}
+
+fn painted_bounds(paint:&Paint, local:&Rect, matrix:&Matrix) -> Option<Rect>{
+  // a conservative estimate of the device-space area a paint will touch when filling or stroking
+  // the `local` rect, or None if a path effect or mask filter makes it impossible to predict
+  if paint.path_effect().is_some() || paint.mask_filter().is_some(){
+    return None
+  }
+
+  let mut bounds = *local;
+  if paint.style() != PaintStyle::Fill{
+    let half_width = paint.stroke_width().max(1.0) / 2.0;
+    let miter = match paint.stroke_join(){
+      PaintJoin::Miter => paint.stroke_miter().max(1.0),
+      _ => 1.0
+    };
+    let outset = half_width * miter.max(std::f32::consts::SQRT_2);
+    bounds.outset((outset, outset));
+  }
+
+  if let Some(filter) = paint.image_filter(){
+    if !filter.can_compute_fast_bounds(){
+      return None
+    }
+    bounds = filter.compute_fast_bounds(bounds);
   }
 
+  // leave room for antialiasing
+  Some(matrix.map_rect(bounds).0.with_outset((1.0, 1.0)))
+}
+
+//
+// Stroke alignment relative to the path's edge
+//
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum StrokeAlign{ Center, Inside, Outside }
+
+//
+// Antialiasing mode (with 'auto' reserved for curved & diagonal edges)
+//
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Antialias{ On, Off, Auto }
+
+fn is_rectilinear(path:&Path) -> bool{
+  // true if the path consists entirely of horizontal & vertical lines (including implicit closing segments)
+  path::Iter::new(path, true).all(|(verb, pts)| match verb{
+    Verb::Line => pts[0].x == pts[1].x || pts[0].y == pts[1].y,
+    Verb::Move | Verb::Close | Verb::Done => true,
+    _ => false
+  })
+}
+
+//
+// Stackable CSS-style box-shadows (drawn outside the shape or inset within it)
+//
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Shadow{
+  pub color: Color,
+  pub offset: Point,
+  pub blur: f32,
+  pub spread: f32,
+  pub inset: bool,
+}
+
+impl Shadow{
+  pub fn filter(&self, offset:Point, sigma:Point, spread:Point, input:Option<SkImageFilter>) -> Option<SkImageFilter>{
+    // positive spreads grow the silhouette and negative ones shrink it
+    let morph = |source:Option<SkImageFilter>|{
+      if spread.x > 0.0 || spread.y > 0.0 { image_filters::dilate((spread.x, spread.y), source, None) }
+      else if spread.x < 0.0 || spread.y < 0.0 { image_filters::erode((-spread.x, -spread.y), source, None) }
+      else { source }
+    };
+
+    if !self.inset{
+      return image_filters::drop_shadow_only(
+        offset, (sigma.x, sigma.y), self.color, ColorSpace::new_srgb(), morph(input.clone()), None
+      )
+    }
+
+    // an inset shadow is cast by the plane surrounding the shape: invert its alpha to find the
+    // 'hole', shift & blur it, then keep only the portion that falls within the original shape
+    let invert = color_filters::matrix_row_major(&[
+      0.0, 0.0, 0.0,  0.0, 0.0,
+      0.0, 0.0, 0.0,  0.0, 0.0,
+      0.0, 0.0, 0.0,  0.0, 0.0,
+      0.0, 0.0, 0.0, -1.0, 1.0
+    ], None);
+    let mut plane = image_filters::color_filter(invert, input.clone(), None);
+    plane = image_filters::offset(offset, plane, None);
+    plane = morph(plane); // growing the plane shrinks the hole (widening the shadow)
+    if sigma.x > 0.0 || sigma.y > 0.0{
+      plane = image_filters::blur((sigma.x, sigma.y), None, plane, None);
+    }
+    let tint = ColorFilter::blend(self.color, BlendMode::SrcIn)?;
+    let shadow = image_filters::color_filter(tint, plane, None);
+    image_filters::blend(BlendMode::SrcIn, input, shadow, None)
+  }
 }
 
 //
-// Dye abstraction for Color / CanvasGradient / CanvasPattern
+// Dye abstraction for Color / CanvasGradient / CanvasPattern / CanvasTexture / CanvasShader
 //
 
 #[derive(Clone)]
@@ -682,7 +1377,8 @@ pub enum Dye{
   Color(Color),
   Gradient(CanvasGradient),
   Pattern(CanvasPattern),
-  Texture(CanvasTexture)
+  Texture(CanvasTexture),
+  Shader(CanvasShader)
 }
 
 impl Dye{
@@ -693,6 +1389,8 @@ impl Dye{
       Some(Dye::Pattern(pattern.borrow().clone()) )
     }else if let Ok(texture) = value.downcast::<BoxedCanvasTexture, _>(cx){
       Some(Dye::Texture(texture.borrow().clone()) )
+    }else if let Ok(shader) = value.downcast::<BoxedCanvasShader, _>(cx){
+      Some(Dye::Shader(shader.borrow().clone()) )
     }else{
       color_in(cx, value).map(Dye::Color)
     }
@@ -711,10 +1409,11 @@ impl Dye{
       Dye::Gradient(gradient) => gradient.is_opaque(),
       Dye::Pattern(pattern) => pattern.is_opaque(),
       Dye::Texture(_) => false,
+      Dye::Shader(_) => false,
     }
   }
 
-  pub fn mix_into(&self, paint: &mut Paint, alpha: f32, image_filter: ImageFilter){
+  pub fn mix_into(&self, paint: &mut Paint, alpha: f32, image_filter: ImageFilter, matrix: &Matrix){
     match self {
       Dye::Color(color) => {
         let mut color = Color4f::from(*color);
@@ -722,16 +1421,22 @@ impl Dye{
         paint.set_color(color.to_color());
       },
       Dye::Gradient(gradient) =>{
-        paint.set_shader(gradient.shader())
+        let scale = matrix.decompose_scale(None).map(|size| size.width.max(size.height)).unwrap_or(1.0);
+        paint.set_shader(gradient.shader_at_scale(scale))
+             .set_dither(gradient.dither())
              .set_alpha_f(alpha);
       },
       Dye::Pattern(pattern) =>{
-        paint.set_shader(pattern.shader(image_filter))
+        paint.set_shader(pattern.shader_at_scale(image_filter, sampling_scale(matrix)))
              .set_alpha_f(alpha);
       }
       Dye::Texture(texture) =>{
         paint.set_color(texture.to_color(alpha));
       }
+      Dye::Shader(shader) =>{
+        paint.set_shader(shader.shader())
+             .set_alpha_f(alpha);
+      }
     };
   }
 }
@@ -1,17 +1,20 @@
 use std::fs;
+use std::io::Write;
 use std::path::Path as FilePath;
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::{Arc, OnceLock};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock, mpsc};
 use rayon::prelude::*;
 use neon::prelude::*;
 use skia_safe::{
-  svg::{self, canvas::Flags},
+  prelude::NativeAccess, svg::{self, canvas::Flags},
   image::{BitDepth, CachingHint}, images, pdf,
   Canvas as SkCanvas, ClipOp, Color, ColorSpace, ColorType, AlphaType, Document, Surface,
-  Image as SkImage, ImageInfo, Matrix, Path, Picture, PictureRecorder, Rect, IRect, Size, ISize,
+  Image as SkImage, ImageInfo, Matrix, M44, Path, Paint, Picture, PictureRecorder, Rect, IRect, Size, ISize,
+  Region, region::RegionOp, BlendMode, Shader,
   SurfaceProps, SurfacePropsFlags, PixelGeometry, jpeg_encoder, png_encoder, webp_encoder
 };
 use dashmap::DashMap;
+use serde_json::{json, Value};
 use little_exif::{metadata::Metadata, exif_tag::ExifTag, filetype::FileExtension};
 use crc::{Crc, CRC_32_ISO_HDLC};
 const CRC32: Crc<u32> = Crc::<u32>::new(&CRC_32_ISO_HDLC);
@@ -19,6 +22,7 @@ const CRC32: Crc<u32> = Crc::<u32>::new(&CRC_32_ISO_HDLC);
 use crate::canvas::BoxedCanvas;
 use crate::context::BoxedContext2D;
 use crate::gpu::RenderingEngine;
+use crate::utils::{opt_object_arg, opt_string_for_key, opt_float_for_key, opt_color_for_key};
 
 static CACHE: OnceLock<Arc<DashMap<usize, PageCache>>> = OnceLock::new();
 
@@ -29,10 +33,17 @@ static CACHE: OnceLock<Arc<DashMap<usize, PageCache>>> = OnceLock::new();
 
 pub struct PageRecorder{
   current: PictureRecorder,
+  groups: Vec<LayerGroup>,
   layers: Vec<Picture>,
+  extent: Rect,
+  extents: Vec<Rect>,
+  backdrop: bool,
+  backdrops: Vec<bool>,
   bounds: Rect,
-  matrix: Matrix,
+  matrix: M44,
   clip: Option<Path>,
+  clip_antialias: bool,
+  mask: Option<Shader>,
   surface: RecordingSurface,
   changed: bool,
   id: usize,
@@ -48,17 +59,87 @@ impl PageRecorder{
     rec.begin_recording(bounds, true).save(); // start at depth 2
 
     PageRecorder{
-      current:rec, layers:vec![], changed:false, matrix:Matrix::default(), clip:None, bounds, id,
-      surface:RecordingSurface::default(),
+      current:rec, groups:vec![], layers:vec![], changed:false, matrix:M44::default(), clip:None, clip_antialias:true, mask:None, bounds, id,
+      extent:Rect::new_empty(), extents:vec![], backdrop:false, backdrops:vec![], surface:RecordingSurface::default(),
     }
   }
 
   pub fn append<F>(&mut self, f:F)
     where F:FnOnce(&SkCanvas)
   {
-    if let Some(canvas) = self.current.recording_canvas() {
-      f(canvas);
-      self.changed = true;
+    self.append_within(None, f);
+  }
+
+  pub fn append_within<F>(&mut self, area:Option<Rect>, f:F)
+    where F:FnOnce(&SkCanvas)
+  {
+    match self.canvas(){
+      Some(canvas) => f(canvas),
+      None => return
+    }
+
+    // keep track of the region affected by the current layer (assume it's the whole page if unspecified)
+    let area = area.unwrap_or(self.bounds);
+    match self.groups.last_mut(){
+      // content drawn within a beginLayer() group doesn't affect the page until the group is closed
+      Some(group) => group.extent.join(area),
+      None => {
+        self.extent.join(area);
+        self.changed = true;
+      }
+    }
+  }
+
+  pub fn append_backdrop<F>(&mut self, area:Option<Rect>, f:F)
+    where F:FnOnce(&SkCanvas)
+  {
+    // note that the current layer reads back pixels drawn before it (which matters when getImageData
+    // only replays part of the page). Within a beginLayer() group, it will only see the group's contents
+    if self.groups.is_empty(){
+      self.backdrop = true;
+    }
+    self.append_within(area, f);
+  }
+
+  fn canvas(&mut self) -> Option<&SkCanvas>{
+    match self.groups.last_mut(){
+      Some(group) => group.recorder.recording_canvas(),
+      None => self.current.recording_canvas()
+    }
+  }
+
+  pub fn begin_layer(&mut self, paint:Paint){
+    let mut recorder = PictureRecorder::new();
+    recorder.begin_recording(self.bounds, true).save();
+    let (clip, mask) = (self.clip.clone(), self.mask.clone());
+    self.groups.push(LayerGroup{ recorder, paint, extent:Rect::new_empty(), clip, mask });
+    self.restore(); // carry over the current transform
+  }
+
+  pub fn end_layer(&mut self){
+    let LayerGroup{ mut recorder, paint, extent, .. } = match self.groups.pop(){
+      Some(group) => group,
+      None => return
+    };
+    self.restore(); // sync the enclosing canvas with the state (including the clip & mask) at beginLayer()
+
+    if let Some(picture) = recorder.finish_recording_as_picture(None){
+      // blend modes that affect the backdrop outside of the group's content touch the whole page
+      let area = match (paint.as_blend_mode(), paint.image_filter()){
+        (Some(BlendMode::SrcOver), None) => Some(extent),
+        (Some(BlendMode::SrcOver), Some(filter)) if filter.can_compute_fast_bounds() => {
+          Some(filter.compute_fast_bounds(extent))
+        },
+        _ => None
+      };
+
+      // the group was recorded in device coordinates, so composite it without the current transform
+      self.append_within(area, |canvas|{
+        canvas.save();
+        canvas.reset_matrix();
+        canvas.draw_picture(&picture, None, Some(&paint));
+        canvas.restore();
+      });
     }
   }
 
@@ -70,26 +151,48 @@ impl PageRecorder{
     self.bounds = bounds; // non-destructively update the size
   }
 
-  pub fn set_matrix(&mut self, matrix:Matrix){
-    self.matrix = matrix;
-    if let Some(canvas) = self.current.recording_canvas() {
-      canvas.set_matrix(&matrix.into());
+  pub fn set_matrix(&mut self, matrix:&M44){
+    self.matrix = matrix.clone();
+    if let Some(canvas) = self.canvas() {
+      canvas.set_matrix(matrix);
     }
   }
 
-  pub fn set_clip(&mut self, clip:&Option<Path>){
+  pub fn set_clip(&mut self, clip:&Option<Path>, antialias:bool){
     self.clip = clip.clone();
+    self.clip_antialias = antialias;
+    self.restore();
+  }
+
+  pub fn set_mask(&mut self, mask:&Option<Shader>){
+    self.mask = mask.clone();
     self.restore();
   }
 
   pub fn restore(&mut self){
-    if let Some(canvas) = self.current.recording_canvas() {
+    let (clip, antialias, mask, matrix) = (self.clip.clone(), self.clip_antialias, self.mask.clone(), self.matrix.clone());
+
+    // within a beginLayer() group, the inherited clip & mask are applied when the group is composited
+    // (applying them to its contents as well would count antialiased edges and mask coverage twice)
+    let (clip, mask) = match self.groups.last(){
+      Some(group) => (
+        clip.filter(|clip| group.clip.as_ref() != Some(clip)),
+        mask.filter(|mask| !group.mask.as_ref().is_some_and(|inherited| std::ptr::eq(inherited.native(), mask.native()))),
+      ),
+      None => (clip, mask)
+    };
+
+    if let Some(canvas) = self.canvas() {
       canvas.restore_to_count(1);
       canvas.save();
-      if let Some(clip) = &self.clip{
-        canvas.clip_path(clip, ClipOp::Intersect, true /* antialias */);
+      if let Some(clip) = &clip{
+        canvas.clip_path(clip, ClipOp::Intersect, antialias);
+      }
+      if let Some(mask) = mask{
+        // masks are pre-transformed to device coordinates
+        canvas.clip_shader(mask, ClipOp::Intersect);
       }
-      canvas.set_matrix(&self.matrix.into());
+      canvas.set_matrix(&matrix);
     }
   }
 
@@ -102,7 +205,7 @@ impl PageRecorder{
     }
 
     let page = self.get_page();
-    self.surface.update(&page, &opts, &engine);
+    self.surface.update(&page, &opts, &engine, crop);
 
     match self.surface.copy_pixels(&dst_info, crop, &mut dst_buffer){
       true => Ok(dst_buffer),
@@ -119,18 +222,24 @@ impl PageRecorder{
           let mut wrapper = PictureRecorder::new();
           wrapper.begin_recording(self.bounds, true).draw_drawable(&mut drawable, None);
           wrapper.finish_recording_as_picture(None)
-        }).map(|pict|
-          self.layers.push(pict)
-        );
+        }).map(|pict|{
+          self.layers.push(pict);
+          self.extents.push(self.extent);
+          self.backdrops.push(self.backdrop);
+        });
 
       // resume recording
       self.current.begin_recording(self.bounds, true);
+      self.extent = Rect::new_empty();
+      self.backdrop = false;
       self.changed = false;
       self.restore();
     }
 
     Page{
       layers: self.layers.clone(),
+      extents: self.extents.clone(),
+      backdrops: self.backdrops.clone(),
       bounds: self.bounds,
       id: self.id,
     }
@@ -160,6 +269,15 @@ impl PageRecorder{
   }
 }
 
+// Contents of a beginLayer() call (composited onto the page as a single unit by endLayer)
+struct LayerGroup{
+  recorder: PictureRecorder,
+  paint: Paint,
+  extent: Rect,
+  clip: Option<Path>,
+  mask: Option<Shader>,
+}
+
 impl Drop for PageRecorder{
   fn drop(&mut self) {
     PageCache::drop(self.id);
@@ -174,6 +292,7 @@ impl Drop for PageRecorder{
 pub struct RecordingSurface{
   surface: Option<Surface>,
   depth: usize,
+  stale: Region,
   matte: Option<Color>,
   msaa: Option<usize>,
   gpu: Option<bool>,
@@ -183,7 +302,7 @@ pub struct RecordingSurface{
 
 impl Default for RecordingSurface{
   fn default() -> Self {
-      Self{surface:None, depth:0, matte:None, msaa:None, gpu:None, color_space:ColorSpace::new_srgb(), density:0.0}
+      Self{surface:None, depth:0, stale:Region::new(), matte:None, msaa:None, gpu:None, color_space:ColorSpace::new_srgb(), density:0.0}
   }
 }
 
@@ -206,7 +325,7 @@ impl RecordingSurface{
     self.color_space != opts.color_space
   }
 
-  pub fn update(&mut self, page:&Page, opts:&ExportOptions, engine:&RenderingEngine){
+  pub fn update(&mut self, page:&Page, opts:&ExportOptions, engine:&RenderingEngine, crop:IRect){
     // check for anything that would invalidate the previous contents
     let reconfigure = self.is_config_stale(&opts);
     let recreate = self.is_surface_stale(&page, &opts, &engine);
@@ -230,31 +349,80 @@ impl RecordingSurface{
 
     if let Some(surface) = self.surface.as_mut(){
       let canvas = surface.canvas();
+      let matte = self.matte.unwrap_or(Color::TRANSPARENT);
       let (cache_image, cache_depth) = PageCache::get(page.id, &opts, page.depth());
 
-      if let Some(image) = cache_image{
-        // use the cached bitmap as the background (if present)
-        canvas.draw_image(image, (0,0), None);
-        self.depth = cache_depth;
-      }else if self.depth==0 {
+      match cache_image{
+        // use the cached bitmap as the background (if it's more current than the surface)
+        Some(image) if cache_depth > self.depth || (cache_depth == self.depth && !self.stale.is_empty()) => {
+          let mut paint = Paint::default();
+          paint.set_blend_mode(BlendMode::Src);
+          canvas.draw_image(image, (0,0), Some(&paint));
+          self.depth = cache_depth;
+          self.stale.set_empty();
+        },
         // otherwise, fill the canvas if requested
-        canvas.clear(self.matte.unwrap_or(Color::TRANSPARENT));
+        _ if self.depth==0 => {
+          canvas.clear(matte);
+          self.stale.set_empty();
+        },
+        _ => {}
       }
 
+      // the surface is up to date everywhere except within the `stale` region and the bounds
+      // of any newly added layers
+      let scale = Matrix::scale((self.density, self.density));
+      let mut dirty = self.stale.clone();
+      for extent in page.extents.iter().skip(self.depth){
+        dirty.op_rect(scale.map_rect(extent).0.round_out(), RegionOp::Union);
+      }
 
-      // only add new layers to surface
-      canvas.scale((self.density, self.density));
+      // only redraw the portion of the dirty region that's actually being read
+      let mut redraw = dirty.clone();
+      redraw.op_rect(crop, RegionOp::Intersect);
+
+      // layers that filter their backdrop read pixels beyond the crop, so the full extent of any that
+      // will be replayed needs to be brought up to date (which may in turn pull in more layers)
+      let mut expanded = true;
+      while expanded{
+        expanded = false;
+        let first_layer = if self.stale.intersects_region(&redraw){ 0 }else{ self.depth };
+        for (extent, _) in page.extents.iter().zip(page.backdrops.iter()).skip(first_layer).filter(|(_, bd)| **bd){
+          let extent = scale.map_rect(extent).0.round_out();
+          if redraw.intersects_rect(extent) && !redraw.contains_rect(extent){
+            redraw.op_rect(extent, RegionOp::Union);
+            expanded = true;
+          }
+        }
+      }
 
-      // draw newly added layers
-      for pict in page.layers.iter().skip(self.depth){
-        pict.playback(canvas);
+      if !redraw.is_empty(){
+        // if the pixels being redrawn include some that predate the current depth, rebuild the
+        // region from scratch, otherwise just add the new layers on top
+        canvas.save();
+        canvas.clip_region(&redraw, ClipOp::Intersect);
+        let first_layer = match self.stale.intersects_region(&redraw){
+          true => { canvas.clear(matte); 0 },
+          false => self.depth
+        };
+
+        // replay only the layers that touch the region
+        canvas.scale((self.density, self.density));
+        page.layers.iter().zip(page.extents.iter())
+          .skip(first_layer)
+          .filter(|(_, extent)| redraw.intersects_rect(scale.map_rect(*extent).0.round_out()))
+          .for_each(|(pict, _)| pict.playback(canvas));
+        canvas.restore();
       }
-      self.depth = page.layers.len();
+
+      dirty.op_region(&redraw, RegionOp::Difference);
+      self.stale = dirty;
+      self.depth = page.depth();
     }
   }
 
   pub fn snapshot_if_valid(&mut self, page:&Page, opts:&ExportOptions, engine:&RenderingEngine) -> Option<SkImage>{
-    match !(self.is_config_stale(&opts) || self.is_surface_stale(&page, &opts, &engine) || self.depth==0){
+    match !(self.is_config_stale(&opts) || self.is_surface_stale(&page, &opts, &engine) || self.depth==0 || !self.stale.is_empty()){
       true => self.surface.as_mut().map(|surface| surface.image_snapshot()),
       false => None,
     }
@@ -277,6 +445,8 @@ pub struct Page{
   pub id: usize,
   pub bounds: Rect,
   pub layers: Vec<Picture>,
+  pub extents: Vec<Rect>,
+  pub backdrops: Vec<bool>,
 }
 
 impl PartialEq for Page {
@@ -288,7 +458,7 @@ impl PartialEq for Page {
 
 impl Default for Page {
   fn default() -> Self {
-    Self{ id:0, bounds: skia_safe::Rect::new_empty(), layers:vec![] }
+    Self{ id:0, bounds: skia_safe::Rect::new_empty(), layers:vec![], extents:vec![], backdrops:vec![] }
   }
 }
 
@@ -309,10 +479,11 @@ impl Page{
     compositor.finish_recording_as_picture(None)
   }
 
-  pub fn encoded_as(&self, options:ExportOptions, engine:RenderingEngine) -> Result<Vec<u8>, String> {
+  pub fn encoded_as(&self, options:ExportOptions, engine:RenderingEngine, task:&ExportTask) -> Result<Vec<u8>, String> {
     if self.bounds.is_empty(){
       return Err("Width and height must be non-zero to generate an image".to_string())
     }
+    task.check()?;
 
     let ExportOptions{ ref format, quality, density, matte, color_type, .. } = options;
     let size = self.bounds.size();
@@ -356,8 +527,10 @@ impl Page{
         // draw newly added layers and cache the full-canvas bitmap
         canvas.set_matrix(&img_scale);
         for pict in self.layers.iter().skip(cache_depth){
+          task.check()?;
           pict.playback(canvas);
         }
+        task.check()?;
 
         // extract the results
         let context = &mut surface.direct_context();
@@ -463,14 +636,27 @@ impl Page{
     }
   }
 
-  pub fn write(&self, filename: &str, options:ExportOptions, engine:RenderingEngine) -> Result<(), String> {
+  pub fn write(&self, filename: &str, options:ExportOptions, engine:RenderingEngine, task:&ExportTask) -> Result<(), String> {
     let path = FilePath::new(&filename);
-    let data = self.encoded_as(options, engine)?;
+    let data = self.encoded_as(options, engine, task)?;
+    create_parent_dirs(path)?;
     fs::write(path, data).map_err(|why|
       format!("{}: \"{}\"", why, path.display())
     )
   }
 
+  pub fn filename(&self, pattern:&str, index:usize, padding:usize, options:&ExportOptions, label:&str) -> String{
+    // the label is substituted last so its contents won't be mistaken for tokens
+    pattern
+      .replace("{}", &format!("{:0width$}", index+1, width=padding))
+      .replace("{index}", &format!("{:0width$}", index, width=padding))
+      .replace("{width}", &self.bounds.width().to_string())
+      .replace("{height}", &self.bounds.height().to_string())
+      .replace("{density}", &options.density.to_string())
+      .replace("{format}", &options.format)
+      .replace("{label}", label)
+  }
+
   fn append_to<'a>(&self, doc:Document<'a>, matte:Option<Color>) -> Result<Document<'a>, String>{
     if !self.bounds.is_empty(){
       let mut doc = doc.begin_page(self.bounds.size(), None);
@@ -514,22 +700,49 @@ impl PageSequence{
     }
   }
 
-  pub fn as_pdf(&self, options:ExportOptions) -> Result<Vec<u8>, String>{
-    let ExportOptions{ quality, density, matte, .. } = options;
+  pub fn materialize_each(&mut self, engine:&RenderingEngine, per_page:&[PageOptions]){
+    for (page, PageOptions{options, ..}) in self.pages.iter_mut().zip(per_page){
+      if options.is_raster(){
+        PageCache::materialize(page.id, &engine, &options);
+      }
+    }
+  }
+
+  pub fn as_pdf(&self, options:ExportOptions, task:&ExportTask) -> Result<Vec<u8>, String>{
     let mut pdf_bytes = Vec::new();
+    self.write_pdf_to(&mut pdf_bytes, options, task)?;
+    Ok(pdf_bytes)
+  }
+
+  pub fn write_pdf_to(&self, buffer:&mut impl Write, options:ExportOptions, task:&ExportTask) -> Result<(), String>{
+    // pages are written to the buffer as they're completed (rather than all at once when the document is closed)
+    let ExportOptions{ quality, density, matte, .. } = options;
     self.pages
       .iter()
-      .try_fold(pdf_document(&mut pdf_bytes, quality, density), |doc, page| page.append_to(doc, matte))
-      .map(|doc| doc.close())?;
-    Ok(pdf_bytes)
+      .try_fold(pdf_document(buffer, quality, density), |doc, page|{
+        task.check()?;
+        let doc = page.append_to(doc, matte)?;
+        task.advance();
+        Ok::<_, String>(doc)
+      })
+      .map(|doc| doc.close())
+  }
+
+  pub fn as_image(&self, options:ExportOptions, task:&ExportTask) -> Result<Vec<u8>, String>{
+    let data = self.first().encoded_as(options, self.engine, task)?;
+    task.advance();
+    Ok(data)
   }
 
-  pub fn write_image(&self, pattern:&str, options:ExportOptions) -> Result<(), String>{
-    self.first().write(pattern, options, self.engine)
+  pub fn write_image(&self, pattern:&str, options:ExportOptions, task:&ExportTask) -> Result<(), String>{
+    let filename = self.first().filename(pattern, 0, 1, &options, "");
+    self.first().write(&filename, options, self.engine, task)?;
+    task.advance();
+    Ok(())
   }
 
   #[allow(clippy::too_many_arguments)]
-  pub fn write_sequence(&self, pattern:&str, padding:f32, options:ExportOptions) -> Result<(), String>{
+  pub fn write_sequence(&self, pattern:&str, padding:f32, per_page:Vec<PageOptions>, task:&ExportTask) -> Result<(), String>{
     let padding = match padding as i32{
       -1 => (1.0 + (self.pages.len() as f32).log10().floor()) as usize,
       pad => pad as usize
@@ -537,17 +750,21 @@ impl PageSequence{
 
     self.pages
       .par_iter()
+      .zip(per_page.into_par_iter())
       .enumerate()
-      .try_for_each(|(pp, page)|{
-        let folio = format!("{:0width$}", pp+1, width=padding);
-        let filename = pattern.replace("{}", folio.as_str());
-        page.write(&filename, options.clone(), self.engine)
+      .try_for_each(|(pp, (page, PageOptions{options, label}))|{
+        let filename = page.filename(pattern, pp, padding, &options, &label);
+        page.write(&filename, options, self.engine, task)?;
+        task.advance();
+        Ok(())
       })
   }
 
-  pub fn write_pdf(&self, path:&str, options:ExportOptions) -> Result<(), String>{
-    let path = FilePath::new(&path);
-    match self.as_pdf(options){
+  pub fn write_pdf(&self, pattern:&str, options:ExportOptions, task:&ExportTask) -> Result<(), String>{
+    let filename = self.first().filename(pattern, 0, 1, &options, "");
+    let path = FilePath::new(&filename);
+    create_parent_dirs(path)?;
+    match self.as_pdf(options, task){
       Ok(document) => fs::write(path, document).map_err(|why|
         format!("{}: \"{}\"", why, path.display())
       ),
@@ -556,22 +773,136 @@ impl PageSequence{
   }
 }
 
+//
+// Cancellation token & progress reporting for exports
+//
+
+pub type BoxedExportTask = JsBox<ExportTask>;
+impl Finalize for ExportTask {}
+
+#[derive(Clone, Default)]
+pub struct ExportTask{
+  cancelled: Arc<AtomicBool>,
+  done: Arc<AtomicUsize>,
+  total: usize,
+  listener: Option<(Channel, Arc<Root<JsFunction>>)>,
+}
+
+impl ExportTask{
+  pub fn watch(&mut self, channel:Channel, callback:Option<Root<JsFunction>>, total:usize){
+    // each export gets its own page count (but shares the cancellation flag with the original)
+    self.done = Arc::new(AtomicUsize::new(0));
+    self.total = total;
+    self.listener = callback.map(|callback| (channel, Arc::new(callback)));
+  }
+
+  pub fn cancel(&self){
+    self.cancelled.store(true, Ordering::Relaxed);
+  }
+
+  pub fn check(&self) -> Result<(), String>{
+    match self.cancelled.load(Ordering::Relaxed){
+      true => Err("Export was cancelled".to_string()),
+      false => Ok(())
+    }
+  }
+
+  pub fn advance(&self){
+    let done = self.done.fetch_add(1, Ordering::Relaxed) + 1;
+    if let Some((channel, callback)) = &self.listener{
+      let (callback, total) = (callback.clone(), self.total);
+      channel.send(move |mut cx| {
+        let done = cx.number(done as f64);
+        let total = cx.number(total as f64);
+        callback.to_inner(&mut cx).call_with(&cx).arg(done).arg(total).exec(&mut cx)
+      });
+    }
+  }
+}
+
+//
+// Output adaptor that passes encoded data to a JS callback in chunks
+//
+
+pub struct ChunkWriter{
+  channel: Channel,
+  sink: Arc<Root<JsFunction>>,
+}
+
+impl ChunkWriter{
+  pub fn new(channel:Channel, sink:Root<JsFunction>) -> Self{
+    Self{channel, sink:Arc::new(sink)}
+  }
+}
+
+impl Write for ChunkWriter{
+  fn write(&mut self, buf:&[u8]) -> std::io::Result<usize>{
+    // block the encoder until js has accepted the chunk so slow destinations apply backpressure
+    let (ready_tx, ready_rx) = mpsc::sync_channel::<bool>(1);
+    let failed_tx = ready_tx.clone();
+    let (chunk, sink) = (buf.to_vec(), self.sink.clone());
+    self.channel.send(move |mut cx| {
+      let buffer = JsBuffer::from_slice(&mut cx, &chunk)?;
+      let ready = JsFunction::new(&mut cx, move |mut cx| {
+        let ok = match cx.argument_opt(0).and_then(|arg| arg.downcast::<JsBoolean, _>(&mut cx).ok()){
+          Some(flag) => flag.value(&mut cx),
+          None => true
+        };
+        let _ = ready_tx.try_send(ok);
+        Ok(cx.undefined())
+      })?;
+      let result = sink.to_inner(&mut cx).call_with(&cx).arg(buffer).arg(ready).exec(&mut cx);
+      if result.is_err(){ let _ = failed_tx.try_send(false); }
+      result
+    });
+
+    match ready_rx.recv(){
+      Ok(true) => Ok(buf.len()),
+      _ => Err(std::io::Error::new(std::io::ErrorKind::BrokenPipe, "The destination stopped accepting data"))
+    }
+  }
+
+  fn flush(&mut self) -> std::io::Result<()>{
+    Ok(())
+  }
+}
+
+pub fn export_task_arg(cx: &mut FunctionContext, idx:usize, total:usize) -> NeonResult<ExportTask> {
+  let mut task = match cx.argument_opt(idx).and_then(|arg| arg.downcast::<BoxedExportTask, _>(cx).ok()){
+    Some(task) => ExportTask::clone(&task),
+    None => ExportTask::default()
+  };
+  let callback = cx.argument_opt(idx + 1)
+    .and_then(|arg| arg.downcast::<JsFunction, _>(cx).ok())
+    .map(|callback| callback.root(cx));
+  let channel = cx.channel();
+  task.watch(channel, callback, total);
+  Ok(task)
+}
+
 //
 // Cache for the last bitmap generated by a given Page
 //
 
+static CACHE_LIMIT: AtomicUsize = AtomicUsize::new(usize::MAX);
+static CACHE_CLOCK: AtomicUsize = AtomicUsize::new(1);
+static CACHE_HITS: AtomicUsize = AtomicUsize::new(0);
+static CACHE_MISSES: AtomicUsize = AtomicUsize::new(0);
+
 #[derive(Debug, Clone)]
-struct PageCache{
+pub struct PageCache{
   image: Option<SkImage>,
   density: f32,
   matte: Option<Color>,
   msaa: Option<usize>,
   depth: usize,
+  bytes: usize,
+  last_used: usize,
 }
 
 impl Default for PageCache{
   fn default() -> Self {
-    Self{image:None, depth:0, density:1.0, matte:None, msaa:None}
+    Self{image:None, depth:0, density:1.0, matte:None, msaa:None, bytes:0, last_used:0}
   }
 }
 
@@ -589,22 +920,34 @@ impl PageCache{
   }
 
   pub fn get(id:usize, opts:&ExportOptions, depth:usize) -> (Option<SkImage>, usize){
-    Self::shared().get(&id).map(|cache|{
+    let found = Self::shared().get_mut(&id).and_then(|mut cache|{
       match cache.is_valid(opts) && depth >= cache.depth{
-        true => (cache.image.clone(), cache.depth),
-        false => (None, 0)
+        true => {
+          cache.last_used = CACHE_CLOCK.fetch_add(1, Ordering::Relaxed);
+          Some((cache.image.clone(), cache.depth))
+        },
+        false => None
       }
-    })
-    .unwrap_or((None, 0))
+    });
+
+    match found{
+      Some(hit) => { CACHE_HITS.fetch_add(1, Ordering::Relaxed); hit },
+      None => { CACHE_MISSES.fetch_add(1, Ordering::Relaxed); (None, 0) }
+    }
   }
 
   pub fn set(id:usize, image:SkImage, opts:&ExportOptions, depth:usize){
     Self::shared().get_mut(&id).map(|mut cache|{
       // save the bitmap if it's newer than the cached version, or is replacing an invaildated cache
       if !cache.is_valid(opts) || depth > cache.depth{
-        *cache = Self{ image:Some(image), density:opts.density, matte:opts.matte, msaa:opts.msaa, depth}
+        let bytes = image.image_info().compute_min_byte_size();
+        let last_used = CACHE_CLOCK.fetch_add(1, Ordering::Relaxed);
+        *cache = Self{ image:Some(image), density:opts.density, matte:opts.matte, msaa:opts.msaa, depth, bytes, last_used}
       }
     });
+
+    // make room for the new bitmap by discarding the least recently used ones
+    Self::enforce_limit(Some(id));
   }
 
   pub fn materialize(id:usize, engine:&RenderingEngine, options:&ExportOptions){
@@ -624,6 +967,68 @@ impl PageCache{
     });
   }
 
+  pub fn limit() -> usize{
+    CACHE_LIMIT.load(Ordering::Relaxed)
+  }
+
+  pub fn set_limit(bytes:usize){
+    CACHE_LIMIT.store(bytes, Ordering::Relaxed);
+    Self::enforce_limit(None);
+  }
+
+  pub fn purge(){
+    Self::shared().iter_mut().for_each(|mut cache| cache.evict());
+  }
+
+  pub fn stats() -> Value{
+    let (mut gpu, mut cpu) = ((0, 0), (0, 0));
+    for cache in Self::shared().iter(){
+      if let Some(img) = &cache.image{
+        let tally = if img.is_texture_backed(){ &mut gpu }else{ &mut cpu };
+        *tally = (tally.0 + 1, tally.1 + cache.bytes);
+      }
+    }
+
+    let limit = match Self::limit(){
+      usize::MAX => Value::Null,
+      bytes => json!(bytes)
+    };
+
+    json!({
+      "entries": gpu.0 + cpu.0,
+      "bytes": gpu.1 + cpu.1,
+      "limit": limit,
+      "hits": CACHE_HITS.load(Ordering::Relaxed),
+      "misses": CACHE_MISSES.load(Ordering::Relaxed),
+      "gpu": {"entries": gpu.0, "bytes": gpu.1},
+      "cpu": {"entries": cpu.0, "bytes": cpu.1},
+    })
+  }
+
+  fn enforce_limit(keep:Option<usize>){
+    let limit = Self::limit();
+    let shared = Self::shared();
+    loop{
+      let total:usize = shared.iter().map(|cache| cache.bytes).sum();
+      if total <= limit { break }
+
+      // the page that was just rendered is exempt (even if it alone exceeds the budget)
+      let oldest = shared.iter()
+        .filter(|cache| cache.image.is_some() && Some(*cache.key()) != keep)
+        .min_by_key(|cache| cache.last_used)
+        .map(|cache| *cache.key());
+
+      match oldest{
+        Some(id) => { shared.get_mut(&id).map(|mut cache| cache.evict()); },
+        None => break
+      }
+    }
+  }
+
+  fn evict(&mut self){
+    *self = Self::default();
+  }
+
   #[cfg(not(any(feature="metal", feature="vulkan")))]
   fn _blit<'a>( &self, _surface: &mut Surface, dst_info: &ImageInfo, src: IRect, pixels: &mut [u8], ) -> Option<bool>{
     self.image.as_ref().map(|image| image.read_pixels(
@@ -664,7 +1069,43 @@ pub fn pages_arg(cx: &mut FunctionContext, idx:usize, opts:&ExportOptions, canva
   Ok(PageSequence::from(pages, engine))
 }
 
-fn pdf_document(buffer:&mut impl std::io::Write, quality:f32, density:f32) -> Document<'_>{
+pub fn page_options_arg(cx: &mut FunctionContext, idx:usize, options:&ExportOptions, count:usize) -> NeonResult<Vec<PageOptions>> {
+  // start from the shared options then apply any per-page overrides
+  let mut per_page = vec![PageOptions{ options:options.clone(), label:String::new() }; count];
+  let overrides = opt_object_arg(cx, idx)
+    .and_then(|opts| opts.get::<JsValue, _, _>(cx, "perPage").ok())
+    .and_then(|val| val.downcast::<JsArray, _>(cx).ok());
+
+  if let Some(overrides) = overrides{
+    for (entry, page) in overrides.to_vec(cx)?.iter().zip(per_page.iter_mut()){
+      let Ok(entry) = entry.downcast::<JsObject, _>(cx) else { continue };
+      if let Some(format) = opt_string_for_key(cx, &entry, "format"){
+        page.options.format = format;
+      }
+      if let Some(density) = opt_float_for_key(cx, &entry, "density"){
+        page.options.density = density;
+      }
+      if let Some(matte) = opt_color_for_key(cx, &entry, "matte"){
+        page.options.matte = Some(matte);
+      }
+      if let Some(label) = opt_string_for_key(cx, &entry, "label"){
+        page.label = label;
+      }
+    }
+  }
+  Ok(per_page)
+}
+
+fn create_parent_dirs(path:&FilePath) -> Result<(), String>{
+  match path.parent(){
+    Some(dir) => fs::create_dir_all(dir).map_err(|why|
+      format!("{}: \"{}\"", why, dir.display())
+    ),
+    None => Ok(())
+  }
+}
+
+fn pdf_document(buffer:&mut impl Write, quality:f32, density:f32) -> Document<'_>{
   pdf::new_document(buffer, Some(&pdf::Metadata {
     producer: "Skia Canvas <https://skia-canvas.org>".to_string(),
     encoding_quality: Some((quality*100.0) as i32),
@@ -673,6 +1114,12 @@ fn pdf_document(buffer:&mut impl std::io::Write, quality:f32, density:f32) -> Do
   }))
 }
 
+#[derive(Clone, Debug)]
+pub struct PageOptions{
+  pub options: ExportOptions,
+  pub label: String,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct ExportOptions{
   pub format: String,
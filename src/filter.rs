@@ -3,16 +3,27 @@
 #![allow(unused_variables)]
 #![allow(dead_code)]
 use std::fmt;
-use skia_safe::{Paint, Matrix, Point, Color, MaskFilter, ImageFilter as SkImageFilter,
-                BlurStyle, FilterMode, MipmapMode, SamplingOptions, TileMode, ColorSpace,
-                image_filters, color_filters, table_color_filter};
+use std::collections::HashMap;
+use serde::Deserialize;
+use std::cell::RefCell;
+use std::sync::Mutex;
+use neon::prelude::*;
+use skia_safe::{Paint, Matrix, Point, Point3, Rect, IRect, Size, Color, Shader, MaskFilter, ImageFilter as SkImageFilter,
+                Image, ImageInfo, Data, images, image::CachingHint,
+                BlurStyle, FilterMode, MipmapMode, SamplingOptions, CubicResampler, TileMode, ColorSpace, ColorChannel,
+                BlendMode, image_filters::{self, CropRect}, color_filters, shaders, table_color_filter};
 
 use crate::utils::*;
+use crate::shader::{CanvasShader, BoxedCanvasShader};
+use crate::gradient::BoxedCanvasGradient;
+use crate::pattern::BoxedCanvasPattern;
 
 #[derive(Clone, Debug)]
 pub enum FilterSpec{
   Plain{name:String, value:f32},
   Shadow{offset:Point, blur:f32, color:Color},
+  Shader(CanvasShader),
+  Graph(FilterGraph),
 }
 
 #[derive(Clone, Debug)]
@@ -59,6 +70,28 @@ impl Filter {
     Filter{ css, specs, _raster:None, _vector:None }
   }
 
+  pub fn from_shader(shader:&CanvasShader) -> Self {
+    // use a copy so later changes to the shader's uniforms don't invalidate the cached filters
+    Filter::new("", &[FilterSpec::Shader(shader.snapshot())])
+  }
+
+  pub fn from_graph(graph:FilterGraph) -> Self {
+    Filter::new("", &[FilterSpec::Graph(graph)])
+  }
+
+  pub fn is_shader(&self) -> bool {
+    matches!(self.specs.as_slice(), [FilterSpec::Shader(_)])
+  }
+
+  pub fn is_object(&self) -> bool {
+    // filters created from a CanvasShader or CanvasFilter (rather than a css string)
+    self.css.is_empty() && !self.specs.is_empty()
+  }
+
+  pub fn has_graph(&self) -> bool {
+    self.specs.iter().any(|spec| matches!(spec, FilterSpec::Graph(_)))
+  }
+
   pub fn mix_into<'a>(&mut self, paint:&'a mut Paint, matrix:Matrix, raster:bool) -> &'a mut Paint {
     let filters = self.filters_for(matrix, raster);
     paint.set_image_filter(filters.image)
@@ -81,6 +114,8 @@ impl Filter {
             let sigma = (    blur / scale.x,     blur / scale.y);
             image_filters::drop_shadow(point, sigma, *color, ColorSpace::new_srgb(), chain, None)
           },
+          FilterSpec::Shader(shader) => shader.image_filter(chain),
+          FilterSpec::Graph(graph) => graph.image_filter(matrix, chain),
           FilterSpec::Plain{ name, value } => match name.as_ref() {
             "blur" => {
               if raster {
@@ -198,26 +233,495 @@ impl Filter {
   }
 }
 
+//
+// SVG filter primitives
+//
+
+#[derive(Clone, Debug)]
+pub struct FilterGraph {
+  primitives: Vec<FilterPrimitive>,
+  sources: Vec<Option<Shader>>,
+  scaled: bool,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct FilterPrimitive {
+  #[serde(flatten)]
+  effect: FilterEffect,
+  #[serde(rename="in")]
+  input: Option<String>,
+  in2: Option<String>,
+  result: Option<String>,
+  subregion: Option<[f32; 4]>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag="type", rename_all="camelCase", rename_all_fields="camelCase")]
+pub enum FilterEffect {
+  Blend{ mode:String },
+  ColorMatrix{ values:Vec<f32> },
+  ComponentTransfer{ funcs:Vec<TransferFunction> },
+  Composite{ operator:String, k:[f32; 4] },
+  ConvolveMatrix{ order:[i32; 2], kernel:Vec<f32>, divisor:f32, bias:f32, target:[i32; 2], edge_mode:String, preserve_alpha:bool },
+  DiffuseLighting{ surface_scale:f32, diffuse_constant:f32, color:String, light:LightSource },
+  DisplacementMap{ scale:f32, x_channel:String, y_channel:String },
+  DropShadow{ dx:f32, dy:f32, std_deviation:[f32; 2], color:String, opacity:f32 },
+  Flood{ color:String, opacity:f32 },
+  GaussianBlur{ std_deviation:[f32; 2], edge_mode:String },
+  Merge{ inputs:Vec<Option<String>> },
+  Morphology{ operator:String, radius:[f32; 2] },
+  Offset{ dx:f32, dy:f32 },
+  SpecularLighting{ surface_scale:f32, specular_constant:f32, specular_exponent:f32, color:String, light:LightSource },
+  Source{ index:usize },
+  Turbulence{ base_frequency:[f32; 2], num_octaves:usize, seed:f32, fractal_noise:bool },
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag="type", rename_all="camelCase", rename_all_fields="camelCase")]
+pub enum LightSource {
+  Distant{ azimuth:f32, elevation:f32 },
+  Point{ x:f32, y:f32, z:f32 },
+  Spot{ x:f32, y:f32, z:f32, points_at:[f32; 3], specular_exponent:f32, limiting_cone_angle:Option<f32> },
+}
+
+impl LightSource {
+  fn position(&self) -> Point3 {
+    // the direction of a distant light or the location of a point or spot light
+    match self {
+      LightSource::Distant{ azimuth, elevation } => {
+        let (az, el) = (to_radians(*azimuth), to_radians(*elevation));
+        Point3::new(az.cos() * el.cos(), az.sin() * el.cos(), el.sin())
+      },
+      LightSource::Point{ x, y, z } | LightSource::Spot{ x, y, z, .. } => Point3::new(*x, *y, *z),
+    }
+  }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag="type", rename_all="camelCase", rename_all_fields="camelCase")]
+pub enum TransferFunction {
+  Identity,
+  Table{ values:Vec<f32> },
+  Discrete{ values:Vec<f32> },
+  Linear{ slope:f32, intercept:f32 },
+  Gamma{ amplitude:f32, exponent:f32, offset:f32 },
+}
+
+impl TransferFunction {
+  fn table(&self) -> Option<[u8; 256]> {
+    // formulæ from: https://www.w3.org/TR/filter-effects-1/#feComponentTransferElement
+    let transfer = |c:f32| match self {
+      TransferFunction::Identity => None,
+      TransferFunction::Table{ values } => match values.len(){
+        0 => None,
+        1 => Some(values[0]),
+        len => {
+          let n = (len - 1) as f32;
+          let k = ((c * n).floor() as usize).min(len - 2);
+          Some(values[k] + (c - k as f32 / n) * n * (values[k + 1] - values[k]))
+        }
+      },
+      TransferFunction::Discrete{ values } => match values.len(){
+        0 => None,
+        len => Some(values[((c * len as f32).floor() as usize).min(len - 1)])
+      },
+      TransferFunction::Linear{ slope, intercept } => Some(slope * c + intercept),
+      TransferFunction::Gamma{ amplitude, exponent, offset } => Some(amplitude * c.powf(*exponent) + offset),
+    };
+
+    let mut ramp = [0u8; 256];
+    for (i, val) in ramp.iter_mut().enumerate() {
+      *val = (transfer(i as f32 / 255.0)?.clamp(0.0, 1.0) * 255.0).round() as u8;
+    }
+    Some(ramp)
+  }
+}
+
+fn graph_color(css:&str, opacity:f32) -> Color {
+  let color = css_to_color(css).unwrap_or(Color::BLACK);
+  color.with_a((color.a() as f32 * opacity.clamp(0.0, 1.0)).round() as u8)
+}
+
+fn graph_channel(name:&str) -> ColorChannel {
+  match name {
+    "R" => ColorChannel::R,
+    "G" => ColorChannel::G,
+    "B" => ColorChannel::B,
+    _ => ColorChannel::A
+  }
+}
+
+fn graph_tile_mode(edge_mode:&str) -> TileMode {
+  match edge_mode {
+    "duplicate" => TileMode::Clamp,
+    "wrap" => TileMode::Repeat,
+    _ => TileMode::Decal
+  }
+}
+
+impl FilterGraph {
+  pub fn new(primitives:Vec<FilterPrimitive>, sources:Vec<Option<Shader>>, scaled:bool) -> Self {
+    // when `scaled` is set, lengths are measured in canvas pixels (like css filters) rather than
+    // in the current coordinate system (like svg filters)
+    FilterGraph{ primitives, sources, scaled }
+  }
+
+  fn image_filter(&self, matrix:Matrix, source:Option<SkImageFilter>) -> Option<SkImageFilter> {
+    let scale = match self.scaled {
+      true => Point::new(matrix.scale_x(), matrix.scale_y()),
+      false => Point::new(1.0, 1.0)
+    };
+    graph_filter(&self.primitives, &self.sources, scale, source)
+  }
+}
+
+fn graph_filter(primitives:&[FilterPrimitive], sources:&[Option<Shader>], scale:Point, source:Option<SkImageFilter>) -> Option<SkImageFilter> {
+  // inputs that are None are interpreted by skia as the content being filtered (i.e., the SourceGraphic)
+  let source_alpha = image_filters::color_filter(color_filters::matrix_row_major(&[
+    0.0, 0.0, 0.0, 0.0, 0.0,
+    0.0, 0.0, 0.0, 0.0, 0.0,
+    0.0, 0.0, 0.0, 0.0, 0.0,
+    0.0, 0.0, 0.0, 1.0, 0.0
+  ], None), source.clone(), None);
+
+  let Point{x:sx, y:sy} = scale;
+  let mut results:HashMap<&str, Option<SkImageFilter>> = HashMap::new();
+  let mut last = source.clone();
+
+  for primitive in primitives {
+    // references to missing results fall back to the previous primitive's output (per the spec)
+    let lookup = |name:&Option<String>| match name.as_deref() {
+      Some("SourceGraphic") => source.clone(),
+      Some("SourceAlpha") => source_alpha.clone(),
+      Some(name) => results.get(name).cloned().unwrap_or_else(|| last.clone()),
+      None => last.clone()
+    };
+    let input = lookup(&primitive.input);
+    let input2 = lookup(&primitive.in2);
+    let crop:CropRect = primitive.subregion
+      .map(|[x, y, w, h]| Rect::from_xywh(x, y, w, h).into())
+      .unwrap_or_default();
+
+    // primitives that can't be constructed pass their input through unchanged (rather than returning
+    // None, which skia would treat as the SourceGraphic, discarding the preceding steps)
+    let passthrough = input.clone();
+    let output = match &primitive.effect {
+      FilterEffect::Blend{ mode } => {
+        let mode = to_blend_mode(mode).unwrap_or(BlendMode::SrcOver);
+        image_filters::blend(mode, input2, input, crop)
+      },
+      FilterEffect::ColorMatrix{ values } => match <&[f32; 20]>::try_from(values.as_slice()) {
+        Ok(matrix) => image_filters::color_filter(color_filters::matrix_row_major(matrix, None), input, crop),
+        Err(_) => input
+      },
+      FilterEffect::ComponentTransfer{ funcs } => {
+        let tables:Vec<_> = funcs.iter().map(|func| func.table()).collect();
+        match tables.as_slice() {
+          [r, g, b, a] => match color_filters::table_argb(a.as_ref(), r.as_ref(), g.as_ref(), b.as_ref()) {
+            Some(table) => image_filters::color_filter(table, input, crop),
+            None => input
+          },
+          _ => input
+        }
+      },
+      FilterEffect::Composite{ operator, k:[k1, k2, k3, k4] } => match operator.as_str() {
+        "arithmetic" => image_filters::arithmetic(*k1, *k2, *k3, *k4, true, input2, input, crop),
+        op => {
+          let mode = match op {
+            "in" => BlendMode::SrcIn,
+            "out" => BlendMode::SrcOut,
+            "atop" => BlendMode::SrcATop,
+            "xor" => BlendMode::Xor,
+            "lighter" => BlendMode::Plus,
+            _ => BlendMode::SrcOver,
+          };
+          image_filters::blend(mode, input2, input, crop)
+        }
+      },
+      FilterEffect::ConvolveMatrix{ order:[cols, rows], kernel, divisor, bias, target:[tx, ty], edge_mode, preserve_alpha } => {
+        // svg kernels are applied rotated by 180° relative to skia's
+        let kernel:Vec<f32> = kernel.iter().rev().cloned().collect();
+        match *cols > 0 && *rows > 0 && (*cols * *rows) as usize == kernel.len() && *divisor != 0.0 {
+          true => image_filters::matrix_convolution(
+            (*cols, *rows), &kernel, 1.0 / divisor, *bias, (*tx, *ty),
+            graph_tile_mode(edge_mode), !preserve_alpha, input, crop
+          ),
+          false => input
+        }
+      },
+      FilterEffect::DiffuseLighting{ surface_scale, diffuse_constant, color, light } => {
+        let (color, kd) = (graph_color(color, 1.0), *diffuse_constant);
+        match light {
+          LightSource::Distant{..} =>
+            image_filters::distant_lit_diffuse(light.position(), color, *surface_scale, kd, input, crop),
+          LightSource::Point{..} =>
+            image_filters::point_lit_diffuse(light.position(), color, *surface_scale, kd, input, crop),
+          LightSource::Spot{ points_at:[x, y, z], specular_exponent, limiting_cone_angle, .. } =>
+            image_filters::spot_lit_diffuse(
+              light.position(), (*x, *y, *z), *specular_exponent, limiting_cone_angle.unwrap_or(180.0),
+              color, *surface_scale, kd, input, crop
+            ),
+        }
+      },
+      FilterEffect::SpecularLighting{ surface_scale, specular_constant, specular_exponent, color, light } => {
+        let (color, ks, shininess) = (graph_color(color, 1.0), *specular_constant, *specular_exponent);
+        match light {
+          LightSource::Distant{..} =>
+            image_filters::distant_lit_specular(light.position(), color, *surface_scale, ks, shininess, input, crop),
+          LightSource::Point{..} =>
+            image_filters::point_lit_specular(light.position(), color, *surface_scale, ks, shininess, input, crop),
+          LightSource::Spot{ points_at:[x, y, z], specular_exponent, limiting_cone_angle, .. } =>
+            image_filters::spot_lit_specular(
+              light.position(), (*x, *y, *z), *specular_exponent, limiting_cone_angle.unwrap_or(180.0),
+              color, *surface_scale, ks, shininess, input, crop
+            ),
+        }
+      },
+      FilterEffect::DisplacementMap{ scale, x_channel, y_channel } => image_filters::displacement_map(
+        (graph_channel(x_channel), graph_channel(y_channel)), *scale / (0.5 * (sx + sy)), input2, input, crop
+      ),
+      FilterEffect::DropShadow{ dx, dy, std_deviation, color, opacity } => image_filters::drop_shadow(
+        (dx / sx, dy / sy), (std_deviation[0] / sx, std_deviation[1] / sy), graph_color(color, *opacity),
+        ColorSpace::new_srgb(), input, crop
+      ),
+      FilterEffect::Flood{ color, opacity } => {
+        image_filters::shader(shaders::color(graph_color(color, *opacity)), crop)
+      },
+      FilterEffect::GaussianBlur{ std_deviation, edge_mode } => image_filters::blur(
+        (std_deviation[0] / sx, std_deviation[1] / sy), graph_tile_mode(edge_mode), input, crop
+      ),
+      FilterEffect::Merge{ inputs } => {
+        image_filters::merge(inputs.iter().map(lookup).collect::<Vec<_>>(), crop)
+      },
+      FilterEffect::Morphology{ operator, radius } => match operator.as_str() {
+        "dilate" => image_filters::dilate((radius[0] / sx, radius[1] / sy), input, crop),
+        _ => image_filters::erode((radius[0] / sx, radius[1] / sy), input, crop),
+      },
+      FilterEffect::Offset{ dx, dy } => image_filters::offset((dx / sx, dy / sy), input, crop),
+      FilterEffect::Source{ index } => match sources.get(*index) {
+        Some(Some(shader)) => image_filters::shader(shader.clone(), crop),
+        _ => image_filters::shader(shaders::empty(), crop),
+      },
+      FilterEffect::Turbulence{ base_frequency:[fx, fy], num_octaves, seed, fractal_noise } => {
+        let noise = match fractal_noise {
+          true => shaders::fractal_noise((fx * sx, fy * sy), *num_octaves, *seed, None),
+          false => shaders::turbulence((fx * sx, fy * sy), *num_octaves, *seed, None),
+        };
+        noise.and_then(|noise| image_filters::shader(noise, crop))
+      },
+    }.or(passthrough);
+
+    if let Some(name) = &primitive.result {
+      results.insert(name.as_str(), output.clone());
+    }
+    last = output;
+  }
+
+  last
+}
+
 #[derive(Copy, Clone)]
 pub enum FilterQuality{
   None, Low, Medium, High
 }
 
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Resampling{
+  Auto, Nearest, Linear, Mipmap, Mitchell, CatmullRom, Lanczos3
+}
+
 #[derive(Copy, Clone)]
 pub struct ImageFilter {
   pub smoothing: bool,
-  pub quality: FilterQuality
+  pub quality: FilterQuality,
+  pub resampling: Resampling
 }
 
 impl ImageFilter {
-  pub fn sampling(&self) -> SamplingOptions {
-    let quality = if self.smoothing { self.quality } else { FilterQuality::None };
-    match quality {
-      FilterQuality::None   => SamplingOptions::new(FilterMode::Nearest, MipmapMode::None),
-      FilterQuality::Low    => SamplingOptions::new(FilterMode::Linear,  MipmapMode::Nearest),
-      FilterQuality::Medium => SamplingOptions::new(FilterMode::Linear,  MipmapMode::Linear),
-      FilterQuality::High   => SamplingOptions::new(FilterMode::Linear,  MipmapMode::Linear)
+  pub fn sampling(&self, scale:f32) -> SamplingOptions {
+    // an explicit resampling mode takes precedence over the imageSmoothingQuality. Since cubic filters
+    // ignore mipmaps (and alias when shrinking), 'high' quality only uses one when the image is being
+    // enlarged (by a `scale` of at least 1) and uses mipmaps otherwise, like skia's legacy kHigh quality
+    let shrinking = scale < 1.0;
+    let resampling = match (self.smoothing, self.resampling, self.quality) {
+      (false, ..) | (_, Resampling::Auto, FilterQuality::None) => Resampling::Nearest,
+      (_, Resampling::Auto, FilterQuality::Low) => Resampling::Linear,
+      (_, Resampling::Auto, FilterQuality::Medium) => Resampling::Mipmap,
+      (_, Resampling::Auto, FilterQuality::High) if shrinking => Resampling::Mipmap,
+      (_, Resampling::Auto, FilterQuality::High) => Resampling::Mitchell,
+      (_, Resampling::Lanczos3, _) if shrinking => Resampling::Mipmap,
+      (_, resampling, _) => resampling
+    };
+    match resampling {
+      Resampling::Nearest  => SamplingOptions::new(FilterMode::Nearest, MipmapMode::None),
+      Resampling::Linear   => SamplingOptions::new(FilterMode::Linear,  MipmapMode::Nearest),
+      Resampling::Mipmap   => SamplingOptions::new(FilterMode::Linear,  MipmapMode::Linear),
+      Resampling::Mitchell => CubicResampler::mitchell().into(),
+      // lanczos is only available via `downscale()`, so use mipmaps or the closest cubic everywhere else
+      Resampling::CatmullRom | Resampling::Lanczos3 | Resampling::Auto => CubicResampler::catmull_rom().into(),
     }
   }
 
+  pub fn filter_mode(&self) -> FilterMode {
+    // for drawing calls that only support a subset of the sampling options
+    match (self.smoothing, self.quality, self.resampling) {
+      (false, ..) | (_, _, Resampling::Nearest) | (_, FilterQuality::None, Resampling::Auto) => FilterMode::Nearest,
+      _ => FilterMode::Linear
+    }
+  }
+
+  pub fn downscale(&self, image:&Image, src:&Rect, size:Size) -> Option<Image> {
+    // resample the src region to the given (device-space) size on the cpu when lanczos is
+    // selected and the image is being shrunk in at least one dimension
+    let bounds = IRect::from_ltrb(src.left.floor() as i32, src.top.floor() as i32, src.right.ceil() as i32, src.bottom.ceil() as i32);
+    let (width, height) = (size.width.round().max(1.0) as usize, size.height.round().max(1.0) as usize);
+    if !self.smoothing || self.resampling != Resampling::Lanczos3 || bounds.is_empty()
+    || (width >= bounds.width() as usize && height >= bounds.height() as usize) {
+      return None
+    }
+
+    // reuse the result if the same image was recently drawn at this size
+    let key = (image.unique_id(), [src.left, src.top, src.right, src.bottom].map(f32::to_bits), width, height);
+    if let Ok(mut cache) = LANCZOS_CACHE.lock(){
+      if let Some(idx) = cache.iter().position(|(k, _)| *k == key){
+        let entry = cache.remove(idx);
+        cache.insert(0, entry.clone());
+        return Some(entry.1)
+      }
+    }
+
+    let info = ImageInfo::new_n32_premul(bounds.size(), image.image_info().color_space());
+    let mut pixels = vec![0u8; info.compute_min_byte_size()];
+    if !image.read_pixels(&info, pixels.as_mut_slice(), info.min_row_bytes(), (bounds.left, bounds.top), CachingHint::Allow){
+      return None
+    }
+
+    // separable filtering: first along rows, then along columns
+    let (src_w, src_h) = (bounds.width() as usize, bounds.height() as usize);
+    let cols = lanczos_weights(src.left - bounds.left as f32, src.width(), src_w, width);
+    let rows = lanczos_weights(src.top - bounds.top as f32, src.height(), src_h, height);
+
+    let mut horiz = vec![0f32; width * src_h * 4];
+    for y in 0..src_h {
+      for (x, (start, weights)) in cols.iter().enumerate() {
+        let dst = (y * width + x) * 4;
+        for (i, weight) in weights.iter().enumerate() {
+          let src = (y * src_w + start + i) * 4;
+          for c in 0..4 { horiz[dst + c] += pixels[src + c] as f32 * weight }
+        }
+      }
+    }
+
+    let mut resized = vec![0u8; width * height * 4];
+    for (y, (start, weights)) in rows.iter().enumerate() {
+      for x in 0..width {
+        let mut px = [0f32; 4];
+        for (i, weight) in weights.iter().enumerate() {
+          let src = ((start + i) * width + x) * 4;
+          for c in 0..4 { px[c] += horiz[src + c] * weight }
+        }
+
+        // lanczos lobes can over/undershoot, so clamp the color channels to stay premultiplied
+        let dst = (y * width + x) * 4;
+        let alpha = px[3].round().clamp(0.0, 255.0);
+        for c in 0..4 { resized[dst + c] = px[c].round().clamp(0.0, alpha) as u8 }
+      }
+    }
+
+    let info = ImageInfo::new_n32_premul((width as i32, height as i32), image.image_info().color_space());
+    let resized = images::raster_from_data(&info, Data::new_copy(&resized), info.min_row_bytes())?;
+    if let Ok(mut cache) = LANCZOS_CACHE.lock(){
+      // keep as many of the most recent results as fit within the budget
+      cache.insert(0, (key, resized.clone()));
+      let mut bytes = 0;
+      let fits = cache.iter().take_while(|(_, img)|{
+        bytes += img.image_info().compute_min_byte_size();
+        bytes <= LANCZOS_CACHE_BYTES
+      }).count();
+      cache.truncate(fits);
+    }
+    Some(resized)
+  }
+}
+
+// the smallest factor by which a transform enlarges the images it draws (treating perspective as shrinking)
+pub fn sampling_scale(matrix:&Matrix) -> f32 {
+  matrix.decompose_scale(None).map(|size| size.width.min(size.height)).unwrap_or(0.0)
+}
+
+// recently downscaled images (keyed by source image id, src rect, and output size), most recent first
+type LanczosKey = (u32, [u32; 4], usize, usize);
+const LANCZOS_CACHE_BYTES:usize = 32 * 1024 * 1024;
+static LANCZOS_CACHE: Mutex<Vec<(LanczosKey, Image)>> = Mutex::new(Vec::new());
+
+pub fn purge_lanczos_cache(){
+  if let Ok(mut cache) = LANCZOS_CACHE.lock(){
+    cache.clear();
+  }
+}
+
+fn lanczos_weights(offset:f32, src_len:f32, src_max:usize, dst_len:usize) -> Vec<(usize, Vec<f32>)> {
+  // for each destination pixel, find the first source pixel it draws from & the per-pixel weights
+  let lanczos3 = |x:f32| match x.abs() {
+    x if x < f32::EPSILON => 1.0,
+    x if x >= 3.0 => 0.0,
+    x => {
+      let px = std::f32::consts::PI * x;
+      3.0 * px.sin() * (px / 3.0).sin() / (px * px)
+    }
+  };
+
+  let scale = src_len / dst_len as f32;
+  let stretch = scale.max(1.0);
+  (0..dst_len).map(|i| {
+    let center = offset + (i as f32 + 0.5) * scale;
+    let start = (center - 3.0 * stretch).floor().max(0.0) as usize;
+    let end = ((center + 3.0 * stretch).ceil() as usize).min(src_max).max(start + 1);
+    let mut weights:Vec<f32> = (start..end).map(|j| lanczos3((j as f32 + 0.5 - center) / stretch)).collect();
+    let sum:f32 = weights.iter().sum();
+    if sum.abs() > f32::EPSILON { weights.iter_mut().for_each(|w| *w /= sum) }
+    (start, weights)
+  }).collect()
+}
+
+//
+// CanvasFilter
+//
+
+pub type BoxedCanvasFilter = JsBox<RefCell<Filter>>;
+impl Finalize for Filter {}
+
+pub fn new(mut cx: FunctionContext) -> JsResult<BoxedCanvasFilter> {
+  let json = string_arg(&mut cx, 1, "primitives")?;
+  let primitives = match serde_json::from_str(&json){
+    Ok(primitives) => primitives,
+    Err(e) => return cx.throw_type_error(format!("Invalid filter definition: {}", e))
+  };
+
+  // gradients, patterns, & shaders that can be used as inputs via `source` primitives
+  let mut sources = vec![];
+  for source in cx.argument::<JsArray>(2)?.to_vec(&mut cx)? {
+    let shader = if let Ok(gradient) = source.downcast::<BoxedCanvasGradient, _>(&mut cx){
+      gradient.borrow().shader()
+    }else if let Ok(pattern) = source.downcast::<BoxedCanvasPattern, _>(&mut cx){
+      pattern.borrow().shader(ImageFilter{ smoothing:true, quality:FilterQuality::Low, resampling:Resampling::Auto })
+    }else if let Ok(shader) = source.downcast::<BoxedCanvasShader, _>(&mut cx){
+      shader.borrow().shader()
+    }else{
+      None
+    };
+    sources.push(shader);
+  }
+
+  let graph = FilterGraph::new(primitives, sources, true);
+  Ok(cx.boxed(RefCell::new(Filter::from_graph(graph))))
+}
+
+pub fn repr(mut cx: FunctionContext) -> JsResult<JsString> {
+  let this = cx.argument::<BoxedCanvasFilter>(0)?;
+  let count = this.borrow().specs.iter().map(|spec| match spec {
+    FilterSpec::Graph(graph) => graph.primitives.len(),
+    _ => 0
+  }).sum::<usize>();
+  Ok(cx.string(format!("{} primitive{}", count, if count == 1 { "" } else { "s" })))
 }
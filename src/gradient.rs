@@ -1,9 +1,9 @@
 #![allow(non_snake_case)]
-use std::cell::{RefCell};
+use std::cell::RefCell;
 use std::rc::Rc;
 use neon::prelude::*;
-use skia_safe::{Shader, Color, Point, TileMode, Matrix};
-use skia_safe::{gradient_shader, gradient_shader::GradientShaderColors::Colors};
+use skia_safe::{Shader, Color, Color4f, ColorSpace, Point, TileMode, Matrix, Image, Rect, ISize, Paint, Path, BlendMode, FilterMode, surfaces};
+use skia_safe::{gradient_shader, gradient_shader::{Interpolation, GradientShaderColors::Colors}};
 
 use crate::utils::*;
 
@@ -27,23 +27,29 @@ enum Gradient{
     angle:f32,
     stops:Vec<f32>,
     colors:Vec<Color>,
+  },
+  Mesh{
+    patches:Vec<Patch>,
+    raster:RefCell<Option<(f32, Option<(Image, Rect)>)>>, // cached bitmap & the scale it was rendered at
   }
 }
 
 impl Gradient{
-  fn get_stops(&self) -> &Vec<f32>{
+  fn get_stops(&self) -> &[f32]{
     match self{
       Gradient::Linear{stops, ..} => stops,
       Gradient::Radial{stops, ..} => stops,
       Gradient::Conic{stops, ..} => stops,
+      Gradient::Mesh{..} => &[],
     }
   }
 
-  fn get_colors(&self) -> &Vec<Color>{
+  fn get_colors(&self) -> &[Color]{
     match self{
       Gradient::Linear{colors, ..} => colors,
       Gradient::Radial{colors, ..} => colors,
       Gradient::Conic{colors, ..} => colors,
+      Gradient::Mesh{..} => &[],
     }
   }
 
@@ -56,30 +62,115 @@ impl Gradient{
       Gradient::Linear{colors, stops, ..} => { colors.insert(idx, color); stops.insert(idx, offset); },
       Gradient::Radial{colors, stops, ..} => { colors.insert(idx, color); stops.insert(idx, offset); },
       Gradient::Conic{colors, stops, ..} => { colors.insert(idx, color); stops.insert(idx, offset); },
+      Gradient::Mesh{..} => {}
     };
   }
 }
 
+//
+// Coons patches
+//
+
+// bitmaps are rendered at twice the patches' device-space size to leave headroom for hi-dpi output
+pub const PATCH_DENSITY:f32 = 2.0;
+const PATCH_MAX_DIM:f32 = 8192.0;
+
+#[derive(Clone, Debug)]
+pub struct Patch{
+  pub cubics:[Point; 12],
+  pub colors:Option<[Color; 4]>,
+  pub tex_coords:Option<[Point; 4]>,
+}
+
+impl Patch{
+  pub fn outline(&self) -> Path{
+    let c = &self.cubics;
+    let mut path = Path::new();
+    path.move_to(c[0]);
+    for i in [1, 4, 7, 10]{
+      path.cubic_to(c[i], c[i + 1], c[(i + 2) % 12]);
+    }
+    path.close();
+    path
+  }
+}
+
+pub fn rasterize_patches(patches:&[Patch], paint:&Paint, scale:f32) -> Option<(Image, Rect)>{
+  // PDF & SVG have no way to represent Skia's patches as vectors, so they are always drawn via a
+  // bitmap covering the patches' control points (returned along with the area it should occupy)
+  let mut bounds = Rect::new_empty();
+  for patch in patches{
+    bounds.join(Rect::from_bounds(&patch.cubics)?);
+  }
+
+  let scale = scale.min(PATCH_MAX_DIM / bounds.width().max(bounds.height()));
+  let dims = ISize::new((bounds.width() * scale).ceil() as i32, (bounds.height() * scale).ceil() as i32);
+  let mut surface = surfaces::raster_n32_premul(dims)?;
+  let canvas = surface.canvas();
+  canvas.scale((scale, scale));
+  canvas.translate((-bounds.left, -bounds.top));
+  for patch in patches{
+    // vertex colors are multiplied with the paint's shader (if any)
+    canvas.draw_patch(&patch.cubics, patch.colors.as_ref(), patch.tex_coords.as_ref(), BlendMode::Modulate, paint);
+  }
+  Some((surface.image_snapshot(), bounds))
+}
+
 pub type BoxedCanvasGradient = JsBox<RefCell<CanvasGradient>>;
 impl Finalize for CanvasGradient {}
 
+struct Options{
+  spread:TileMode,
+  interpolation:Option<Interpolation>, // None for Skia's default (premultiplied sRGB)
+  dither:bool,
+  matrix:Matrix,
+}
+
+impl Default for Options{
+  fn default() -> Self{
+    Options{ spread:TileMode::Clamp, interpolation:None, dither:false, matrix:Matrix::new_identity() }
+  }
+}
+
 #[derive(Clone)]
 pub struct CanvasGradient{
-  gradient:Rc<RefCell<Gradient>>
+  gradient:Rc<RefCell<Gradient>>,
+  options:Rc<RefCell<Options>>,
 }
 
 impl CanvasGradient{
+  fn new(gradient:Gradient) -> Self{
+    CanvasGradient{ gradient:Rc::new(RefCell::new(gradient)), options:Rc::new(RefCell::new(Options::default())) }
+  }
+
   pub fn shader(&self) -> Option<Shader>{
-    match &*self.gradient.borrow(){
-      Gradient::Linear{start, end, stops, colors} => {
-        gradient_shader::linear((*start, *end), Colors(colors), Some(stops.as_slice()), TileMode::Clamp, None, None)
+    self.shader_at_scale(1.0)
+  }
+
+  pub fn shader_at_scale(&self, device_scale:f32) -> Option<Shader>{
+    // the device scale is only used to choose the resolution of mesh gradients' bitmaps
+    let Options{spread, interpolation, matrix, ..} = *self.options.borrow();
+    let to_color4f = |colors:&[Color]| colors.iter().map(|c| Color4f::from(*c)).collect::<Vec<_>>();
+
+    let shader = match &*self.gradient.borrow(){
+      Gradient::Linear{start, end, stops, colors} => match interpolation{
+        Some(interpolation) => gradient_shader::linear_with_interpolation(
+          (*start, *end),
+          (&to_color4f(colors), ColorSpace::new_srgb()), Some(stops.as_slice()),
+          spread, interpolation, None),
+        None => gradient_shader::linear((*start, *end), Colors(colors), Some(stops.as_slice()), spread, None, None)
       },
-      Gradient::Radial{start_point, start_radius, end_point, end_radius, stops, colors} => {
-        gradient_shader::two_point_conical(
+      Gradient::Radial{start_point, start_radius, end_point, end_radius, stops, colors} => match interpolation{
+        Some(interpolation) => gradient_shader::two_point_conical_with_interpolation(
+          (*start_point, *start_radius),
+          (*end_point, *end_radius),
+          (&to_color4f(colors), ColorSpace::new_srgb()), Some(stops.as_slice()),
+          spread, interpolation, None),
+        None => gradient_shader::two_point_conical(
           *start_point, *start_radius,
           *end_point, *end_radius,
           Colors(colors), Some(stops.as_slice()),
-          TileMode::Clamp, None, None)
+          spread, None, None)
       },
       Gradient::Conic{center, angle, stops, colors} => {
         let Point{x, y} = *center;
@@ -89,17 +180,57 @@ impl CanvasGradient{
           .pre_rotate(*angle, None)
           .pre_translate((-x, -y));
 
-        gradient_shader::sweep(
-          *center,
-          Colors(colors),
-          Some(stops.as_slice()),
-          TileMode::Clamp,
-          None, // angles
-          None, // flags
-          Some(&rotated), // local_matrix
-
-        )
+        match interpolation{
+          Some(interpolation) => gradient_shader::sweep_with_interpolation(
+            *center,
+            (&to_color4f(colors), ColorSpace::new_srgb()),
+            Some(stops.as_slice()),
+            spread,
+            None, // angles
+            interpolation,
+            Some(&rotated), // local_matrix
+          ),
+          None => gradient_shader::sweep(
+            *center,
+            Colors(colors),
+            Some(stops.as_slice()),
+            spread,
+            None, // angles
+            None, // flags
+            Some(&rotated), // local_matrix
+          )
+        }
+      },
+      Gradient::Mesh{patches, raster} => {
+        // re-render the bitmap whenever the gradient is drawn at a larger scale than before
+        let scale = device_scale * matrix.scale_x().hypot(matrix.skew_y()).max(matrix.scale_y().hypot(matrix.skew_x()));
+        let mut cache = raster.borrow_mut();
+        if !matches!(*cache, Some((cached, _)) if cached >= scale){
+          let mut paint = Paint::default();
+          paint.set_anti_alias(true).set_color(Color::WHITE);
+          *cache = Some((scale, rasterize_patches(patches, &paint, scale * PATCH_DENSITY)));
+        }
+        let (image, bounds) = cache.as_ref().and_then(|(_, raster)| raster.as_ref())?;
+        let placement = Matrix::rect_to_rect(Rect::from_iwh(image.width(), image.height()), bounds, None)?;
+        image.to_shader((TileMode::Decal, TileMode::Decal), FilterMode::Linear, &placement)
       }
+    };
+
+    shader.map(|shader| shader.with_local_matrix(&matrix))
+  }
+
+  pub fn dither(&self) -> bool{
+    self.options.borrow().dither
+  }
+
+  pub fn is_mesh(&self) -> bool{
+    matches!(&*self.gradient.borrow(), Gradient::Mesh{..})
+  }
+
+  pub fn add_patch(&mut self, patch:Patch){
+    if let Gradient::Mesh{patches, raster} = &mut *self.gradient.borrow_mut(){
+      patches.push(patch);
+      raster.borrow_mut().take();
     }
   }
 
@@ -108,12 +239,489 @@ impl CanvasGradient{
   }
 
   pub fn is_opaque(&self) -> bool{
-    // true if all colors are 100% opaque
+    // true if all colors are 100% opaque (meshes are transparent beyond their patches' edges)
     let gradient = self.gradient.borrow();
-    !gradient.get_colors().iter().any(|c| c.a() < 255)
+    !matches!(*gradient, Gradient::Mesh{..}) && !gradient.get_colors().iter().any(|c| c.a() < 255)
   }
 }
 
+//
+// CSS gradient syntax
+//
+
+// number of intermediate stops used to approximate the easing curve implied by a color hint
+const HINT_STEPS:usize = 9;
+
+// most times a repeating-conic-gradient's stops will be tiled around the circle before being averaged
+const MAX_CONIC_REPEATS:f32 = 4096.0;
+
+enum Length{ Px(f32), Pct(f32) }
+
+impl Length{
+  fn resolve(&self, basis:f32) -> f32{
+    match self{
+      Length::Px(px) => *px,
+      Length::Pct(pct) => pct / 100.0 * basis,
+    }
+  }
+}
+
+fn split_top_level(text:&str, is_sep:impl Fn(char) -> bool) -> Vec<&str>{
+  // splits on separators that aren't nested within parentheses (e.g., the commas in `rgba(…)`)
+  let (mut depth, mut start, mut parts) = (0, 0, vec![]);
+  for (i, c) in text.char_indices(){
+    match c{
+      '(' => depth += 1,
+      ')' => depth -= 1,
+      c if depth == 0 && is_sep(c) => {
+        parts.push(text[start..i].trim());
+        start = i + c.len_utf8();
+      },
+      _ => {}
+    }
+  }
+  parts.push(text[start..].trim());
+  parts
+}
+
+fn tokenize(text:&str) -> Vec<&str>{
+  split_top_level(text, char::is_whitespace).into_iter().filter(|t| !t.is_empty()).collect()
+}
+
+fn parse_dimension(token:&str) -> Option<(f32, String)>{
+  let num_end = token.trim_end_matches(|c:char| c.is_ascii_alphabetic() || c == '%').len();
+  let value = token[..num_end].parse::<f32>().ok()?;
+  Some((value, token[num_end..].to_lowercase()))
+}
+
+fn parse_angle(token:&str) -> Option<f32>{
+  // returns the angle in degrees
+  let (value, unit) = parse_dimension(token)?;
+  match unit.as_str(){
+    "deg" => Some(value),
+    "rad" => Some(to_degrees(value)),
+    "grad" => Some(value * 0.9),
+    "turn" => Some(value * 360.0),
+    "" if value == 0.0 => Some(0.0),
+    _ => None
+  }
+}
+
+fn parse_length(token:&str) -> Option<Length>{
+  let (value, unit) = parse_dimension(token)?;
+  match unit.as_str(){
+    "px" => Some(Length::Px(value)),
+    "%" => Some(Length::Pct(value)),
+    "" if value == 0.0 => Some(Length::Px(0.0)),
+    _ => None
+  }
+}
+
+fn parse_position(tokens:&[&str], rect:&Rect) -> Result<Point, String>{
+  // one or two keywords or lengths (e.g., "center", "left 25%", or "10px bottom")
+  let is_vertical = |t:&str| matches!(t.to_lowercase().as_str(), "top" | "bottom");
+  let is_horizontal = |t:&str| matches!(t.to_lowercase().as_str(), "left" | "right");
+  let (x, y) = match tokens{
+    [] => ("center", "center"),
+    [one] if is_vertical(one) => ("center", *one),
+    [one] => (*one, "center"),
+    [a, b] if is_vertical(a) || is_horizontal(b) => (*b, *a),
+    [a, b] => (*a, *b),
+    _ => return Err(format!("Expected 1 or 2 values for position (got \"{}\")", tokens.join(" ")))
+  };
+
+  let resolve = |token:&str, start:f32, size:f32, start_kw:&str, end_kw:&str| -> Option<f32>{
+    match token.to_lowercase().as_str(){
+      kw if kw == start_kw => Some(start),
+      "center" => Some(start + size / 2.0),
+      kw if kw == end_kw => Some(start + size),
+      _ => parse_length(token).map(|len| start + len.resolve(size))
+    }
+  };
+  match (resolve(x, rect.left, rect.width(), "left", "right"), resolve(y, rect.top, rect.height(), "top", "bottom")){
+    (Some(x), Some(y)) => Ok(Point::new(x, y)),
+    _ => Err(format!("Could not parse position \"{}\"", tokens.join(" ")))
+  }
+}
+
+fn take_interpolation(tokens:&mut Vec<&str>) -> Result<Option<Interpolation>, String>{
+  // removes an `in <color-space> [<hue-method> hue]` clause from the tokens (if present)
+  let idx = match tokens.iter().position(|t| t.eq_ignore_ascii_case("in")){
+    Some(idx) => idx,
+    None => return Ok(None)
+  };
+  let len = match tokens.get(idx + 3){
+    Some(t) if t.eq_ignore_ascii_case("hue") => 4,
+    _ => 2
+  };
+  let clause = tokens.drain(idx..(idx + len).min(tokens.len())).collect::<Vec<_>>().join(" ");
+  match to_interpolation(&clause){
+    Some(interpolation) => Ok(Some(interpolation)),
+    None => Err(format!("Unsupported color interpolation method \"{}\"", clause))
+  }
+}
+
+fn lerp_color(a:Color, b:Color, t:f32) -> Color{
+  let (a, b) = (Color4f::from(a), Color4f::from(b));
+  Color4f::new(a.r + (b.r - a.r) * t, a.g + (b.g - a.g) * t, a.b + (b.b - a.b) * t, a.a + (b.a - a.a) * t).to_color()
+}
+
+fn color_at(stops:&[f32], colors:&[Color], pos:f32) -> Color{
+  // the color a list of stops produces at a given offset
+  match stops.iter().position(|stop| *stop > pos){
+    Some(0) => colors[0],
+    Some(i) => lerp_color(colors[i - 1], colors[i], (pos - stops[i - 1]) / (stops[i] - stops[i - 1])),
+    None => colors[colors.len() - 1]
+  }
+}
+
+fn average_color(stops:&[f32], colors:&[Color]) -> Color{
+  // the mean color across the span of a list of stops, weighting each segment by its length
+  let span = stops[stops.len() - 1] - stops[0];
+  let mut sum = [0.0f32; 4];
+  for i in 1..stops.len(){
+    let weight = match span > 0.0{
+      true => (stops[i] - stops[i - 1]) / span,
+      false => 1.0 / (stops.len() - 1) as f32
+    };
+    let mid = Color4f::from(lerp_color(colors[i - 1], colors[i], 0.5));
+    for (total, channel) in sum.iter_mut().zip([mid.r, mid.g, mid.b, mid.a]){
+      *total += channel * weight;
+    }
+  }
+  Color4f::new(sum[0], sum[1], sum[2], sum[3]).to_color()
+}
+
+fn clip_stops(stops:&[f32], colors:&[Color], lo:f32, hi:f32) -> (Vec<f32>, Vec<Color>){
+  // restricts the stops to the lo–hi range, adding stops at the edges if any were dropped
+  let (mut new_stops, mut new_colors) = (vec![], vec![]);
+  if stops[0] < lo{
+    new_stops.push(lo);
+    new_colors.push(color_at(stops, colors, lo));
+  }
+  for (stop, color) in stops.iter().zip(colors){
+    if *stop >= lo && *stop <= hi{
+      new_stops.push(*stop);
+      new_colors.push(*color);
+    }
+  }
+  if stops[stops.len() - 1] > hi{
+    new_stops.push(hi);
+    new_colors.push(color_at(stops, colors, hi));
+  }
+  (new_stops, new_colors)
+}
+
+fn parse_stops(args:&[&str], to_offset:&dyn Fn(&str) -> Option<f32>) -> Result<(Vec<f32>, Vec<Color>), String>{
+  // each entry is either a color stop (with 0–2 positions) or a color hint (with no color)
+  let mut entries:Vec<(Option<Color>, Option<f32>)> = vec![];
+  for arg in args{
+    let (mut color, mut positions) = (None, vec![]);
+    for token in tokenize(arg){
+      if let Some(offset) = to_offset(token){
+        positions.push(offset);
+      }else if color.is_none(){
+        color = Some(css_to_color(token).ok_or(format!("Could not parse \"{}\" as a color", token))?);
+      }else{
+        return Err(format!("Unexpected \"{}\" in color stop \"{}\"", token, arg))
+      }
+    }
+
+    match (color, positions.as_slice()){
+      (Some(color), []) => entries.push((Some(color), None)),
+      (Some(color), [pos]) => entries.push((Some(color), Some(*pos))),
+      (Some(color), [pos1, pos2]) => entries.extend([(Some(color), Some(*pos1)), (Some(color), Some(*pos2))]),
+      (None, [hint]) => entries.push((None, Some(*hint))),
+      _ => return Err(format!("Could not parse color stop \"{}\"", arg))
+    }
+  }
+
+  // hints must lie between two color stops
+  let is_hint = |entry:Option<&(Option<Color>, Option<f32>)>| matches!(entry, Some((None, _)));
+  if entries.is_empty() || is_hint(entries.first()) || is_hint(entries.last())
+  || entries.windows(2).any(|pair| pair[0].0.is_none() && pair[1].0.is_none()){
+    return Err("Expected a list of color stops (with color hints only between two colors)".to_string())
+  }
+
+  // fill in missing positions (following https://drafts.csswg.org/css-images-4/#color-stop-fixup)
+  let count = entries.len();
+  if entries[0].1.is_none(){ entries[0].1 = Some(0.0) }
+  if entries[count - 1].1.is_none(){ entries[count - 1].1 = Some(1.0) }
+  let mut max_pos = f32::MIN;
+  for entry in entries.iter_mut(){
+    if let Some(pos) = entry.1.as_mut(){
+      *pos = pos.max(max_pos);
+      max_pos = *pos;
+    }
+  }
+  let mut i = 1;
+  while i < count{
+    if entries[i].1.is_none(){
+      let end = (i..count).find(|j| entries[*j].1.is_some()).unwrap_or(count - 1);
+      let (start_pos, end_pos) = (entries[i - 1].1.unwrap_or(0.0), entries[end].1.unwrap_or(1.0));
+      for j in i..end{
+        let t = (j - i + 1) as f32 / (end - i + 1) as f32;
+        entries[j].1 = Some(start_pos + (end_pos - start_pos) * t);
+      }
+      i = end;
+    }
+    i += 1;
+  }
+
+  // replace hints with a series of stops approximating their exponential easing curve
+  let (mut stops, mut colors):(Vec<f32>, Vec<Color>) = (vec![], vec![]);
+  for (i, (color, pos)) in entries.iter().enumerate(){
+    let pos = pos.unwrap_or(0.0);
+    if let Some(color) = color{
+      stops.push(pos);
+      colors.push(*color);
+      continue
+    }
+
+    let (start_pos, start_color) = (stops[stops.len() - 1], colors[colors.len() - 1]);
+    let (end_pos, end_color) = match entries[i + 1]{
+      (Some(color), Some(pos)) => (pos, color),
+      _ => continue
+    };
+    let midpoint = match end_pos > start_pos{
+      true => (pos - start_pos) / (end_pos - start_pos),
+      false => 0.5
+    };
+    if midpoint <= 0.0{
+      stops.push(start_pos);
+      colors.push(end_color);
+    }else if midpoint >= 1.0{
+      stops.push(end_pos);
+      colors.push(start_color);
+    }else{
+      let exponent = 0.5_f32.ln() / midpoint.ln();
+      for step in 1..HINT_STEPS{
+        let t = step as f32 / HINT_STEPS as f32;
+        stops.push(start_pos + (end_pos - start_pos) * t);
+        colors.push(lerp_color(start_color, end_color, t.powf(exponent)));
+      }
+    }
+  }
+  Ok((stops, colors))
+}
+
+fn parse_css_gradient(css:&str, rect:&Rect) -> Result<CanvasGradient, String>{
+  // converts a CSS <gradient> function into a CanvasGradient that fills the given rect
+  let (name, body) = match css.trim().strip_suffix(')').and_then(|css| css.split_once('(')){
+    Some((name, body)) => (name.trim().to_lowercase(), body),
+    None => return Err("Expected a CSS gradient function like `linear-gradient(…)`".to_string())
+  };
+  let (repeating, kind) = match name.strip_prefix("repeating-"){
+    Some(kind) => (true, kind),
+    None => (false, name.as_str())
+  };
+
+  // the optional first argument describes the gradient's geometry and interpolation
+  let args = split_top_level(body, |c| c == ',');
+  let mut tokens = tokenize(args[0]);
+  let interpolation = take_interpolation(&mut tokens)?;
+  let first = tokens.first().map(|t| t.to_lowercase()).unwrap_or_default();
+  let is_config = interpolation.is_some() || match kind{
+    "linear" => first == "to" || parse_angle(&first).is_some(),
+    "radial" => parse_length(&first).is_some() || matches!(first.as_str(),
+      "at" | "circle" | "ellipse" | "closest-side" | "closest-corner" | "farthest-side" | "farthest-corner"
+    ),
+    "conic" => first == "from" || first == "at",
+    _ => return Err(format!("Unknown gradient type \"{}\"", name))
+  };
+  let (tokens, stop_args) = match is_config{
+    true => (tokens, &args[1..]),
+    false => (vec![], &args[..])
+  };
+
+  let center = rect.center();
+  let mut options = Options{ interpolation, ..Options::default() };
+  let gradient = match kind{
+    "linear" => {
+      // the direction is either an angle (clockwise from 12 o'clock) or a side or corner to aim at
+      let (w, h) = (rect.width(), rect.height());
+      let dir = match tokens.as_slice(){
+        [] => Point::new(0.0, 1.0),
+        [angle] => match parse_angle(angle){
+          Some(angle) => Point::new(to_radians(angle).sin(), -to_radians(angle).cos()),
+          None => return Err(format!("Could not parse \"{}\" as an angle", angle))
+        },
+        [to, sides @ ..] if to.eq_ignore_ascii_case("to") && (1..=2).contains(&sides.len()) => {
+          let (mut dx, mut dy) = (0.0, 0.0);
+          for side in sides{
+            match side.to_lowercase().as_str(){
+              "left" if dx == 0.0 => dx = -1.0,
+              "right" if dx == 0.0 => dx = 1.0,
+              "top" if dy == 0.0 => dy = -1.0,
+              "bottom" if dy == 0.0 => dy = 1.0,
+              _ => return Err(format!("Could not parse direction \"{}\"", tokens.join(" ")))
+            }
+          }
+          // corners use the line perpendicular to the diagonal between the two adjacent corners
+          let (dx, dy) = (dx * h.max(f32::EPSILON), dy * w.max(f32::EPSILON));
+          Point::new(dx, dy) * (1.0 / dx.hypot(dy))
+        },
+        _ => return Err(format!("Could not parse direction \"{}\"", tokens.join(" ")))
+      };
+
+      let length = (w * dir.x).abs() + (h * dir.y).abs();
+      let to_offset = |token:&str| parse_length(token).map(|len| len.resolve(length) / length.max(f32::EPSILON));
+      let (stops, colors) = parse_stops(stop_args, &to_offset)?;
+
+      // move the gradient's endpoints to the first & last stops so offsets beyond 0–1 are honored
+      let (first, last) = (stops[0], stops[stops.len() - 1]);
+      let span = last - first;
+      let (stops, start, end) = match span > 0.0{
+        true => (
+          stops.iter().map(|s| (s - first) / span).collect(),
+          center + dir * (length * (first - 0.5)),
+          center + dir * (length * (last - 0.5)),
+        ),
+        false => (stops, center - dir * (length / 2.0), center + dir * (length / 2.0))
+      };
+      if repeating && span > 0.0{
+        options.spread = TileMode::Repeat;
+      }
+      Gradient::Linear{ start, end, stops, colors }
+    },
+
+    "radial" => {
+      let (shape_tokens, pos_tokens) = match tokens.iter().position(|t| t.eq_ignore_ascii_case("at")){
+        Some(idx) => (&tokens[..idx], &tokens[idx + 1..]),
+        None => (&tokens[..], &tokens[tokens.len()..])
+      };
+      let center = parse_position(pos_tokens, rect)?;
+
+      let (mut shape, mut extent, mut lengths) = (None, "farthest-corner".to_string(), vec![]);
+      for token in shape_tokens{
+        match token.to_lowercase().as_str(){
+          "circle" | "ellipse" if shape.is_none() => shape = Some(token.to_lowercase()),
+          kw @ ("closest-side" | "closest-corner" | "farthest-side" | "farthest-corner") => extent = kw.to_string(),
+          _ => match parse_length(token){
+            Some(len) => lengths.push(len),
+            None => return Err(format!("Could not parse radial gradient shape \"{}\"", shape_tokens.join(" ")))
+          }
+        }
+      }
+      let is_circle = match shape.as_deref(){
+        Some(shape) => shape == "circle",
+        None => lengths.len() == 1
+      };
+
+      let sides = [center.x - rect.left, rect.right - center.x, center.y - rect.top, rect.bottom - center.y].map(f32::abs);
+      let (closest, farthest) = (
+        (sides[0].min(sides[1]), sides[2].min(sides[3])),
+        (sides[0].max(sides[1]), sides[2].max(sides[3])),
+      );
+      let (rx, ry) = match (lengths.as_slice(), is_circle){
+        ([Length::Px(r)], true) => (*r, *r),
+        ([x, y], false) => (x.resolve(rect.width()), y.resolve(rect.height())),
+        ([], _) => {
+          let (dx, dy) = match extent.starts_with("closest"){
+            true => closest,
+            false => farthest
+          };
+          match (extent.ends_with("side"), is_circle){
+            (true, true) => match extent.starts_with("closest"){
+              true => (dx.min(dy), dx.min(dy)),
+              false => (dx.max(dy), dx.max(dy)),
+            },
+            (true, false) => (dx, dy),
+            (false, true) => (dx.hypot(dy), dx.hypot(dy)),
+            (false, false) => (dx * std::f32::consts::SQRT_2, dy * std::f32::consts::SQRT_2),
+          }
+        },
+        _ => return Err(format!("Could not parse radial gradient size \"{}\"", shape_tokens.join(" ")))
+      };
+      if rx < 0.0 || ry < 0.0{
+        return Err("Radial gradient sizes cannot be negative".to_string())
+      }
+
+      let to_offset = |token:&str| parse_length(token).map(|len| len.resolve(rx) / rx.max(f32::EPSILON));
+      let (mut stops, mut colors) = parse_stops(stop_args, &to_offset)?;
+      let (first, last) = (stops[0], stops[stops.len() - 1]);
+      let span = last - first;
+      if first < 0.0{
+        if repeating && span > 0.0{
+          // shift the pattern by whole periods so it starts at a non-negative radius
+          let shift = (-first / span).ceil() * span;
+          stops.iter_mut().for_each(|s| *s += shift);
+        }else{
+          (stops, colors) = clip_stops(&stops, &colors, 0.0, last.max(0.0));
+        }
+      }
+
+      let (first, last) = (stops[0], stops[stops.len() - 1]);
+      let span = last - first;
+      let (stops, start_radius, end_radius) = match span > 0.0{
+        true => (stops.iter().map(|s| (s - first) / span).collect(), rx * first, rx * last),
+        false => (stops, 0.0, rx)
+      };
+      if repeating && span > 0.0{
+        options.spread = TileMode::Repeat;
+      }
+
+      // ellipses are circles with a non-uniform scale
+      if rx > 0.0 && ry > 0.0 && rx != ry{
+        options.matrix
+          .pre_translate((center.x, center.y))
+          .pre_scale((1.0, ry / rx), None)
+          .pre_translate((-center.x, -center.y));
+      }
+      Gradient::Radial{ start_point:center, start_radius, end_point:center, end_radius, stops, colors }
+    },
+
+    "conic" => {
+      let (from_tokens, pos_tokens) = match tokens.iter().position(|t| t.eq_ignore_ascii_case("at")){
+        Some(idx) => (&tokens[..idx], &tokens[idx + 1..]),
+        None => (&tokens[..], &tokens[tokens.len()..])
+      };
+      let start_angle = match from_tokens{
+        [] => 0.0,
+        [from, angle] if from.eq_ignore_ascii_case("from") => match parse_angle(angle){
+          Some(angle) => angle,
+          None => return Err(format!("Could not parse \"{}\" as an angle", angle))
+        },
+        _ => return Err(format!("Could not parse conic gradient origin \"{}\"", tokens.join(" ")))
+      };
+      let center = parse_position(pos_tokens, rect)?;
+
+      let to_offset = |token:&str| match parse_length(token){
+        Some(Length::Pct(pct)) => Some(pct / 100.0),
+        _ => parse_angle(token).map(|angle| angle / 360.0)
+      };
+      let (mut stops, mut colors) = parse_stops(stop_args, &to_offset)?;
+      let (first, last) = (stops[0], stops[stops.len() - 1]);
+      let span = last - first;
+      if repeating && span > 0.0{
+        // sweeps always span a full turn, so tile the stops around the circle (or, if the
+        // repetitions would be too fine to resolve, fill it with their average color instead)
+        let (start, end) = (-(first / span).ceil(), ((1.0 - first) / span).ceil());
+        if end - start > MAX_CONIC_REPEATS{
+          let color = average_color(&stops, &colors);
+          (stops, colors) = (vec![0.0, 1.0], vec![color, color]);
+        }else{
+          let (mut tiled_stops, mut tiled_colors) = (vec![], vec![]);
+          for n in start as i32..end as i32{
+            let shift = n as f32 * span;
+            tiled_stops.extend(stops.iter().map(|s| s + shift));
+            tiled_colors.extend(colors.iter().cloned());
+          }
+          (stops, colors) = (tiled_stops, tiled_colors);
+        }
+      }
+      let (stops, colors) = clip_stops(&stops, &colors, 0.0, 1.0);
+
+      // css angles start at 12 o'clock while skia's start at 3 o'clock
+      Gradient::Conic{ center, angle:start_angle - 90.0, stops, colors }
+    },
+
+    _ => return Err(format!("Unknown gradient type \"{}\"", name))
+  };
+
+  Ok(CanvasGradient{ gradient:Rc::new(RefCell::new(gradient)), options:Rc::new(RefCell::new(options)) })
+}
+
 //
 // -- Javascript Methods --------------------------------------------------------------------------
 //
@@ -125,7 +733,7 @@ pub fn linear(mut cx: FunctionContext) -> JsResult<BoxedCanvasGradient> {
   let start = Point::new(*x1, *y1);
   let end = Point::new(*x2, *y2);
   let ramp = Gradient::Linear{ start, end, stops:vec![], colors:vec![] };
-  let canvas_gradient = CanvasGradient{ gradient:Rc::new(RefCell::new(ramp)) };
+  let canvas_gradient = CanvasGradient::new(ramp);
   let this = RefCell::new(canvas_gradient);
   Ok(cx.boxed(this))
 }
@@ -137,7 +745,7 @@ pub fn radial(mut cx: FunctionContext) -> JsResult<BoxedCanvasGradient> {
   let start_point = Point::new(*x1, *y1);
   let end_point = Point::new(*x2, *y2);
   let bloom = Gradient::Radial{ start_point, start_radius:*r1, end_point, end_radius:*r2, stops:vec![], colors:vec![] };
-  let canvas_gradient = CanvasGradient{ gradient:Rc::new(RefCell::new(bloom)) };
+  let canvas_gradient = CanvasGradient::new(bloom);
   let this = RefCell::new(canvas_gradient);
   Ok(cx.boxed(this))
 }
@@ -149,7 +757,25 @@ pub fn conic(mut cx: FunctionContext) -> JsResult<BoxedCanvasGradient> {
   let center = Point::new(*x, *y);
   let angle = to_degrees(*theta);
   let sweep = Gradient::Conic{ center, angle, stops:vec![], colors:vec![] };
-  let canvas_gradient = CanvasGradient{ gradient:Rc::new(RefCell::new(sweep)) };
+  let canvas_gradient = CanvasGradient::new(sweep);
+  let this = RefCell::new(canvas_gradient);
+  Ok(cx.boxed(this))
+}
+
+pub fn css(mut cx: FunctionContext) -> JsResult<BoxedCanvasGradient> {
+  let css = string_arg(&mut cx, 1, "css")?;
+  let nums = float_args_at(&mut cx, 2, &["x", "y", "width", "height"])?;
+  let rect = Rect::from_xywh(nums[0], nums[1], nums[2], nums[3]);
+
+  match parse_css_gradient(&css, &rect){
+    Ok(canvas_gradient) => Ok(cx.boxed(RefCell::new(canvas_gradient))),
+    Err(msg) => cx.throw_type_error(format!("Could not parse CSS gradient: {}", msg))
+  }
+}
+
+pub fn mesh(mut cx: FunctionContext) -> JsResult<BoxedCanvasGradient> {
+  let weave = Gradient::Mesh{ patches:vec![], raster:RefCell::new(None) };
+  let canvas_gradient = CanvasGradient::new(weave);
   let this = RefCell::new(canvas_gradient);
   Ok(cx.boxed(this))
 }
@@ -157,6 +783,9 @@ pub fn conic(mut cx: FunctionContext) -> JsResult<BoxedCanvasGradient> {
 pub fn addColorStop(mut cx: FunctionContext) -> JsResult<JsUndefined> {
   let this = cx.argument::<BoxedCanvasGradient>(0)?;
   let mut this = this.borrow_mut();
+  if this.is_mesh(){
+    return cx.throw_type_error("Mesh gradients are defined using addPatch() rather than color stops")
+  }
 
   let offset = float_arg(&mut cx, 1, "offset")?;
   if offset < 0.0 || offset > 1.0{
@@ -175,6 +804,101 @@ pub fn addColorStop(mut cx: FunctionContext) -> JsResult<JsUndefined> {
   Ok(cx.undefined())
 }
 
+pub fn addPatch(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+  let this = cx.argument::<BoxedCanvasGradient>(0)?;
+  let mut this = this.borrow_mut();
+  if !this.is_mesh(){
+    return cx.throw_type_error("Only mesh gradients support addPatch()")
+  }
+
+  let patch = patch_args(&mut cx, 1)?;
+  if patch.colors.is_none(){
+    return cx.throw_type_error("Expected an array of 4 corner colors")
+  }
+  this.add_patch(patch);
+  Ok(cx.undefined())
+}
+
+pub fn patch_args(cx: &mut FunctionContext, idx:usize) -> NeonResult<Patch>{
+  // reads the 12 control points, 4 corner colors, and 4 texture coords of a patch starting at `idx`
+  let cubics = points_arg(cx, idx)?;
+  let colors = colors_arg(cx, idx + 1, "colors")?;
+  let tex_coords = points_arg(cx, idx + 2)?;
+
+  let cubics:[Point; 12] = match cubics.try_into(){
+    Ok(cubics) => cubics,
+    Err(pts) => return cx.throw_type_error(format!("Expected 12 control points for patch (got {})", pts.len()))
+  };
+  let colors:Option<[Color; 4]> = match colors.len(){
+    0 => None,
+    _ => match colors.try_into(){
+      Ok(colors) => Some(colors),
+      Err(colors) => return cx.throw_type_error(format!("Expected 4 corner colors for patch (got {})", colors.len()))
+    }
+  };
+  let tex_coords:Option<[Point; 4]> = match tex_coords.len(){
+    0 => None,
+    _ => match tex_coords.try_into(){
+      Ok(coords) => Some(coords),
+      Err(coords) => return cx.throw_type_error(format!("Expected 4 corner texture coordinates for patch (got {})", coords.len()))
+    }
+  };
+
+  Ok(Patch{cubics, colors, tex_coords})
+}
+
+pub fn get_spread(mut cx: FunctionContext) -> JsResult<JsString> {
+  let this = cx.argument::<BoxedCanvasGradient>(0)?;
+  let spread = from_spread_mode(this.borrow().options.borrow().spread);
+  Ok(cx.string(spread))
+}
+
+pub fn set_spread(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+  let this = cx.argument::<BoxedCanvasGradient>(0)?;
+  let name = string_arg(&mut cx, 1, "spread")?;
+
+  if let Some(spread) = to_spread_mode(&name){
+    this.borrow().options.borrow_mut().spread = spread;
+  }
+  Ok(cx.undefined())
+}
+
+pub fn get_interpolation(mut cx: FunctionContext) -> JsResult<JsString> {
+  let this = cx.argument::<BoxedCanvasGradient>(0)?;
+  let interpolation = from_interpolation(this.borrow().options.borrow().interpolation);
+  Ok(cx.string(interpolation))
+}
+
+pub fn set_interpolation(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+  let this = cx.argument::<BoxedCanvasGradient>(0)?;
+  let spec = string_arg(&mut cx, 1, "interpolation")?;
+
+  if let Some(interpolation) = to_interpolation(&spec){
+    this.borrow().options.borrow_mut().interpolation = Some(interpolation);
+  }
+  Ok(cx.undefined())
+}
+
+pub fn get_dither(mut cx: FunctionContext) -> JsResult<JsBoolean> {
+  let this = cx.argument::<BoxedCanvasGradient>(0)?;
+  let dither = this.borrow().dither();
+  Ok(cx.boolean(dither))
+}
+
+pub fn set_dither(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+  let this = cx.argument::<BoxedCanvasGradient>(0)?;
+  let dither = bool_arg(&mut cx, 1, "dither")?;
+  this.borrow().options.borrow_mut().dither = dither;
+  Ok(cx.undefined())
+}
+
+pub fn setTransform(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+  let this = cx.argument::<BoxedCanvasGradient>(0)?;
+  let matrix = matrix_arg(&mut cx, 1)?;
+  this.borrow().options.borrow_mut().matrix = matrix;
+  Ok(cx.undefined())
+}
+
 pub fn repr(mut cx: FunctionContext) -> JsResult<JsString> {
   let this = cx.argument::<BoxedCanvasGradient>(0)?;
   let this = this.borrow();
@@ -184,6 +908,7 @@ pub fn repr(mut cx: FunctionContext) -> JsResult<JsString> {
     Gradient::Linear{..} => "Linear",
     Gradient::Radial{..} => "Radial",
     Gradient::Conic{..} => "Conic",
+    Gradient::Mesh{..} => "Mesh",
   };
 
   Ok(cx.string(style))
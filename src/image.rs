@@ -4,7 +4,7 @@ use std::cell::RefCell;
 use neon::{prelude::*, types::buffer::TypedArray};
 use skia_safe::{
   Image as SkImage, ImageInfo, ISize, ColorType, ColorSpace, AlphaType, Data, Size,
-  FontMgr, Picture, PictureRecorder, Rect, image::images,
+  FontMgr, Picture, PictureRecorder, Rect, Matrix, image::{images, BitDepth},
   svg::{self, Length, LengthUnit},
 };
 use crate::utils::*;
@@ -72,6 +72,18 @@ impl Content{
     }
   }
 
+  pub fn to_bitmap(&self, scale:f32) -> Option<SkImage> {
+    // vector content is rasterized at its nominal size times `scale` (bitmaps are returned as-is)
+    match &self{
+      Content::Bitmap(img) => Some(img.clone()),
+      Content::Vector(pict, size) => images::deferred_from_picture(
+        pict, (*size * scale).to_ceil(), Some(&Matrix::scale((scale, scale))), None,
+        BitDepth::U8, Some(ColorSpace::new_srgb()), None
+      ),
+      _ => None
+    }
+  }
+
   pub fn is_complete(&self) -> bool {
     match &self{
       Content::Loading => false,
@@ -10,6 +10,7 @@ mod filter;
 mod gradient;
 mod pattern;
 mod texture;
+mod shader;
 mod font_library;
 mod typography;
 mod utils;
@@ -64,6 +65,7 @@ fn main(mut cx: ModuleContext) -> NeonResult<()> {
   cx.export_function("Path2D_round", path::round)?;
   cx.export_function("Path2D_trim", path::trim)?;
   cx.export_function("Path2D_jitter", path::jitter)?;
+  cx.export_function("Path2D_outline", path::outline)?;
   cx.export_function("Path2D_offset", path::offset)?;
   cx.export_function("Path2D_transform", path::transform)?;
   cx.export_function("Path2D_bounds", path::bounds)?;
@@ -77,7 +79,17 @@ fn main(mut cx: ModuleContext) -> NeonResult<()> {
   cx.export_function("CanvasGradient_linear", gradient::linear)?;
   cx.export_function("CanvasGradient_radial", gradient::radial)?;
   cx.export_function("CanvasGradient_conic", gradient::conic)?;
+  cx.export_function("CanvasGradient_mesh", gradient::mesh)?;
+  cx.export_function("CanvasGradient_css", gradient::css)?;
   cx.export_function("CanvasGradient_addColorStop", gradient::addColorStop)?;
+  cx.export_function("CanvasGradient_addPatch", gradient::addPatch)?;
+  cx.export_function("CanvasGradient_get_spread", gradient::get_spread)?;
+  cx.export_function("CanvasGradient_set_spread", gradient::set_spread)?;
+  cx.export_function("CanvasGradient_get_interpolation", gradient::get_interpolation)?;
+  cx.export_function("CanvasGradient_set_interpolation", gradient::set_interpolation)?;
+  cx.export_function("CanvasGradient_get_dither", gradient::get_dither)?;
+  cx.export_function("CanvasGradient_set_dither", gradient::set_dither)?;
+  cx.export_function("CanvasGradient_setTransform", gradient::setTransform)?;
   cx.export_function("CanvasGradient_repr", gradient::repr)?;
 
   // -- CanvasPattern -----------------------------------------------------------------------------
@@ -93,6 +105,19 @@ fn main(mut cx: ModuleContext) -> NeonResult<()> {
   cx.export_function("CanvasTexture_new", texture::new)?;
   cx.export_function("CanvasTexture_repr", texture::repr)?;
 
+  // -- CanvasShader ------------------------------------------------------------------------------
+
+  cx.export_function("CanvasShader_new", shader::new)?;
+  cx.export_function("CanvasShader_setUniform", shader::setUniform)?;
+  cx.export_function("CanvasShader_get_uniforms", shader::get_uniforms)?;
+  cx.export_function("CanvasShader_setTransform", shader::setTransform)?;
+  cx.export_function("CanvasShader_repr", shader::repr)?;
+
+  // -- CanvasFilter ------------------------------------------------------------------------------
+
+  cx.export_function("CanvasFilter_new", filter::new)?;
+  cx.export_function("CanvasFilter_repr", filter::repr)?;
+
   // -- FontLibrary -------------------------------------------------------------------------------
 
   cx.export_function("FontLibrary_get_families", font_library::get_families)?;
@@ -109,6 +134,11 @@ fn main(mut cx: ModuleContext) -> NeonResult<()> {
   cx.export_function("Canvas_set_engine", canvas::set_engine)?;
   cx.export_function("Canvas_get_engine_status", canvas::get_engine_status)?;
 
+  cx.export_function("Canvas_get_cacheStats", canvas::get_cacheStats)?;
+  cx.export_function("Canvas_get_cacheLimit", canvas::get_cacheLimit)?;
+  cx.export_function("Canvas_set_cacheLimit", canvas::set_cacheLimit)?;
+  cx.export_function("Canvas_purgeCache", canvas::purgeCache)?;
+
   cx.export_function("Canvas_get_width", canvas::get_width)?;
   cx.export_function("Canvas_set_width", canvas::set_width)?;
   cx.export_function("Canvas_get_height", canvas::get_height)?;
@@ -118,6 +148,9 @@ fn main(mut cx: ModuleContext) -> NeonResult<()> {
   cx.export_function("Canvas_saveSync", canvas::saveSync)?;
   cx.export_function("Canvas_toBuffer", canvas::toBuffer)?;
   cx.export_function("Canvas_toBufferSync", canvas::toBufferSync)?;
+  cx.export_function("Canvas_toStream", canvas::toStream)?;
+  cx.export_function("Canvas_exportTask", canvas::exportTask)?;
+  cx.export_function("Canvas_cancelExport", canvas::cancelExport)?;
 
   // -- Context -----------------------------------------------------------------------------------
 
@@ -130,11 +163,18 @@ fn main(mut cx: ModuleContext) -> NeonResult<()> {
   // grid state
   cx.export_function("CanvasRenderingContext2D_save", ctx::save)?;
   cx.export_function("CanvasRenderingContext2D_restore", ctx::restore)?;
+  cx.export_function("CanvasRenderingContext2D_beginLayer", ctx::beginLayer)?;
+  cx.export_function("CanvasRenderingContext2D_endLayer", ctx::endLayer)?;
+  cx.export_function("CanvasRenderingContext2D_applyFilter", ctx::applyFilter)?;
   cx.export_function("CanvasRenderingContext2D_transform", ctx::transform)?;
   cx.export_function("CanvasRenderingContext2D_translate", ctx::translate)?;
   cx.export_function("CanvasRenderingContext2D_scale", ctx::scale)?;
   cx.export_function("CanvasRenderingContext2D_rotate", ctx::rotate)?;
   cx.export_function("CanvasRenderingContext2D_resetTransform", ctx::resetTransform)?;
+  cx.export_function("CanvasRenderingContext2D_translate3d", ctx::translate3d)?;
+  cx.export_function("CanvasRenderingContext2D_scale3d", ctx::scale3d)?;
+  cx.export_function("CanvasRenderingContext2D_rotate3d", ctx::rotate3d)?;
+  cx.export_function("CanvasRenderingContext2D_perspective", ctx::perspective)?;
   cx.export_function("CanvasRenderingContext2D_get_currentTransform", ctx::get_currentTransform)?;
   cx.export_function("CanvasRenderingContext2D_set_currentTransform", ctx::set_currentTransform)?;
   cx.export_function("CanvasRenderingContext2D_createProjection", ctx::createProjection)?;
@@ -155,6 +195,11 @@ fn main(mut cx: ModuleContext) -> NeonResult<()> {
   cx.export_function("CanvasRenderingContext2D_isPointInPath", ctx::isPointInPath)?;
   cx.export_function("CanvasRenderingContext2D_isPointInStroke", ctx::isPointInStroke)?;
   cx.export_function("CanvasRenderingContext2D_clip", ctx::clip)?;
+  cx.export_function("CanvasRenderingContext2D_clipOut", ctx::clipOut)?;
+  cx.export_function("CanvasRenderingContext2D_resetClip", ctx::resetClip)?;
+  cx.export_function("CanvasRenderingContext2D_getClipBounds", ctx::getClipBounds)?;
+  cx.export_function("CanvasRenderingContext2D_isPointInClip", ctx::isPointInClip)?;
+  cx.export_function("CanvasRenderingContext2D_mask", ctx::mask)?;
 
   // fill & stroke
   cx.export_function("CanvasRenderingContext2D_fill", ctx::fill)?;
@@ -162,6 +207,8 @@ fn main(mut cx: ModuleContext) -> NeonResult<()> {
   cx.export_function("CanvasRenderingContext2D_fillRect", ctx::fillRect)?;
   cx.export_function("CanvasRenderingContext2D_strokeRect", ctx::strokeRect)?;
   cx.export_function("CanvasRenderingContext2D_clearRect", ctx::clearRect)?;
+  cx.export_function("CanvasRenderingContext2D_drawMesh", ctx::drawMesh)?;
+  cx.export_function("CanvasRenderingContext2D_drawPatch", ctx::drawPatch)?;
   cx.export_function("CanvasRenderingContext2D_get_fillStyle", ctx::get_fillStyle)?;
   cx.export_function("CanvasRenderingContext2D_set_fillStyle", ctx::set_fillStyle)?;
   cx.export_function("CanvasRenderingContext2D_get_strokeStyle", ctx::get_strokeStyle)?;
@@ -182,18 +229,30 @@ fn main(mut cx: ModuleContext) -> NeonResult<()> {
   cx.export_function("CanvasRenderingContext2D_set_lineJoin", ctx::set_lineJoin)?;
   cx.export_function("CanvasRenderingContext2D_get_lineWidth", ctx::get_lineWidth)?;
   cx.export_function("CanvasRenderingContext2D_set_lineWidth", ctx::set_lineWidth)?;
+  cx.export_function("CanvasRenderingContext2D_get_lineWidthProfile", ctx::get_lineWidthProfile)?;
+  cx.export_function("CanvasRenderingContext2D_set_lineWidthProfile", ctx::set_lineWidthProfile)?;
+  cx.export_function("CanvasRenderingContext2D_get_strokeAlign", ctx::get_strokeAlign)?;
+  cx.export_function("CanvasRenderingContext2D_set_strokeAlign", ctx::set_strokeAlign)?;
   cx.export_function("CanvasRenderingContext2D_get_miterLimit", ctx::get_miterLimit)?;
   cx.export_function("CanvasRenderingContext2D_set_miterLimit", ctx::set_miterLimit)?;
 
   // imagery
   cx.export_function("CanvasRenderingContext2D_drawImage", ctx::drawImage)?;
   cx.export_function("CanvasRenderingContext2D_drawCanvas", ctx::drawCanvas)?;
+  cx.export_function("CanvasRenderingContext2D_drawAtlas", ctx::drawAtlas)?;
+  cx.export_function("CanvasRenderingContext2D_drawImageNine", ctx::drawImageNine)?;
+  cx.export_function("CanvasRenderingContext2D_drawImageLattice", ctx::drawImageLattice)?;
+  cx.export_function("CanvasRenderingContext2D_drawImageWarped", ctx::drawImageWarped)?;
   cx.export_function("CanvasRenderingContext2D_getImageData", ctx::getImageData)?;
   cx.export_function("CanvasRenderingContext2D_putImageData", ctx::putImageData)?;
   cx.export_function("CanvasRenderingContext2D_get_imageSmoothingEnabled", ctx::get_imageSmoothingEnabled)?;
   cx.export_function("CanvasRenderingContext2D_set_imageSmoothingEnabled", ctx::set_imageSmoothingEnabled)?;
   cx.export_function("CanvasRenderingContext2D_get_imageSmoothingQuality", ctx::get_imageSmoothingQuality)?;
   cx.export_function("CanvasRenderingContext2D_set_imageSmoothingQuality", ctx::set_imageSmoothingQuality)?;
+  cx.export_function("CanvasRenderingContext2D_get_imageResampling", ctx::get_imageResampling)?;
+  cx.export_function("CanvasRenderingContext2D_set_imageResampling", ctx::set_imageResampling)?;
+  cx.export_function("CanvasRenderingContext2D_get_antialias", ctx::get_antialias)?;
+  cx.export_function("CanvasRenderingContext2D_set_antialias", ctx::set_antialias)?;
 
   // typography
   cx.export_function("CanvasRenderingContext2D_fillText", ctx::fillText)?;
@@ -238,6 +297,8 @@ fn main(mut cx: ModuleContext) -> NeonResult<()> {
   cx.export_function("CanvasRenderingContext2D_get_shadowOffsetY", ctx::get_shadowOffsetY)?;
   cx.export_function("CanvasRenderingContext2D_set_shadowOffsetX", ctx::set_shadowOffsetX)?;
   cx.export_function("CanvasRenderingContext2D_set_shadowOffsetY", ctx::set_shadowOffsetY)?;
+  cx.export_function("CanvasRenderingContext2D_get_shadows", ctx::get_shadows)?;
+  cx.export_function("CanvasRenderingContext2D_set_shadows", ctx::set_shadows)?;
 
   // -- Window -----------------------------------------------------------------------------------
 
@@ -7,7 +7,7 @@ use std::cell::RefCell;
 use std::f32::{EPSILON, consts::PI};
 use neon::prelude::*;
 use skia_safe::{Path, Point, PathFillType, PathDirection, PathBuilder, Rect, RRect, Matrix, PathOp, StrokeRec};
-use skia_safe::{PathEffect, trim_path_effect};
+use skia_safe::{PathEffect, PaintCap, PaintJoin, ContourMeasure, ContourMeasureIter, trim_path_effect};
 use skia_safe::path::{self, AddPathMode, Verb};
 
 use crate::utils::*;
@@ -95,6 +95,278 @@ impl Path2D{
   }
 }
 
+//
+// Variable-width strokes
+//
+
+const PROFILE_STEP:f32 = 2.0; // max distance between samples along the path
+const PROFILE_MAX_STEPS:f32 = 4096.0; // per-contour cap on the number of samples
+const DASH_MAX_REPEATS:f32 = 100000.0; // dash patterns finer than this are drawn as solid lines
+
+#[derive(Clone, Debug)]
+pub enum WidthProfile{
+  Stops(Vec<(f32, f32)>), // (t, width) pairs where t is relative to each contour's length
+  Pressure(Vec<f32>),     // one width for each on-curve point, in the order they were added
+}
+
+impl WidthProfile{
+  pub fn stops(mut stops:Vec<(f32, f32)>) -> Self{
+    stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+    WidthProfile::Stops(stops)
+  }
+
+  // the width-scale at a given distance along a contour whose on-curve points lie at `vertices`
+  fn scale_at(&self, dist:f32, length:f32, vertices:&[(f32, f32)]) -> f32{
+    match self{
+      WidthProfile::Stops(stops) => {
+        let t = if length > 0.0 { dist / length } else { 0.0 };
+        interpolate_stops(stops, t)
+      },
+      WidthProfile::Pressure(_) => interpolate_stops(vertices, dist)
+    }
+  }
+}
+
+// linear interpolation within a sorted list of (position, value) pairs, clamped at the ends
+fn interpolate_stops(stops:&[(f32, f32)], pos:f32) -> f32{
+  match stops.iter().position(|(at, _)| *at >= pos){
+    None => stops.last().map(|(_, val)| *val).unwrap_or(1.0),
+    Some(0) => stops[0].1,
+    Some(i) => {
+      let ((a, val_a), (b, val_b)) = (stops[i-1], stops[i]);
+      match b - a > 0.0{
+        true => val_a + (val_b - val_a) * (pos - a) / (b - a),
+        false => val_b
+      }
+    }
+  }
+}
+
+struct Contour{
+  start: Point,
+  segments: Vec<(f32, ContourMeasure)>, // each segment's measure & its offset from the contour's start
+  vertices: Vec<f32>, // distance of each user-supplied on-curve point from the contour's start
+  length: f32,
+  closed: bool,
+}
+
+impl Contour{
+  // the position & tangent at a given distance from the contour's start
+  fn pos_tan(&self, dist:f32) -> Option<(Point, Point)>{
+    let (offset, measure) = self.segments.iter().rev().find(|(offset, _)| *offset <= dist)?;
+    measure.pos_tan((dist - offset).min(measure.length()))
+  }
+}
+
+// split a path into contours whose segments can be measured (and sampled) individually
+fn measure_contours(path:&Path) -> Vec<Contour>{
+  let mut verbs = vec![0u8; path.count_verbs()];
+  path.get_verbs(&mut verbs);
+
+  let mut contours:Vec<Contour> = vec![];
+  let mut iter = path::Iter::new(path, false);
+  let mut raw_idx = 0;
+  while let Some((verb, pts)) = iter.next(){
+    // the iterator synthesizes a line back to the start point when closing a contour, but since it
+    // doesn't correspond to a user-supplied point it shouldn't consume a value from the profile
+    let is_synthetic = verb == Verb::Line && verbs.get(raw_idx) == Some(&(Verb::Close as u8));
+    if !is_synthetic{ raw_idx += 1 }
+
+    match verb{
+      Verb::Move => contours.push(Contour{
+        start:pts[0], segments:vec![], vertices:vec![0.0], length:0.0, closed:false
+      }),
+      Verb::Close => if let Some(contour) = contours.last_mut(){
+        contour.closed = true
+      },
+      _ => if let Some(contour) = contours.last_mut(){
+        let mut segment = Path::new();
+        segment.move_to(pts[0]);
+        match verb{
+          Verb::Line => segment.line_to(pts[1]),
+          Verb::Quad => segment.quad_to(pts[1], pts[2]),
+          Verb::Conic => segment.conic_to(pts[1], pts[2], iter.conic_weight().unwrap_or(1.0)),
+          _ => segment.cubic_to(pts[1], pts[2], pts[3]),
+        };
+
+        if let Some(measure) = ContourMeasureIter::new(&segment, false, None).next(){
+          let length = measure.length();
+          contour.segments.push((contour.length, measure));
+          contour.length += length;
+        }
+        if !is_synthetic{
+          contour.vertices.push(contour.length);
+        }
+      }
+    }
+  }
+  contours
+}
+
+// add a triangle to the outline, always winding in the same direction so nonzero fills take their union
+fn add_triangle(outline:&mut Path, a:Point, b:Point, c:Point){
+  let area = (b - a).cross(c - a);
+  if area.abs() > EPSILON{
+    let (b, c) = if area > 0.0 { (b, c) } else { (c, b) };
+    outline.move_to(a).line_to(b).line_to(c).close();
+  }
+}
+
+fn add_cap(outline:&mut Path, pt:Point, dir:Point, radius:f32, cap:PaintCap){
+  if radius <= EPSILON { return }
+  match cap{
+    PaintCap::Round => { outline.add_circle(pt, radius, PathDirection::CW); },
+    PaintCap::Square => {
+      let (ext, norm) = (dir * radius, Point::new(-dir.y, dir.x) * radius);
+      add_triangle(outline, pt + norm, pt + norm + ext, pt - norm + ext);
+      add_triangle(outline, pt + norm, pt - norm + ext, pt - norm);
+    },
+    _ => {}
+  }
+}
+
+fn add_join(outline:&mut Path, pt:Point, tan_in:Point, tan_out:Point, radius:f32, join:PaintJoin, miter_limit:f32){
+  if radius <= EPSILON { return }
+  if join == PaintJoin::Round{
+    outline.add_circle(pt, radius, PathDirection::CW);
+    return
+  }
+
+  // only the outer side of the turn needs filling in since the inner side is covered by the strips
+  let norm_in = Point::new(-tan_in.y, tan_in.x);
+  let side = if norm_in.dot(tan_out) > 0.0 { -1.0 } else { 1.0 };
+  let (a, b) = (norm_in * side, Point::new(-tan_out.y, tan_out.x) * side);
+  add_triangle(outline, pt, pt + a * radius, pt + b * radius);
+
+  // extend the bevel to a point if the angle is shallow enough to stay within the miter limit
+  let cos_half = ((1.0 + tan_in.dot(tan_out)) / 2.0).max(0.0).sqrt();
+  if join == PaintJoin::Miter && cos_half > EPSILON && 1.0 / cos_half <= miter_limit{
+    let mut bisector = a + b;
+    if bisector.normalize(){
+      let tip = pt + bisector * (radius / cos_half);
+      add_triangle(outline, pt + a * radius, tip, pt + b * radius);
+    }
+  }
+}
+
+// the on-intervals of a dash pattern laid along a contour of the given length
+fn dash_spans(intervals:&[f32], phase:f32, length:f32) -> Vec<(f32, f32)>{
+  let total:f32 = intervals.iter().sum();
+  if intervals.len() < 2 || total <= 0.0 || length / total > DASH_MAX_REPEATS{
+    return vec![(0.0, length)]
+  }
+
+  let mut spans = vec![];
+  let mut pos = -phase.rem_euclid(total);
+  let mut idx = 0;
+  while pos < length{
+    let next = pos + intervals[idx];
+    if idx % 2 == 0 && next > 0.0{
+      spans.push((pos.max(0.0), next.min(length)));
+    }
+    pos = next;
+    idx = (idx + 1) % intervals.len();
+  }
+  spans
+}
+
+// Returns a fillable outline of the path as if it had been stroked with a width that varies along its length
+// (optionally broken up by a list of dash intervals, which restart with each subpath just as Skia's do)
+pub fn variable_outline(path:&Path, width:f32, profile:&WidthProfile, cap:PaintCap, join:PaintJoin, miter_limit:f32, dash:Option<(&[f32], f32)>) -> Path{
+  let mut outline = Path::new();
+  let pressure = match profile{
+    WidthProfile::Pressure(values) => values.as_slice(),
+    _ => &[]
+  };
+  let mut pressure_idx = 0;
+
+  for contour in measure_contours(path){
+    // pair each on-curve point with its entry in the pressure array (repeating the last one if it runs out)
+    let mut vertices:Vec<(f32, f32)> = contour.vertices.iter().map(|dist|{
+      let scale = pressure.get(pressure_idx).or(pressure.last()).cloned().unwrap_or(1.0);
+      pressure_idx += 1;
+      (*dist, scale)
+    }).collect();
+    if contour.closed{
+      let first = vertices[0].1;
+      vertices.push((contour.length, first));
+    }
+    let radius_at = |dist:f32| (width * profile.scale_at(dist, contour.length, &vertices)).max(0.0) / 2.0;
+
+    // zero-length contours only show up if they have caps
+    if contour.segments.is_empty(){
+      if contour.vertices.len() > 1 {
+        add_cap(&mut outline, contour.start, Point::new(1.0, 0.0), radius_at(0.0), cap);
+        add_cap(&mut outline, contour.start, Point::new(-1.0, 0.0), radius_at(0.0), cap);
+      }
+      continue
+    }
+
+    // the stretches of the contour that are actually drawn
+    let spans = match dash{
+      Some((intervals, phase)) => dash_spans(intervals, phase, contour.length),
+      None => vec![(0.0, contour.length)]
+    };
+    let is_on = |dist:f32| spans.iter().any(|(on, off)| *on <= dist && dist <= *off);
+
+    let step = PROFILE_STEP.max(contour.length / PROFILE_MAX_STEPS);
+    let count = contour.segments.len();
+    for (i, (offset, measure)) in contour.segments.iter().enumerate(){
+      let length = measure.length();
+
+      // sweep a strip of triangles along each portion of the segment that falls within a span
+      for (on, off) in &spans{
+        let (from, to) = ((on - offset).max(0.0), (off - offset).min(length));
+        if to <= from { continue }
+
+        let steps = ((to - from) / step).ceil().max(1.0) as usize;
+        let mut prev:Option<(Point, Point)> = None;
+        for n in 0..=steps{
+          let dist = from + (to - from) * n as f32 / steps as f32;
+          if let Some((pt, tan)) = measure.pos_tan(dist){
+            let norm = Point::new(-tan.y, tan.x) * radius_at(offset + dist);
+            let (left, right) = (pt + norm, pt - norm);
+            if let Some((prev_left, prev_right)) = prev{
+              add_triangle(&mut outline, prev_left, left, right);
+              add_triangle(&mut outline, prev_left, right, prev_right);
+            }
+            prev = Some((left, right));
+          }
+        }
+      }
+
+      // connect this segment to the previous one
+      if (i > 0 || contour.closed) && is_on(*offset){
+        let (_, prev) = &contour.segments[(i + count - 1) % count];
+        if let (Some((_, tan_in)), Some((pt, tan_out))) = (prev.pos_tan(prev.length()), measure.pos_tan(0.0)){
+          add_join(&mut outline, pt, tan_in, tan_out, radius_at(*offset), join, miter_limit);
+        }
+      }
+    }
+
+    // cap the ends of each span (unless a closed contour's first and last spans meet at its start)
+    let wraps = contour.closed && is_on(0.0) && is_on(contour.length);
+    for (on, off) in &spans{
+      if !(wraps && *on == 0.0){
+        if let Some((pt, tan)) = contour.pos_tan(*on){
+          add_cap(&mut outline, pt, -tan, radius_at(*on), cap);
+        }
+      }
+      if !(wraps && *off == contour.length){
+        if let Some((pt, tan)) = contour.pos_tan(*off){
+          add_cap(&mut outline, pt, tan, radius_at(*off), cap);
+        }
+      }
+    }
+  }
+
+  outline.set_fill_type(PathFillType::Winding);
+  match outline.simplify(){
+    Some(simpler) => simpler,
+    None => outline
+  }
+}
+
 //
 // -- Javascript Methods --------------------------------------------------------------------------
 //
@@ -443,6 +715,18 @@ pub fn jitter(mut cx: FunctionContext) -> JsResult<BoxedPath2D> {
   Ok(cx.boxed(RefCell::new(Path2D{path:this.path.clone()})))
 }
 
+// Returns a filled outline of the path stroked with a width that varies along its length
+pub fn outline(mut cx: FunctionContext) -> JsResult<BoxedPath2D> {
+  let this = cx.argument::<BoxedPath2D>(0)?;
+  let width = float_arg(&mut cx, 1, "width")?;
+  let profile = opt_width_profile_arg(&mut cx, 2)?.unwrap_or(WidthProfile::Stops(vec![]));
+  let cap = to_stroke_cap(&string_arg_or(&mut cx, 3, "butt")).unwrap_or(PaintCap::Butt);
+
+  let this = this.borrow();
+  let path = variable_outline(&this.path, width, &profile, cap, PaintJoin::Round, 4.0, None);
+  Ok(cx.boxed(RefCell::new(Path2D{path})))
+}
+
 // Returns the computed `tight` bounds that contain all the points, control points, and connecting contours
 pub fn bounds(mut cx: FunctionContext) -> JsResult<JsObject> {
   let this = cx.argument::<BoxedPath2D>(0)?;
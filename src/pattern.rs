@@ -7,7 +7,7 @@ use skia_safe::{Shader, TileMode, Size, Rect, Matrix, FilterMode};
 use crate::utils::*;
 use crate::image::{BoxedImage, Content};
 use crate::context::BoxedContext2D;
-use crate::filter::ImageFilter;
+use crate::filter::{ImageFilter, sampling_scale};
 
 pub type BoxedCanvasPattern = JsBox<RefCell<CanvasPattern>>;
 impl Finalize for CanvasPattern {}
@@ -27,11 +27,16 @@ pub struct CanvasPattern{
 
 impl CanvasPattern{
   pub fn shader(&self, image_filter: ImageFilter) -> Option<Shader>{
+    self.shader_at_scale(image_filter, 1.0)
+  }
+
+  pub fn shader_at_scale(&self, image_filter: ImageFilter, device_scale:f32) -> Option<Shader>{
+    // `device_scale` is the scale factor of the transform the pattern will be drawn with
     let stamp = self.stamp.borrow();
 
     match &stamp.content{
       Content::Bitmap(image) =>
-        image.to_shader(stamp.repeat, image_filter.sampling(), None).map(|shader|
+        image.to_shader(stamp.repeat, image_filter.sampling(device_scale * sampling_scale(&stamp.matrix)), None).map(|shader|
           shader.with_local_matrix(&stamp.matrix)
         ),
       Content::Vector(pict, ..) => {
@@ -0,0 +1,252 @@
+#![allow(non_snake_case)]
+use std::cell::RefCell;
+use std::rc::Rc;
+use neon::prelude::*;
+use serde_json::json;
+use skia_safe::{Shader, Matrix, Data, Color4f, ImageFilter as SkImageFilter, RuntimeEffect, image_filters, shaders};
+use skia_safe::runtime_effect::{ChildPtr, ChildType, RuntimeShaderBuilder, uniform::Type as UniformType};
+
+use crate::utils::*;
+use crate::filter::{ImageFilter, FilterQuality, Resampling};
+use crate::gradient::BoxedCanvasGradient;
+use crate::pattern::BoxedCanvasPattern;
+
+pub type BoxedCanvasShader = JsBox<RefCell<CanvasShader>>;
+impl Finalize for CanvasShader {}
+
+#[derive(Clone, Debug)]
+pub struct Program{
+  effect: RuntimeEffect,
+  uniforms: Vec<u8>,
+  children: Vec<Option<ChildPtr>>,
+  matrix: Matrix,
+}
+
+#[derive(Clone, Debug)]
+pub struct CanvasShader{
+  program: Rc<RefCell<Program>>
+}
+
+impl CanvasShader{
+  pub fn new(sksl:&str) -> Result<Self, String>{
+    let effect = RuntimeEffect::make_for_shader(sksl, None)?;
+    let uniforms = vec![0; effect.uniform_size()];
+    let children = vec![None; effect.children().len()];
+    let program = Program{effect, uniforms, children, matrix:Matrix::new_identity()};
+    Ok(CanvasShader{ program:Rc::new(RefCell::new(program)) })
+  }
+
+  pub fn shader(&self) -> Option<Shader>{
+    let program = self.program.borrow();
+
+    // unassigned child shaders evaluate to transparent black
+    let children = program.effect.children().iter().zip(&program.children)
+      .map(|(child, ptr)| match (ptr, child.ty()){
+        (Some(ptr), _) => Some(ptr.clone()),
+        (None, ChildType::Shader) => Some(shaders::empty().into()),
+        _ => None
+      })
+      .collect::<Option<Vec<ChildPtr>>>()?;
+
+    program.effect.make_shader(Data::new_copy(&program.uniforms), &children, &program.matrix)
+  }
+
+  pub fn image_filter(&self, input:Option<SkImageFilter>) -> Option<SkImageFilter>{
+    // the effect's lone child shader is bound to the content being filtered
+    let program = self.program.borrow();
+    let child = program.effect.children().first()?.name().to_string();
+    let mut builder = RuntimeShaderBuilder::new(program.effect.clone());
+
+    for uniform in program.effect.uniforms(){
+      let bytes = &program.uniforms[uniform.offset()..uniform.offset() + uniform.size_in_bytes()];
+      let words = bytes.chunks_exact(4).map(|word| [word[0], word[1], word[2], word[3]]);
+      let result = match is_int(uniform.ty()){
+        true => builder.set_uniform_int(uniform.name(), &words.map(i32::from_ne_bytes).collect::<Vec<_>>()),
+        false => builder.set_uniform_float(uniform.name(), &words.map(f32::from_ne_bytes).collect::<Vec<_>>()),
+      };
+      result.ok()?;
+    }
+
+    image_filters::runtime_shader(&builder, child, input)
+  }
+
+  pub fn can_filter(&self) -> Result<(), String>{
+    let program = self.program.borrow();
+    match program.effect.children(){
+      [child] if child.ty() == ChildType::Shader => Ok(()),
+      _ => Err("Shaders used as a filter must declare exactly one `uniform shader` (which receives the content being filtered)".to_string())
+    }
+  }
+
+  pub fn snapshot(&self) -> Self{
+    // a copy that won't be affected by subsequent changes to the original's uniforms
+    let program = self.program.borrow().clone();
+    CanvasShader{ program:Rc::new(RefCell::new(program)) }
+  }
+
+  fn set_uniform(&self, name:&str, values:&[f32]) -> Result<(), String>{
+    let mut program = self.program.borrow_mut();
+    let (offset, size, ty) = match program.effect.find_uniform(name){
+      Some(uniform) => (uniform.offset(), uniform.size_in_bytes(), uniform.ty()),
+      None => return Err(unknown_uniform(&program.effect, name))
+    };
+
+    let count = size / 4;
+    if values.len() != count{
+      return Err(format!(
+        "Expected {} value{} for uniform \"{}\" (got {})", count, if count==1{""}else{"s"}, name, values.len()
+      ))
+    }
+
+    for (i, val) in values.iter().enumerate(){
+      let word = match is_int(ty){
+        true => (*val as i32).to_ne_bytes(),
+        false => val.to_ne_bytes(),
+      };
+      program.uniforms[offset + i*4..offset + i*4 + 4].copy_from_slice(&word);
+    }
+    Ok(())
+  }
+
+  fn set_child(&self, name:&str, shader:Option<Shader>) -> Result<(), String>{
+    let mut program = self.program.borrow_mut();
+    let index = match program.effect.find_child(name){
+      Some(child) if child.ty() == ChildType::Shader => child.index(),
+      Some(_) => return Err(format!("Uniform \"{}\" is not a shader", name)),
+      None => return Err(unknown_uniform(&program.effect, name))
+    };
+    program.children[index] = shader.map(ChildPtr::Shader);
+    Ok(())
+  }
+}
+
+fn is_int(ty:UniformType) -> bool{
+  matches!(ty, UniformType::Int | UniformType::Int2 | UniformType::Int3 | UniformType::Int4)
+}
+
+fn type_name(ty:UniformType) -> &'static str{
+  match ty{
+    UniformType::Float => "float",
+    UniformType::Float2 => "float2",
+    UniformType::Float3 => "float3",
+    UniformType::Float4 => "float4",
+    UniformType::Float2x2 => "float2x2",
+    UniformType::Float3x3 => "float3x3",
+    UniformType::Float4x4 => "float4x4",
+    UniformType::Int => "int",
+    UniformType::Int2 => "int2",
+    UniformType::Int3 => "int3",
+    UniformType::Int4 => "int4",
+  }
+}
+
+fn unknown_uniform(effect:&RuntimeEffect, name:&str) -> String{
+  let names:Vec<&str> = effect.uniforms().iter().map(|u| u.name())
+    .chain(effect.children().iter().map(|c| c.name()))
+    .collect();
+  match names.is_empty(){
+    true => format!("Unknown uniform \"{}\" (shader has no uniforms)", name),
+    false => format!("Unknown uniform \"{}\" (expected one of: {})", name, names.join(", "))
+  }
+}
+
+//
+// -- Javascript Methods --------------------------------------------------------------------------
+//
+
+pub fn new(mut cx: FunctionContext) -> JsResult<BoxedCanvasShader> {
+  let sksl = string_arg(&mut cx, 1, "source")?;
+  match CanvasShader::new(&sksl){
+    Ok(shader) => Ok(cx.boxed(RefCell::new(shader))),
+    Err(msg) => cx.throw_error(format!("Could not compile shader: {}", msg.trim()))
+  }
+}
+
+pub fn setUniform(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+  let this = cx.argument::<BoxedCanvasShader>(0)?;
+  let name = string_arg(&mut cx, 1, "name")?;
+  let value = cx.argument::<JsValue>(2)?;
+  let this = this.borrow();
+
+  let result = if let Ok(gradient) = value.downcast::<BoxedCanvasGradient, _>(&mut cx){
+    this.set_child(&name, gradient.borrow().shader())
+  }else if let Ok(pattern) = value.downcast::<BoxedCanvasPattern, _>(&mut cx){
+    let sampling = ImageFilter{ smoothing:true, quality:FilterQuality::Low, resampling:Resampling::Auto };
+    this.set_child(&name, pattern.borrow().shader(sampling))
+  }else if let Ok(shader) = value.downcast::<BoxedCanvasShader, _>(&mut cx){
+    this.set_child(&name, shader.borrow().shader())
+  }else if value.is_a::<JsString, _>(&mut cx){
+    match color_in(&mut cx, value){
+      Some(color) => {
+        let Color4f{r, g, b, a} = Color4f::from(color);
+        let rgba = [r, g, b, a];
+        let size = this.program.borrow().effect.find_uniform(&name).map(|u| u.size_in_bytes() / 4);
+        match size{
+          Some(len @ (3 | 4)) => this.set_uniform(&name, &rgba[..len]),
+          Some(_) => Err(format!("Colors can only be assigned to float3 or float4 uniforms (\"{}\")", name)),
+          None => this.set_uniform(&name, &rgba)
+        }
+      },
+      None => Err(format!("Expected a number, array of numbers, color, or shader for uniform \"{}\"", name))
+    }
+  }else if let Ok(array) = value.downcast::<JsArray, _>(&mut cx){
+    let vals = array.to_vec(&mut cx)?;
+    let nums = floats_in(&mut cx, &vals);
+    match nums.len() == vals.len(){
+      true => this.set_uniform(&name, &nums),
+      false => Err(format!("Expected only numbers for uniform \"{}\"", name))
+    }
+  }else{
+    Err(format!("Expected a number, array of numbers, color, or shader for uniform \"{}\"", name))
+  };
+
+  match result{
+    Ok(()) => Ok(cx.undefined()),
+    Err(msg) => cx.throw_type_error(msg)
+  }
+}
+
+pub fn get_uniforms(mut cx: FunctionContext) -> JsResult<JsString> {
+  let this = cx.argument::<BoxedCanvasShader>(0)?;
+  let this = this.borrow();
+  let program = this.program.borrow();
+
+  let mut uniforms = serde_json::Map::new();
+  for uniform in program.effect.uniforms(){
+    let ty = match uniform.is_array(){
+      true => format!("{}[{}]", type_name(uniform.ty()), uniform.count()),
+      false => type_name(uniform.ty()).to_string()
+    };
+    uniforms.insert(uniform.name().to_string(), json!(ty));
+  }
+  for child in program.effect.children(){
+    let ty = match child.ty(){
+      ChildType::Shader => "shader",
+      ChildType::ColorFilter => "colorFilter",
+      ChildType::Blender => "blender",
+    };
+    uniforms.insert(child.name().to_string(), json!(ty));
+  }
+  Ok(cx.string(serde_json::Value::Object(uniforms).to_string()))
+}
+
+pub fn setTransform(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+  let this = cx.argument::<BoxedCanvasShader>(0)?;
+  let matrix = matrix_arg(&mut cx, 1)?;
+  let this = this.borrow();
+
+  this.program.borrow_mut().matrix = matrix;
+  Ok(cx.undefined())
+}
+
+pub fn repr(mut cx: FunctionContext) -> JsResult<JsString> {
+  let this = cx.argument::<BoxedCanvasShader>(0)?;
+  let this = this.borrow();
+  let program = this.program.borrow();
+
+  let uniforms = program.effect.uniforms().len();
+  let children = program.effect.children().len();
+  Ok(cx.string(format!(
+    "{} uniform{}, {} child{}", uniforms, if uniforms==1{""}else{"s"}, children, if children==1{""}else{"ren"}
+  )))
+}
@@ -4,7 +4,7 @@ use std::ops::Range;
 use std::iter::zip;
 use neon::prelude::*;
 use serde_json::{json, Value};
-use skia_safe::{FontMetrics, Typeface, Paint, Point, Rect, Path as SkPath, Color};
+use skia_safe::{FontMetrics, Typeface, Paint, Point, Rect, Path as SkPath, Color, Canvas, BlendMode, font::Edging};
 use skia_safe::font_style::{FontStyle, Weight, Width, Slant};
 use skia_safe::textlayout::{
   Decoration, FontCollection, Paragraph, ParagraphBuilder, ParagraphStyle, RectHeightStyle, RectWidthStyle,
@@ -29,6 +29,7 @@ pub struct Typesetter{
   graf_style: ParagraphStyle,
   text_decoration: DecorationStyle,
   text_wrap: bool,
+  edging: Edging,
 }
 
 impl Typesetter{
@@ -45,14 +46,19 @@ impl Typesetter{
       false => text.replace("\n", " ")
     };
 
-    Typesetter{text, width, baseline, typefaces, char_style, graf_style, text_decoration, text_wrap}
+    let edging = state.text_edging();
+    Typesetter{text, width, baseline, typefaces, char_style, graf_style, text_decoration, text_wrap, edging}
   }
 
   pub fn layout(&self, paint:&Paint) -> (Paragraph, Point) {
+    self.layout_with_decoration(paint, paint.color())
+  }
+
+  fn layout_with_decoration(&self, paint:&Paint, decoration_color:Color) -> (Paragraph, Point) {
     let mut char_style = self.char_style.clone();
     char_style.set_foreground_paint(paint);
     char_style.set_decoration(
-      &self.text_decoration.for_layout(&char_style, paint.color())
+      &self.text_decoration.for_layout(&char_style, decoration_color)
     );
 
     // prevent SkParagraph from faking the font style if the match isn't the requested weight/slant
@@ -76,6 +82,31 @@ impl Typesetter{
     (paragraph, offset)
   }
 
+  pub fn paint(&self, canvas:&Canvas, paint:&Paint, point:Point){
+    if self.edging == Edging::AntiAlias{
+      let (paragraph, offset) = self.layout(paint);
+      return paragraph.paint(canvas, point + offset)
+    }
+
+    // SkParagraph always antialiases its glyphs, so let it draw just the decorations (using a paint
+    // that leaves the glyphs invisible) then draw each run's glyphs with a copy of its font whose
+    // edging has been changed (which preserves its hinting & subpixel positioning)
+    let mut hidden = paint.clone();
+    hidden.set_blend_mode(BlendMode::Dst);
+    let (mut paragraph, offset) = self.layout_with_decoration(&hidden, paint.color());
+    paragraph.paint(canvas, point + offset);
+
+    let mut glyph_paint = paint.clone();
+    glyph_paint.set_anti_alias(false);
+    paragraph.visit(|_, info|{
+      if let Some(info) = info{
+        let mut font = info.font().clone();
+        font.set_edging(self.edging);
+        canvas.draw_glyphs_at(info.glyphs(), info.positions(), point + offset + info.origin(), &font, &glyph_paint);
+      }
+    });
+  }
+
   pub fn metrics(&self) -> Value {
     let (mut paragraph, origin) = self.layout(&Paint::default());
     let mut line_rects:Vec<Rect> = vec![]; // accumulate line rects to calculate full bounds
@@ -4,7 +4,7 @@ use std::f32::consts::PI;
 use core::ops::Range;
 use neon::prelude::*;
 use css_color::Rgba;
-use skia_safe::{ Path, Matrix, Point, Color, RGB, Data };
+use skia_safe::{ Path, Matrix, M44, Point, Color, RGB, Data };
 
 //
 // meta-helpers
@@ -311,6 +311,10 @@ pub fn floats_in(cx: &mut FunctionContext, vals: &[Handle<JsValue>]) -> Vec<f32>
   vals.iter().filter_map(|val| _as_float(cx, val)).collect::<Vec<f32>>()
 }
 
+pub fn opt_double_arg(cx: &mut FunctionContext, idx: usize) -> Option<f64>{
+  cx.argument_opt(idx).and_then(|val| _as_double(cx, &val))
+}
+
 pub fn opt_float_arg(cx: &mut FunctionContext, idx: usize) -> Option<f32>{
   cx.argument_opt(idx).and_then(|val| _as_float(cx, &val))
 }
@@ -413,6 +417,20 @@ pub fn opt_color_arg(cx: &mut FunctionContext, idx: usize) -> Option<Color> {
   }
 }
 
+pub fn colors_arg(cx: &mut FunctionContext, idx: usize, attr:&str) -> NeonResult<Vec<Color>>{
+  // an optional array of css color strings
+  let mut colors = vec![];
+  if let Some(array) = cx.argument_opt(idx).and_then(|arg| arg.downcast::<JsArray, _>(cx).ok()){
+    for (i, val) in array.to_vec(cx)?.into_iter().enumerate(){
+      match color_in(cx, val){
+        Some(color) => colors.push(color),
+        None => return cx.throw_type_error(format!("Expected a CSS color for entry {} in `{}`", i, attr))
+      }
+    }
+  }
+  Ok(colors)
+}
+
 pub fn opt_color_for_key(cx: &mut FunctionContext, obj: &Handle<JsObject>, attr:&str) -> Option<Color>{
   obj.get(cx, attr).ok()
     .and_then(|val|
@@ -434,6 +452,58 @@ pub fn color_to_css<'a>(cx: &mut FunctionContext<'a>, color:&Color) -> JsResult<
   Ok(cx.string(css).upcast())
 }
 
+//
+// Shadows
+//
+
+use crate::context::Shadow;
+
+pub fn opt_shadows_arg(cx: &mut FunctionContext, idx: usize) -> NeonResult<Option<Vec<Shadow>>>{
+  // expects the list of {offsetX, offsetY, blur, spread, color, inset} objects produced by css.shadows()
+  let list = match cx.argument_opt(idx).and_then(|arg| arg.downcast::<JsArray, _>(cx).ok()){
+    Some(array) => array.to_vec(cx)?,
+    None => return Ok(None)
+  };
+
+  let mut shadows = vec![];
+  for val in list{
+    let obj = match val.downcast::<JsObject, _>(cx){
+      Ok(obj) => obj,
+      Err(_) => return Ok(None)
+    };
+    let color = opt_color_for_key(cx, &obj, "color");
+    let dims = ["offsetX", "offsetY", "blur", "spread"].iter()
+      .map(|key| opt_float_for_key(cx, &obj, key))
+      .collect::<Option<Vec<f32>>>();
+    match (color, dims.as_deref()){
+      (Some(color), Some([x, y, blur, spread])) if *blur >= 0.0 => shadows.push(Shadow{
+        color, offset:Point::new(*x, *y), blur:*blur, spread:*spread, inset:bool_for_key(cx, &obj, "inset")?
+      }),
+      _ => return Ok(None)
+    }
+  }
+  Ok(Some(shadows))
+}
+
+pub fn shadows_to_array<'a>(cx: &mut FunctionContext<'a>, shadows:&[Shadow]) -> JsResult<'a, JsArray>{
+  let array = JsArray::new(cx, shadows.len());
+  for (i, shadow) in shadows.iter().enumerate(){
+    let obj = cx.empty_object();
+    let color = color_to_css(cx, &shadow.color)?;
+    obj.set(cx, "color", color)?;
+    for (key, val) in [
+      ("offsetX", shadow.offset.x), ("offsetY", shadow.offset.y), ("blur", shadow.blur), ("spread", shadow.spread)
+    ]{
+      let num = cx.number(val);
+      obj.set(cx, key, num)?;
+    }
+    let inset = cx.boolean(shadow.inset);
+    obj.set(cx, "inset", inset)?;
+    array.set(cx, i as u32, obj)?;
+  }
+  Ok(array)
+}
+
 //
 // Matrices
 //
@@ -476,6 +546,22 @@ pub fn opt_matrix_arg(cx: &mut FunctionContext, idx: usize) -> Option<Matrix>{
   None
 }
 
+pub fn opt_m44_arg(cx: &mut FunctionContext, idx: usize) -> Option<M44>{
+  // accepts either the 6/9 terms of a 2D matrix or all 16 terms of a 3D one (in row-major order)
+  if let Some(arg) = cx.argument_opt(idx) {
+    if let Ok(array) = arg.downcast::<JsArray, _>(cx) {
+      if let Ok(vals) = array.to_vec(cx){
+        let terms = floats_in(cx, &vals);
+        return match terms.len(){
+          16 => terms.try_into().ok().map(|t:[f32; 16]| M44::row_major(&t)),
+          _ => to_matrix(&terms).map(M44::from)
+        }
+      }
+    }
+  }
+  None
+}
+
 pub fn matrix_arg(cx: &mut FunctionContext, idx:usize) -> NeonResult<Matrix> {
   match opt_matrix_arg(cx, idx){
     Some(v) => Ok(v),
@@ -679,7 +765,7 @@ pub fn export_options_arg(cx: &mut FunctionContext, idx: usize) -> NeonResult<Ex
 // Path2D
 //
 
-use crate::path::{BoxedPath2D};
+use crate::path::{BoxedPath2D, WidthProfile};
 
 pub fn opt_skpath_arg(cx: &mut FunctionContext, idx:usize) -> Option<Path> {
   if let Some(arg) = cx.argument_opt(idx){
@@ -702,11 +788,57 @@ pub fn path2d_arg<'a>(cx: &mut FunctionContext<'a>, idx: usize) -> NeonResult<Ha
   }
 }
 
+pub fn opt_width_profile_arg(cx: &mut FunctionContext, idx:usize) -> NeonResult<Option<WidthProfile>>{
+  let arg = match cx.argument_opt(idx){
+    Some(arg) if !(arg.is_a::<JsNull, _>(cx) || arg.is_a::<JsUndefined, _>(cx)) => arg,
+    _ => return Ok(None)
+  };
+
+  let list = match arg.downcast::<JsArray, _>(cx){
+    Ok(array) => array.to_vec(cx)?,
+    Err(_) => return cx.throw_type_error("Expected an array of widths or [t, width] pairs (or null)")
+  };
+
+  // a flat list of numbers is treated as per-point pressure values
+  let widths = floats_in(cx, &list);
+  if widths.len() == list.len(){
+    return Ok(Some(WidthProfile::Pressure(widths)))
+  }
+
+  let mut stops = vec![];
+  for val in list{
+    let pair = match val.downcast::<JsArray, _>(cx){
+      Ok(pair) => pair.to_vec(cx)?,
+      Err(_) => vec![]
+    };
+    match floats_in(cx, &pair).as_slice(){
+      [t, width] if pair.len() == 2 => stops.push((*t, *width)),
+      _ => return cx.throw_type_error("Expected an array of widths or [t, width] pairs (or null)")
+    }
+  }
+  Ok(Some(WidthProfile::stops(stops)))
+}
+
+pub fn width_profile_to_array<'a>(cx: &mut FunctionContext<'a>, profile:&WidthProfile) -> JsResult<'a, JsValue>{
+  match profile{
+    WidthProfile::Pressure(widths) => floats_to_array(cx, widths),
+    WidthProfile::Stops(stops) => {
+      let array = JsArray::new(cx, stops.len());
+      for (i, (t, width)) in stops.iter().enumerate(){
+        let pair = floats_to_array(cx, &[*t, *width])?;
+        array.set(cx, i as u32, pair)?;
+      }
+      Ok(array.upcast())
+    }
+  }
+}
+
 //
 // Filters
 //
 
-use crate::filter::{FilterSpec, FilterQuality};
+use crate::filter::{Filter, FilterSpec, FilterGraph, FilterQuality, Resampling, BoxedCanvasFilter};
+use crate::shader::BoxedCanvasShader;
 
 pub fn filter_arg(cx: &mut FunctionContext, idx: usize) -> NeonResult<(String, Vec<FilterSpec>)> {
   let arg = cx.argument::<JsObject>(idx)?;
@@ -728,6 +860,14 @@ pub fn filter_arg(cx: &mut FunctionContext, idx: usize) -> NeonResult<(String, V
           });
         }
       },
+      url if url.starts_with("url(") => {
+        // svg filter definitions arrive as a json list of primitives
+        let json = obj.get::<JsString, _, _>(cx, key)?.value(cx);
+        match serde_json::from_str(&json){
+          Ok(primitives) => filters.push(FilterSpec::Graph(FilterGraph::new(primitives, vec![], false))),
+          Err(e) => return cx.throw_type_error(format!("Invalid filter definition for {}: {}", url, e))
+        }
+      },
       _ => {
         let value = obj.get::<JsNumber, _, _>(cx, key)?.value(cx) as f32;
         filters.push(FilterSpec::Plain{
@@ -739,6 +879,31 @@ pub fn filter_arg(cx: &mut FunctionContext, idx: usize) -> NeonResult<(String, V
   Ok( (canonical, filters) )
 }
 
+pub fn opt_filter_arg(cx: &mut FunctionContext, idx: usize) -> NeonResult<Option<Filter>> {
+  // accepts either a parsed css filter string, a CanvasShader, or a CanvasFilter
+  let arg = match cx.argument_opt(idx){
+    Some(arg) => arg,
+    None => return Ok(None)
+  };
+
+  if let Ok(filter) = arg.downcast::<BoxedCanvasFilter, _>(cx){
+    return Ok(Some(filter.borrow().clone()))
+  }
+
+  if let Ok(shader) = arg.downcast::<BoxedCanvasShader, _>(cx){
+    let shader = shader.borrow();
+    return match shader.can_filter(){
+      Ok(()) => Ok(Some(Filter::from_shader(&shader))),
+      Err(msg) => cx.throw_type_error(msg)
+    }
+  }
+
+  match arg.is_a::<JsObject, _>(cx){
+    true => filter_arg(cx, idx).map(|(filter_text, specs)| Some(Filter::new(&filter_text, &specs))),
+    false => Ok(None)
+  }
+}
+
 pub fn to_filter_quality(mode_name:&str) -> Option<FilterQuality>{
   let mode = match mode_name.to_lowercase().as_str(){
     "low" => FilterQuality::Low,
@@ -758,6 +923,32 @@ pub fn from_filter_quality(mode:FilterQuality) -> String{
   }.to_string()
 }
 
+pub fn to_resampling(mode_name:&str) -> Option<Resampling>{
+  let mode = match mode_name.to_lowercase().as_str(){
+    "auto" => Resampling::Auto,
+    "nearest" => Resampling::Nearest,
+    "linear" => Resampling::Linear,
+    "mipmap" => Resampling::Mipmap,
+    "mitchell" => Resampling::Mitchell,
+    "catmull-rom" => Resampling::CatmullRom,
+    "lanczos3" => Resampling::Lanczos3,
+    _ => return None
+  };
+  Some(mode)
+}
+
+pub fn from_resampling(mode:Resampling) -> String{
+  match mode{
+    Resampling::Auto => "auto",
+    Resampling::Nearest => "nearest",
+    Resampling::Linear => "linear",
+    Resampling::Mipmap => "mipmap",
+    Resampling::Mitchell => "mitchell",
+    Resampling::CatmullRom => "catmull-rom",
+    Resampling::Lanczos3 => "lanczos3",
+  }.to_string()
+}
+
 //
 // CanvasPattern
 //
@@ -791,6 +982,89 @@ pub fn to_repeat_mode(repeat:&str) -> Option<(TileMode, TileMode)> {
   Some(mode)
 }
 
+pub fn to_spread_mode(spread:&str) -> Option<TileMode> {
+  let mode = match spread.to_lowercase().as_str() {
+    "pad" => TileMode::Clamp,
+    "repeat" => TileMode::Repeat,
+    "reflect" => TileMode::Mirror,
+    "decal" => TileMode::Decal,
+    _ => return None
+  };
+  Some(mode)
+}
+
+pub fn from_spread_mode(mode:TileMode) -> String {
+  match mode {
+    TileMode::Clamp => "pad",
+    TileMode::Repeat => "repeat",
+    TileMode::Mirror => "reflect",
+    TileMode::Decal => "decal",
+  }.to_string()
+}
+
+use skia_safe::gradient_shader::{Interpolation, interpolation::{ColorSpace as InColorSpace, HueMethod, InPremul}};
+pub fn to_interpolation(spec:&str) -> Option<Interpolation> {
+  // parses a CSS-style `<color-space> [<hue-method> hue]` string (with an optional leading "in")
+  let spec = spec.to_lowercase();
+  let mut words:Vec<&str> = spec.split_whitespace().collect();
+  if words.first() == Some(&"in"){
+    words.remove(0);
+  }
+
+  let (space, hue) = match words.as_slice(){
+    [space] => (*space, "shorter"),
+    [space, hue, "hue"] => (*space, *hue),
+    _ => return None
+  };
+
+  let color_space = match space{
+    "srgb" => InColorSpace::SRGB,
+    "srgb-linear" => InColorSpace::SRGBLinear,
+    "lab" => InColorSpace::Lab,
+    "oklab" => InColorSpace::OKLab,
+    "lch" => InColorSpace::LCH,
+    "oklch" => InColorSpace::OKLCH,
+    "hsl" => InColorSpace::HSL,
+    "hwb" => InColorSpace::HWB,
+    _ => return None
+  };
+
+  let is_polar = matches!(color_space, InColorSpace::LCH | InColorSpace::OKLCH | InColorSpace::HSL | InColorSpace::HWB);
+  let hue_method = match hue{
+    "shorter" => HueMethod::Shorter,
+    "longer" if is_polar => HueMethod::Longer,
+    "increasing" if is_polar => HueMethod::Increasing,
+    "decreasing" if is_polar => HueMethod::Decreasing,
+    _ => return None
+  };
+
+  Some(Interpolation{ in_premul:InPremul::Yes, color_space, hue_method })
+}
+
+pub fn from_interpolation(interpolation:Option<Interpolation>) -> String {
+  let Interpolation{color_space, hue_method, ..} = match interpolation{
+    Some(interpolation) => interpolation,
+    None => return "srgb".to_string()
+  };
+
+  let space = match color_space{
+    InColorSpace::SRGBLinear => "srgb-linear",
+    InColorSpace::Lab => "lab",
+    InColorSpace::OKLab => "oklab",
+    InColorSpace::LCH => "lch",
+    InColorSpace::OKLCH => "oklch",
+    InColorSpace::HSL => "hsl",
+    InColorSpace::HWB => "hwb",
+    _ => "srgb"
+  };
+  match hue_method{
+    HueMethod::Longer => format!("{} longer hue", space),
+    HueMethod::Increasing => format!("{} increasing hue", space),
+    HueMethod::Decreasing => format!("{} decreasing hue", space),
+    _ => space.to_string()
+  }
+}
+
 use skia_safe::{PaintCap};
 pub fn to_stroke_cap(mode_name:&str) -> Option<PaintCap>{
   let mode = match mode_name.to_lowercase().as_str(){
@@ -829,6 +1103,25 @@ pub fn from_stroke_join(mode:PaintJoin) -> String{
   }.to_string()
 }
 
+use crate::context::StrokeAlign;
+pub fn to_stroke_align(mode_name:&str) -> Option<StrokeAlign>{
+  let mode = match mode_name.to_lowercase().as_str(){
+    "center" => StrokeAlign::Center,
+    "inside" => StrokeAlign::Inside,
+    "outside" => StrokeAlign::Outside,
+    _ => return None
+  };
+  Some(mode)
+}
+
+pub fn from_stroke_align(mode:StrokeAlign) -> String{
+  match mode{
+    StrokeAlign::Center => "center",
+    StrokeAlign::Inside => "inside",
+    StrokeAlign::Outside => "outside",
+  }.to_string()
+}
+
 
 use skia_safe::{BlendMode};
 pub fn to_blend_mode(mode_name:&str) -> Option<BlendMode>{
@@ -913,6 +1206,17 @@ pub fn to_path_op(op_name:&str) -> Option<PathOp> {
   Some(op)
 }
 
+use skia_safe::vertices::VertexMode;
+pub fn to_vertex_mode(mode_name:&str) -> Option<VertexMode>{
+  let mode = match mode_name.to_lowercase().as_str(){
+    "triangles" => VertexMode::Triangles,
+    "strip" => VertexMode::TriangleStrip,
+    "fan" => VertexMode::TriangleFan,
+    _ => return None
+  };
+  Some(mode)
+}
+
 use skia_safe::path_1d_path_effect;
 pub fn to_1d_style(mode_name:&str) -> Option<path_1d_path_effect::Style>{
   let mode = match mode_name.to_lowercase().as_str(){
@@ -299,6 +299,35 @@ describe("Canvas", ()=>{
 
     })
 
+    test("filename templates", async () => {
+      canvas.newPage(200, 100)
+      canvas.newPage(50, 50)
+
+      await canvas.toFile(`${TMP}/{format}/{width}x{height}/{label}-{index:2}@{density}x.{format}`, {
+        density: 2,
+        perPage: ["wide", {label:"square", format:"jpg", density:1, matte:"white"}, undefined]
+      })
+
+      let expected = ["png/512x512/-00@2x.png", "png/200x100/wide-01@2x.png", "jpg/50x50/square-02@1x.jpg"]
+      for (let name of expected) assert.ok(fs.existsSync(`${TMP}/${name}`), name)
+
+      let img = new Image()
+      img.src = `${TMP}/png/200x100/wide-01@2x.png`
+      await img.decode()
+      assert.equal(img.width, 400)
+      assert.equal(img.height, 200)
+
+      let jpg = fs.readFileSync(`${TMP}/jpg/50x50/square-02@1x.jpg`)
+      assert.ok(jpg.slice(0, MAGIC.jpg.length).equals(MAGIC.jpg))
+
+      // single files can use the non-sequence tokens too
+      await canvas.toFile(`${TMP}/single/page-{width}.{format}`, {format:'svg'})
+      assert.ok(fs.existsSync(`${TMP}/single/page-50.svg`))
+
+      assert.throws(() => canvas.toFile(`${TMP}/{}.png`, {perPage:[{format:'gif'}]}), /Unsupported file format/)
+      assert.throws(() => canvas.toFile(`${TMP}/{}.png`, {perPage:[{density:0.5}]}), /Expected a non-negative integer/)
+    })
+
     test("multi-page PDFs", async () => {
       let colors = ['orange', 'yellow', 'green', 'skyblue', 'purple']
       colors.forEach((color, i) => {
@@ -317,6 +346,119 @@ describe("Canvas", ()=>{
       assert(header.equals(MAGIC.pdf))
     })
 
+    test("progress reports", async () => {
+      for (let i=0; i<3; i++) canvas.newPage().fillRect(0, 0, 10, 10)
+
+      let reports = [],
+          progress = (done, total) => reports.push([done, total])
+      await canvas.toBuffer("pdf", {progress})
+      assert.deepEqual(reports, [[1, 4], [2, 4], [3, 4], [4, 4]])
+
+      reports = []
+      await canvas.toFile(`${TMP}/frame-{}.png`, {progress})
+      assert.equal(reports.length, 4)
+      assert.deepEqual(reports.map(([done]) => done).sort(), [1, 2, 3, 4])
+
+      reports = []
+      await canvas.toBuffer("png", {progress})
+      assert.deepEqual(reports, [[1, 1]])
+
+      assert.throws(() => canvas.toBuffer("png", {progress:"yes"}), /Expected a function/)
+    })
+
+    test("cancellable exports", async () => {
+      for (let i=0; i<20; i++) canvas.newPage().fillRect(0, 0, 10, 10)
+
+      // already aborted
+      let controller = new AbortController()
+      controller.abort()
+      await assert.rejects(canvas.toBuffer("pdf", {signal:controller.signal}), {name:'AbortError'})
+
+      // aborted mid-render
+      controller = new AbortController()
+      let progress = () => controller.abort(new Error("client went away"))
+      await assert.rejects(
+        canvas.toFile(`${TMP}/frame-{}.png`, {signal:controller.signal, progress}),
+        /client went away/
+      )
+
+      // unaborted signals have no effect
+      controller = new AbortController()
+      let pdf = await canvas.toBuffer("pdf", {signal:controller.signal})
+      assert.ok(pdf.slice(0, MAGIC.pdf.length).equals(MAGIC.pdf))
+
+      assert.throws(() => canvas.toBuffer("pdf", {signal:{}}), /Expected an AbortSignal/)
+    })
+
+    test("streams & file descriptors", async () => {
+      const {PassThrough} = require('stream')
+      for (let i=0; i<3; i++) canvas.newPage().fillRect(0, 0, 10, 10)
+
+      // writable stream
+      let stream = new PassThrough(),
+          chunks = []
+      stream.on('data', chunk => chunks.push(chunk))
+      await canvas.toFile(stream, {format:'pdf'})
+      let pdf = Buffer.concat(chunks)
+      assert.ok(pdf.slice(0, MAGIC.pdf.length).equals(MAGIC.pdf))
+      assert.ok(stream.writableEnded)
+
+      // leave the stream open if requested
+      stream = new PassThrough()
+      stream.resume()
+      await canvas.toFile(stream, {format:'png', end:false})
+      assert.ok(!stream.writableEnded)
+      stream.end()
+
+      // file descriptor
+      let path = `${TMP}/from-fd.png`,
+          fd = fs.openSync(path, 'w')
+      await canvas.toFile(fd, {format:'png'})
+      fs.closeSync(fd)
+      let png = fs.readFileSync(path)
+      assert.ok(png.slice(0, MAGIC.png.length).equals(MAGIC.png))
+      assert.ok(png.equals(await canvas.toBuffer('png')))
+
+      // format is required
+      assert.throws(() => canvas.toFile(new PassThrough()), /Cannot determine image format/)
+    })
+
+    test("stream backpressure", async () => {
+      const {Writable} = require('stream')
+      for (let i=0; i<20; i++) canvas.newPage().fillRect(0, 0, 10, 10)
+
+      // a slow destination should never have more than one chunk waiting in its buffer
+      let pending = 0, maxPending = 0, chunks = []
+      let stream = new Writable({
+        highWaterMark: 1,
+        write(chunk, encoding, callback){
+          maxPending = Math.max(maxPending, ++pending)
+          chunks.push(chunk)
+          setTimeout(() => { pending--; callback() }, 2)
+        }
+      })
+
+      await canvas.toFile(stream, {format:'pdf'})
+      let pdf = Buffer.concat(chunks)
+      assert.ok(pdf.slice(0, MAGIC.pdf.length).equals(MAGIC.pdf))
+      assert.equal(maxPending, 1)
+
+      // errors raised by the destination while the export is waiting for it should be reported
+      let broken = new Writable({
+        highWaterMark: 1,
+        write(chunk, encoding, callback){ setTimeout(() => callback(new Error("disk full")), 2) }
+      })
+      await assert.rejects(canvas.toFile(broken, {format:'pdf'}), /disk full/)
+
+      // as should streams that are destroyed (without an error) while the export waits for them to drain
+      let closing = new Writable({
+        highWaterMark: 1,
+        write(chunk, encoding, callback){ setTimeout(() => closing.destroy(), 2) }
+      })
+      await assert.rejects(canvas.toFile(closing, {format:'pdf'}), /closed before the export/)
+      await assert.rejects(canvas.toFile(closing, {format:'pdf'}), /closed before the export/)
+    })
+
     test("image Buffers", async () => {
       for (let ext of ["png", "jpg", "pdf", "svg"]){
         // use extension to specify type
@@ -558,4 +700,53 @@ describe("Canvas", ()=>{
     })
   })
 
+
+  describe("page cache", ()=>{
+    afterEach(() => {
+      Canvas.cacheLimit = Infinity
+      Canvas.purgeCache()
+    })
+
+    test("reports stats", () => {
+      Canvas.purgeCache()
+      let {hits, misses} = Canvas.cacheStats
+      ctx.fillRect(0, 0, 10, 10)
+      canvas.toBufferSync("raw")
+      ctx.fillRect(10, 10, 10, 10)
+      canvas.toBufferSync("raw")
+
+      let stats = Canvas.cacheStats
+      assert.equal(stats.entries, 1)
+      assert.equal(stats.bytes, WIDTH * HEIGHT * 4)
+      assert.equal(stats.gpu.bytes + stats.cpu.bytes, stats.bytes)
+      assert.equal(stats.limit, null)
+      assert.ok(stats.hits > hits)
+      assert.ok(stats.misses > misses)
+    })
+
+    test("evicts least recently used bitmaps", () => {
+      Canvas.purgeCache()
+      Canvas.cacheLimit = WIDTH * HEIGHT * 4 * 1.5
+      assert.equal(Canvas.cacheStats.limit, WIDTH * HEIGHT * 4 * 1.5)
+
+      let canvases = [canvas, new Canvas(WIDTH, HEIGHT)]
+      for (const cnv of canvases){
+        cnv.getContext("2d").fillRect(0, 0, 10, 10)
+        cnv.toBufferSync("raw")
+      }
+      assert.equal(Canvas.cacheStats.entries, 1)
+
+      Canvas.cacheLimit = null
+      assert.equal(Canvas.cacheLimit, Infinity)
+      assert.equal(Canvas.cacheStats.limit, Infinity)
+      canvases.forEach(cnv => cnv.toBufferSync("raw", {density:2}))
+      assert.equal(Canvas.cacheStats.entries, 2)
+
+      Canvas.purgeCache()
+      assert.equal(Canvas.cacheStats.entries, 0)
+      assert.equal(Canvas.cacheStats.bytes, 0)
+      assert.throws(() => Canvas.cacheLimit = -1, /non-negative/)
+    })
+  })
+
 })
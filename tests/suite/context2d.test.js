@@ -3,7 +3,7 @@
 "use strict"
 
 const {assert, describe, test, beforeEach, afterEach} = require('../runner'),
-      {Canvas, DOMMatrix, DOMPoint, ImageData, Path2D, FontLibrary, loadImage} = require('../../lib'),
+      {Canvas, CanvasFilter, DOMMatrix, DOMPoint, ImageData, Path2D, FontLibrary, FilterLibrary, loadImage} = require('../../lib'),
       css = require('../../lib/classes/css')
 
 const BLACK = [0,0,0,255],
@@ -111,6 +111,46 @@ describe("Context2D", ()=>{
       }
     })
 
+    test('imageResampling', () => {
+      let vals = ["nearest", "linear", "mipmap", "mitchell", "catmull-rom", "lanczos3", "auto"]
+
+      assert.equal(ctx.imageResampling, 'auto')
+      ctx.imageResampling = 'invalid'
+      assert.equal(ctx.imageResampling, 'auto')
+
+      for (let val of vals){
+        ctx.imageResampling = val
+        assert.equal(ctx.imageResampling, val)
+      }
+
+      // a 1px checkerboard should shrink to an even gray rather than aliasing
+      let src = new Canvas(100, 100),
+          srcCtx = src.getContext('2d')
+      srcCtx.fillStyle = 'white'
+      srcCtx.fillRect(0, 0, 100, 100)
+      srcCtx.fillStyle = 'black'
+      for (let y=0; y<100; y++){
+        for (let x=y % 2; x<100; x+=2) srcCtx.fillRect(x, y, 1, 1)
+      }
+
+      ctx.imageResampling = 'nearest'
+      ctx.drawImage(src, 0, 0, 10, 10)
+      assert(pixel(5, 5)[0] == 0 || pixel(5, 5)[0] == 255)
+
+      ctx.imageResampling = 'lanczos3'
+      ctx.drawImage(src, 20, 0, 10, 10)
+      let [r, g, b, a] = pixel(25, 5)
+      assert(Math.abs(r - 128) < 8 && r == g && g == b)
+      assert.equal(a, 255)
+
+      // 'high' quality switches from cubic to mipmapped sampling when shrinking
+      ctx.imageResampling = 'auto'
+      ctx.imageSmoothingQuality = 'high'
+      ctx.drawImage(src, 40, 0, 24, 24)
+      let grays = ctx.getImageData(42, 2, 20, 20).data.filter((_, i) => i % 4 == 0)
+      assert(grays.every(r => Math.abs(r - 128) < 16))
+    })
+
     test('lineCap', () => {
       let vals = ["butt", "square", "round"]
 
@@ -158,6 +198,148 @@ describe("Context2D", ()=>{
       assert.equal(ctx.lineWidth, 10)
     })
 
+    test('lineWidthProfile', () => {
+      assert.strictEqual(ctx.lineWidthProfile, null)
+      ctx.lineWidthProfile = [[1, 0.5], [0, 2]]
+      assert.deepEqual(ctx.lineWidthProfile, [[0, 2], [1, 0.5]])
+      ctx.lineWidthProfile = [0.5, 1, 0.25]
+      assert.deepEqual(ctx.lineWidthProfile, [0.5, 1, 0.25])
+      assert.throws(() => ctx.lineWidthProfile = 'wide', /Expected an array/)
+      assert.deepEqual(ctx.lineWidthProfile, [0.5, 1, 0.25])
+      ctx.lineWidthProfile = null
+      assert.strictEqual(ctx.lineWidthProfile, null)
+
+      ctx.lineWidth = 40
+      ctx.lineWidthProfile = [[0, 0], [1, 1]]
+      ctx.beginPath()
+      ctx.moveTo(100, 100)
+      ctx.lineTo(300, 100)
+      ctx.stroke()
+      assert.deepEqual(pixel(110, 90), CLEAR)
+      assert.deepEqual(pixel(290, 85), BLACK)
+      assert(!ctx.isPointInStroke(110, 90))
+      assert(ctx.isPointInStroke(290, 85))
+
+      // dashes & joins still apply to variable-width strokes
+      ctx.lineWidth = 20
+      ctx.lineWidthProfile = [[0, 1], [1, 1]]
+      ctx.setLineDash([20, 20])
+      ctx.beginPath()
+      ctx.moveTo(100, 200)
+      ctx.lineTo(300, 200)
+      ctx.stroke()
+      assert.deepEqual(pixel(110, 200), BLACK)
+      assert.deepEqual(pixel(130, 200), CLEAR)
+      assert.deepEqual(pixel(150, 200), BLACK)
+      assert(!ctx.isPointInStroke(130, 200))
+
+      ctx.setLineDash([])
+      for (let [join, corner] of [['miter', BLACK], ['bevel', CLEAR], ['round', CLEAR]]){
+        ctx.clearRect(0, 0, WIDTH, HEIGHT)
+        ctx.lineJoin = join
+        ctx.beginPath()
+        ctx.moveTo(100, 300)
+        ctx.lineTo(200, 300)
+        ctx.lineTo(200, 400)
+        ctx.stroke()
+        assert.deepEqual(pixel(208, 292), corner)
+      }
+    })
+
+    test('strokeAlign', () => {
+      let vals = ["center", "inside", "outside"]
+
+      assert.equal(ctx.strokeAlign, 'center')
+      ctx.strokeAlign = 'invalid'
+      assert.equal(ctx.strokeAlign, 'center')
+
+      for (let val of vals){
+        ctx.strokeAlign = val
+        assert.equal(ctx.strokeAlign, val)
+      }
+
+      ctx.lineWidth = 20
+      ctx.strokeAlign = 'inside'
+      ctx.strokeRect(100, 100, 100, 100)
+      assert.deepEqual(pixel(95, 150), CLEAR)
+      assert.deepEqual(pixel(105, 150), BLACK)
+      assert.deepEqual(pixel(125, 150), CLEAR)
+      ctx.beginPath()
+      ctx.rect(100, 100, 100, 100)
+      assert(!ctx.isPointInStroke(95, 150))
+      assert(ctx.isPointInStroke(115, 150))
+
+      canvas.width = WIDTH
+      ctx.lineWidth = 20
+      ctx.strokeAlign = 'outside'
+      ctx.strokeRect(100, 100, 100, 100)
+      assert.deepEqual(pixel(85, 150), BLACK)
+      assert.deepEqual(pixel(105, 150), CLEAR)
+      ctx.beginPath()
+      ctx.rect(100, 100, 100, 100)
+      assert(ctx.isPointInStroke(85, 150))
+      assert(!ctx.isPointInStroke(105, 150))
+
+      // text strokes are aligned to the glyph outlines
+      let ink = () => ctx.getImageData(0, 0, WIDTH, HEIGHT).data.filter((_, i) => i % 4 == 3)
+      ctx.font = 'bold 160px Arial, DejaVu Sans'
+      ctx.lineWidth = 12
+      ctx.strokeAlign = 'center'
+      ctx.clearRect(0, 0, WIDTH, HEIGHT)
+      ctx.fillText('HI', 50, 400)
+      let glyphs = ink()
+      for (let align of ['inside', 'outside']){
+        ctx.clearRect(0, 0, WIDTH, HEIGHT)
+        ctx.strokeAlign = align
+        ctx.strokeText('HI', 50, 400)
+        let stroke = ink(),
+            strays = stroke.filter((a, i) => a == 255 && glyphs[i] == (align == 'inside' ? 0 : 255))
+        assert(stroke.some(a => a == 255))
+        assert.equal(strays.length, 0)
+      }
+    })
+
+    test('antialias', () => {
+      let alphas = (x, y, w, h) => new Set(ctx.getImageData(x, y, w, h).data.filter((_, i) => i % 4 == 3))
+
+      assert.strictEqual(ctx.antialias, true)
+      ctx.antialias = 'invalid'
+      assert.strictEqual(ctx.antialias, true)
+      for (let val of [false, "auto", true]){
+        ctx.antialias = val
+        assert.strictEqual(ctx.antialias, val)
+      }
+
+      // edges that fall between pixels are normally blended
+      ctx.fillRect(10.25, 10, 10, 10)
+      let [,,, alpha] = pixel(10, 15)
+      assert(alpha > 0 && alpha < 255)
+
+      // but can be drawn without partial coverage
+      ctx.antialias = false
+      ctx.fillRect(30.25, 10, 10, 10)
+      assert.deepEqual(pixel(30, 15), BLACK)
+      assert.deepEqual(pixel(40, 15), CLEAR)
+      ctx.beginPath()
+      ctx.arc(100, 100, 20.5, 0, 2 * Math.PI)
+      ctx.fill()
+      assert.deepEqual([...alphas(75, 75, 50, 50)].sort(), [0, 255])
+
+      // glyphs are rendered with aliased edges too
+      ctx.font = '40px Arial, DejaVu Sans'
+      ctx.fillText('Swag', 300, 300)
+      assert.deepEqual([...alphas(290, 250, 150, 70)].sort(), [0, 255])
+
+      // 'auto' mode only snaps rectilinear shapes to the pixel grid
+      ctx.antialias = "auto"
+      ctx.fillRect(50.25, 10, 10, 10)
+      assert.deepEqual(pixel(50, 15), BLACK)
+      ctx.beginPath()
+      ctx.arc(200, 100, 20.5, 0, 2 * Math.PI)
+      ctx.fill()
+      assert(alphas(175, 75, 50, 50).size > 2)
+    })
+
     test('textAlign', () => {
       let vals = ["start", "end", "left", "center", "right", "justify"]
 
@@ -403,6 +585,122 @@ describe("Context2D", ()=>{
         assert.deepEqual(pixel(256, 500), WHITE)
         assert.deepEqual(pixel(256, 5), BLACK)
       })
+
+      test("options", () => {
+        // a hard edge between red & blue halfway along a 10px ramp
+        let gradient = ctx.createLinearGradient(0,0,10,0);
+        gradient.addColorStop(0,'red');
+        gradient.addColorStop(.5,'red');
+        gradient.addColorStop(.5,'blue');
+        gradient.addColorStop(1,'blue');
+        ctx.fillStyle = gradient;
+
+        assert.equal(gradient.spread, 'pad')
+        ctx.fillRect(0,0,30,1);
+        assert.deepEqual(pixel(12,0), [0,0,255,255])
+
+        gradient.spread = 'repeat'
+        ctx.fillRect(0,0,30,1);
+        assert.deepEqual(pixel(12,0), [255,0,0,255])
+
+        gradient.spread = 'reflect'
+        ctx.fillRect(0,0,30,1);
+        assert.deepEqual(pixel(12,0), [0,0,255,255])
+
+        gradient.spread = 'decal'
+        ctx.clearRect(0,0,30,1);
+        ctx.fillRect(0,0,30,1);
+        assert.deepEqual(pixel(15,0), CLEAR)
+
+        gradient.spread = 'nonesuch'
+        assert.equal(gradient.spread, 'decal')
+
+        // transforms apply to the gradient's geometry
+        gradient.spread = 'pad'
+        gradient.setTransform(new DOMMatrix().scale(2, 1))
+        ctx.fillRect(0,0,30,1);
+        assert.deepEqual(pixel(8,0), [255,0,0,255])
+        assert.deepEqual(pixel(12,0), [0,0,255,255])
+
+        // interpolation uses CSS color-space names
+        assert.equal(gradient.interpolation, 'srgb')
+        gradient.interpolation = 'in OKLCH longer hue'
+        assert.equal(gradient.interpolation, 'oklch longer hue')
+        gradient.interpolation = 'oklab longer hue'
+        assert.equal(gradient.interpolation, 'oklch longer hue')
+
+        let ramp = ctx.createLinearGradient(0,0,100,0);
+        ramp.addColorStop(0,'red');
+        ramp.addColorStop(1,'blue');
+        ctx.fillStyle = ramp;
+        ctx.fillRect(0,0,100,1);
+        let [srgbRed] = pixel(50,0)
+
+        ramp.interpolation = 'srgb-linear'
+        ctx.fillRect(0,0,100,1);
+        let [linearRed] = pixel(50,0)
+        assert.ok(linearRed > srgbRed + 40)
+
+        assert.equal(ramp.dither, false)
+        ramp.dither = true
+        assert.equal(ramp.dither, true)
+      })
+
+      test("CSS", () => {
+        const RED = [255,0,0,255], BLUE = [0,0,255,255]
+        let fill = (css, ...rect) => {
+          ctx.fillStyle = ctx.createCSSGradient(css, ...rect)
+          ctx.clearRect(...rect)
+          ctx.fillRect(...rect)
+        }
+
+        fill("linear-gradient(to right, red 50%, blue 50%)", 0, 0, 100, 10)
+        assert.deepEqual(pixel(25, 5), RED)
+        assert.deepEqual(pixel(75, 5), BLUE)
+
+        fill("linear-gradient(0.5turn, red 50%, blue 50%)", 0, 0, 10, 100)
+        assert.deepEqual(pixel(5, 25), RED)
+        assert.deepEqual(pixel(5, 75), BLUE)
+
+        fill("repeating-linear-gradient(to right, red 0 10px, blue 10px 20px)", 0, 0, 100, 10)
+        assert.deepEqual(pixel(5, 5), RED)
+        assert.deepEqual(pixel(15, 5), BLUE)
+        assert.deepEqual(pixel(45, 5), RED)
+        assert.deepEqual(pixel(55, 5), BLUE)
+
+        fill("radial-gradient(circle closest-side at 50px 50px, red 50%, blue 50%)", 0, 0, 100, 100)
+        assert.deepEqual(pixel(50, 50), RED)
+        assert.deepEqual(pixel(85, 50), BLUE)
+        assert.deepEqual(pixel(50, 85), BLUE)
+
+        fill("radial-gradient(closest-side, red 98%, transparent)", 0, 0, 200, 100)
+        assert.deepEqual(pixel(190, 50), RED)
+        assert.deepEqual(pixel(100, 95), RED)
+        assert.deepEqual(pixel(190, 90), CLEAR)
+
+        fill("conic-gradient(from 90deg, red 0 50%, blue 50% 100%)", 0, 0, 100, 100)
+        assert.deepEqual(pixel(50, 90), RED)
+        assert.deepEqual(pixel(50, 10), BLUE)
+
+        // repetitions too fine to be drawn are replaced by their average color
+        fill("repeating-conic-gradient(red 0deg, blue 0.000001deg)", 0, 0, 100, 100)
+        let [r, g, b, a] = pixel(20, 30)
+        assert.ok(Math.abs(r - 128) < 8 && g == 0 && Math.abs(b - 128) < 8 && a == 255)
+
+        // color hints shift the midpoint of the transition
+        fill("linear-gradient(to right, black, 10%, white)", 0, 0, 100, 10)
+        let [dark] = pixel(10, 5), [light] = pixel(50, 5)
+        assert.ok(dark > 100 && dark < 150)
+        assert.ok(light > 190)
+
+        let gradient = ctx.createCSSGradient("linear-gradient(in oklch longer hue, red, blue)")
+        assert.equal(gradient.interpolation, 'oklch longer hue')
+
+        assert.throws(() => ctx.createCSSGradient("sideways-gradient(red, blue)"), /Unknown gradient type/)
+        assert.throws(() => ctx.createCSSGradient("linear-gradient(to middle, red, blue)"), /Could not parse direction/)
+        assert.throws(() => ctx.createCSSGradient("linear-gradient(red, nocolor)"), /as a color/)
+        assert.throws(() => ctx.createCSSGradient("linear-gradient(red, 50%)"), /color hints only between/)
+      })
     })
 
     describe("CanvasTexture", () => {
@@ -484,6 +782,101 @@ describe("Context2D", ()=>{
       canvas.gpu = gpu
     })
 
+    test("filter url()", () => {
+      let ids = FilterLibrary.use(`<svg xmlns="http://www.w3.org/2000/svg">
+        <filter id="shift"><feOffset dx="20" dy="0"/></filter>
+        <filter id="recolor">
+          <feFlood flood-color="#00f" result="fill"/>
+          <feComposite in="fill" in2="SourceAlpha" operator="in"/>
+        </filter>
+      </svg>`)
+      assert.deepEqual(ids, ['shift', 'recolor'])
+      assert.deepEqual(FilterLibrary.ids, ['shift', 'recolor'])
+      assert(FilterLibrary.has('shift'))
+
+      ctx.filter = 'url(#shift)'
+      assert.equal(ctx.filter, 'url(#shift)')
+      ctx.fillRect(0, 0, 20, 20)
+      assert.deepEqual(pixel(10, 10), CLEAR)
+      assert.deepEqual(pixel(30, 10), BLACK)
+
+      // results can be referenced by name and mixed with css filters
+      ctx.filter = 'url(#recolor) opacity(100%)'
+      assert.equal(ctx.filter, 'url(#recolor) opacity(100%)')
+      ctx.fillRect(100, 0, 20, 20)
+      assert.deepEqual(pixel(110, 10), [0, 0, 255, 255])
+      assert.deepEqual(pixel(130, 10), CLEAR)
+
+      // references to unknown filters are ignored
+      ctx.filter = 'url(#missing)'
+      assert.equal(ctx.filter, 'url(#recolor) opacity(100%)')
+
+      // primitives with invalid attributes pass their input through rather than resetting the chain
+      FilterLibrary.use(`<svg xmlns="http://www.w3.org/2000/svg">
+        <filter id="broken">
+          <feOffset dx="20" dy="0"/>
+          <feConvolveMatrix order="3" kernelMatrix="0 0 0 0 1 0 0 0 0" targetX="7"/>
+        </filter>
+      </svg>`)
+      ctx.filter = 'opacity(50%) url(#broken)'
+      ctx.fillRect(200, 0, 20, 20)
+      assert.deepEqual(pixel(210, 10), CLEAR)
+      assert.ok(Math.abs(pixel(230, 10)[3] - 128) <= 1)
+
+      FilterLibrary.reset()
+      assert(!FilterLibrary.has('shift'))
+      assert.deepEqual(FilterLibrary.ids, [])
+    })
+
+    test("CanvasFilter", () => {
+      let shift = new CanvasFilter({name:'offset', dx:20})
+      ctx.filter = shift
+      assert.equal(ctx.filter, shift)
+      ctx.fillRect(0, 0, 20, 20)
+      assert.deepEqual(pixel(10, 10), CLEAR)
+      assert.deepEqual(pixel(30, 10), BLACK)
+
+      // lengths are measured in canvas pixels regardless of the transform
+      ctx.save()
+      ctx.scale(2, 2)
+      ctx.fillRect(0, 50, 10, 10)
+      ctx.restore()
+      assert.deepEqual(pixel(10, 110), CLEAR)
+      assert.deepEqual(pixel(30, 110), BLACK)
+      assert.deepEqual(pixel(45, 110), CLEAR)
+
+      // lists of primitives form a chain unless connected by name
+      ctx.filter = new CanvasFilter([
+        {name:'flood', floodColor:'#00f', result:'fill'},
+        {name:'composite', in:'fill', in2:'SourceAlpha', operator:'in'},
+        {name:'colorMatrix', values:[
+          0, 0, 1, 0, 0,
+          0, 1, 0, 0, 0,
+          1, 0, 0, 0, 0,
+          0, 0, 0, 1, 0,
+        ]},
+      ])
+      ctx.fillRect(100, 0, 20, 20)
+      assert.deepEqual(pixel(110, 10), [255, 0, 0, 255])
+
+      // image nodes can supply the second input
+      let src = new Canvas(10, 10),
+          srcCtx = src.getContext('2d')
+      srcCtx.fillStyle = '#0f0'
+      srcCtx.fillRect(0, 0, 10, 10)
+      ctx.filter = new CanvasFilter([
+        {name:'image', image:src, result:'img'},
+        {name:'composite', in:'img', in2:'SourceGraphic', operator:'over'},
+      ])
+      ctx.fillRect(0, 200, 20, 20)
+      assert.deepEqual(pixel(5, 5), [0, 255, 0, 255])
+      assert.deepEqual(pixel(15, 210), BLACK)
+
+      assert.throws(() => new CanvasFilter({name:'feGaussianBlur'}), /Unsupported filter primitive/)
+      assert.throws(() => new CanvasFilter({name:'image', image:'nope'}), /Expected an Image/)
+      assert.throws(() => new CanvasFilter(), /not enough arguments/)
+    })
+
     test('shadow', async() => {
       const sin = Math.sin(1.15*Math.PI)
       const cos = Math.cos(1.15*Math.PI)
@@ -501,6 +894,385 @@ describe("Context2D", ()=>{
       assert.notEqual(pixel(143, 117), BLACK)
     })
 
+    test('shadows', () => {
+      ctx.shadows = "0 2px 4px red, inset 1px 1px 0 2px rgba(0,0,255,.2)"
+      assert.deepEqual(ctx.shadows, [
+        {color:'#ff0000', offsetX:0, offsetY:2, blur:4, spread:0, inset:false},
+        {color:'rgba(0, 0, 255, 0.2)', offsetX:1, offsetY:1, blur:0, spread:2, inset:true},
+      ])
+
+      // invalid lists are ignored
+      ctx.shadows = "2px red"
+      assert.equal(ctx.shadows.length, 2)
+      ctx.shadows = [{offsetX:2, blur:-1}]
+      assert.equal(ctx.shadows.length, 2)
+      ctx.shadows = "none"
+      assert.deepEqual(ctx.shadows, [])
+
+      // spread grows the shadow beyond the shape's edges
+      ctx.fillStyle = 'white'
+      ctx.shadows = [{spread:10, color:'black'}]
+      ctx.fillRect(50, 50, 50, 50)
+      assert.deepEqual(pixel(75, 75), WHITE)
+      assert.deepEqual(pixel(45, 75), BLACK)
+      assert.deepEqual(pixel(35, 75), CLEAR)
+
+      // inset shadows are drawn within the shape
+      ctx.shadows = "inset 10px 0 black"
+      ctx.fillRect(150, 50, 50, 50)
+      assert.deepEqual(pixel(145, 75), CLEAR)
+      assert.deepEqual(pixel(155, 75), BLACK)
+      assert.deepEqual(pixel(185, 75), WHITE)
+
+      // the first shadow in the list is drawn on top
+      ctx.shadows = "0 0 0 10px black, 0 0 0 20px white"
+      ctx.fillRect(250, 50, 50, 50)
+      assert.deepEqual(pixel(245, 75), BLACK)
+      assert.deepEqual(pixel(235, 75), WHITE)
+    })
+
+    test('beginLayer() & endLayer()', () => {
+      // the layer's alpha is applied to the group as a whole rather than to each shape
+      ctx.fillStyle = 'red'
+      ctx.beginLayer({alpha:0.5})
+      ctx.fillRect(0, 0, 20, 20)
+      ctx.fillRect(10, 0, 20, 20)
+      assert.deepEqual(pixel(15, 10), CLEAR) // not visible until the layer is closed
+      ctx.endLayer()
+      assert.deepEqual(pixel(15, 10), pixel(5, 10))
+      assert.ok(Math.abs(pixel(15, 10)[3] - 128) <= 1)
+
+      // compositing state is reset within the layer and restored afterward
+      ctx.globalAlpha = 0.25
+      ctx.globalCompositeOperation = 'multiply'
+      ctx.filter = 'blur(2px)'
+      ctx.beginLayer()
+      assert.equal(ctx.globalAlpha, 1)
+      assert.equal(ctx.globalCompositeOperation, 'source-over')
+      assert.equal(ctx.filter, 'none')
+
+      // restore() can't unwind past the start of the layer but endLayer() discards any unbalanced saves
+      ctx.fillStyle = 'blue'
+      ctx.restore()
+      assert.equal(ctx.fillStyle, '#0000ff')
+      ctx.save()
+      ctx.translate(100, 100)
+      ctx.endLayer()
+      assert.equal(ctx.globalAlpha, 0.25)
+      assert.equal(ctx.globalCompositeOperation, 'multiply')
+      assert.equal(ctx.filter, 'blur(2px)')
+      assert.equal(ctx.fillStyle, '#ff0000')
+      assert.equal(ctx.getTransform().e, 0)
+
+      // layers can be nested
+      ctx.reset()
+      ctx.beginLayer({alpha:0.5})
+      ctx.beginLayer({compositeOperation:'source-over', filter:'none'})
+      ctx.fillRect(50, 50, 10, 10)
+      ctx.endLayer()
+      assert.deepEqual(pixel(55, 55), CLEAR)
+      ctx.endLayer()
+      assert.ok(Math.abs(pixel(55, 55)[3] - 128) <= 1)
+
+      // shapes covering the whole canvas within a layer don't erase the content beneath it
+      ctx.reset()
+      ctx.fillStyle = 'blue'
+      ctx.fillRect(0, 0, 10, 10)
+      ctx.fillStyle = 'red'
+      ctx.beginLayer({alpha:0.5})
+      ctx.fillRect(0, 0, WIDTH, HEIGHT)
+      ctx.endLayer()
+      let [r, g, b, a] = pixel(5, 5)
+      assert(r > 100 && b > 100 && g == 0 && a == 255)
+      assert.ok(Math.abs(pixel(50, 50)[3] - 128) <= 1)
+
+      ctx.beginLayer({alpha:0.5})
+      ctx.clearRect(0, 0, WIDTH, HEIGHT)
+      ctx.endLayer()
+      assert.ok(Math.abs(pixel(50, 50)[3] - 128) <= 1)
+
+      // masks in effect at beginLayer() are only applied once, when the group is composited
+      let stencil = new Canvas(100, 100),
+          sctx = stencil.getContext('2d')
+      sctx.fillStyle = 'rgba(255,255,255,0.5)'
+      sctx.fillRect(0, 0, 100, 100)
+      ctx.reset()
+      ctx.save()
+      ctx.mask(stencil)
+      ctx.beginLayer()
+      ctx.fillRect(0, 0, 100, 100)
+      ctx.endLayer()
+      ctx.restore()
+      assert.ok(Math.abs(pixel(50, 50)[3] - 128) <= 1)
+
+      assert.throws(() => ctx.endLayer(), /without a matching beginLayer/)
+    })
+
+    test('applyFilter()', () => {
+      ctx.fillStyle = 'red'
+      ctx.fillRect(0, 0, 100, 100)
+
+      // only the region is affected
+      ctx.applyFilter('grayscale(100%)', 0, 0, 50, 50)
+      let [r, g, b, a] = pixel(25, 25)
+      assert(r > 0 && r == g && g == b && a == 255)
+      assert.deepEqual(pixel(75, 25), [255, 0, 0, 255])
+
+      // regions can be rect-like objects or paths and are positioned by the current transform
+      ctx.applyFilter('invert(100%)', {x:50, y:50, width:50, height:50})
+      assert.deepEqual(pixel(75, 75), [0, 255, 255, 255])
+
+      let path = new Path2D()
+      path.rect(0, 0, 50, 50)
+      ctx.translate(50, 0)
+      ctx.applyFilter('invert(100%)', path)
+      assert.deepEqual(pixel(75, 25), [0, 255, 255, 255])
+      assert.deepEqual(pixel(25, 75), [255, 0, 0, 255])
+      ctx.resetTransform()
+
+      // blurring the backdrop softens edges within the region but not outside it
+      ctx.fillStyle = 'black'
+      ctx.fillRect(200, 0, 100, 200)
+      ctx.applyFilter('blur(8px)', 250, 100, 100, 100)
+      let [,,, alpha] = pixel(300, 150)
+      assert(alpha > 0 && alpha < 255)
+      assert.deepEqual(pixel(301, 50), CLEAR)
+
+      // reading back a small crop still filters the full backdrop the region samples from
+      ctx.fillRect(400, 300, 50, 100)
+      ctx.applyFilter('blur(8px)', 400, 300, 100, 100)
+      let edge = pixel(452, 350), idx = (350 * WIDTH + 452) * 4
+      assert(edge[3] > 0 && edge[3] < 255)
+      assert.deepEqual(Array.from(ctx.getImageData(0, 0, WIDTH, HEIGHT).data.slice(idx, idx + 4)), edge)
+
+      // the clip applies when no region is given, and a filter of 'none' does nothing
+      ctx.save()
+      ctx.rect(0, 0, 50, 100)
+      ctx.clip()
+      ctx.applyFilter('invert(100%)')
+      ctx.restore()
+      assert.deepEqual(pixel(25, 75), [0, 255, 255, 255])
+      assert.deepEqual(pixel(75, 75), [0, 255, 255, 255])
+      ctx.applyFilter('none')
+      assert.deepEqual(pixel(25, 75), [0, 255, 255, 255])
+
+      assert.throws(() => ctx.applyFilter(), /not enough arguments/)
+      assert.throws(() => ctx.applyFilter('invalid'), /Expected a filter/)
+    })
+
+    test('clipOut() & resetClip()', () => {
+      let hole = new Path2D()
+      hole.rect(50, 50, 100, 100)
+
+      ctx.clipOut(hole)
+      ctx.fillRect(0, 0, 200, 200)
+      assert.deepEqual(pixel(25, 25), BLACK)
+      assert.deepEqual(pixel(100, 100), CLEAR)
+
+      // clip() & clipOut() accumulate
+      canvas.width = WIDTH
+      ctx.rect(0, 0, 100, 100)
+      ctx.clip()
+      ctx.beginPath()
+      ctx.rect(25, 25, 50, 50)
+      ctx.clipOut('evenodd')
+      ctx.fillRect(0, 0, 200, 200)
+      assert.deepEqual(pixel(10, 10), BLACK)
+      assert.deepEqual(pixel(50, 50), CLEAR)
+      assert.deepEqual(pixel(150, 150), CLEAR)
+
+      // resetClip() leaves the rest of the state alone
+      ctx.fillStyle = 'red'
+      ctx.translate(10, 10)
+      ctx.resetClip()
+      assert.equal(ctx.fillStyle, '#ff0000')
+      assert.equal(ctx.getTransform().e, 10)
+      ctx.fillRect(40, 40, 10, 10)
+      assert.deepEqual(pixel(55, 55), [255, 0, 0, 255])
+    })
+
+    test('getClipBounds() & isPointInClip()', () => {
+      assert.matchesSubset(ctx.getClipBounds(), {left:0, top:0, width:WIDTH, height:HEIGHT})
+      assert(ctx.isPointInClip(10, 10))
+      assert(!ctx.isPointInClip(-10, 10))
+
+      ctx.rect(100, 100, 50, 50)
+      ctx.clip()
+      assert.matchesSubset(ctx.getClipBounds(), {left:100, top:100, right:150, bottom:150})
+      assert(ctx.isPointInClip(125, 125))
+      assert(!ctx.isPointInClip(10, 10))
+
+      // bounds are reported in the current coordinate system but hit-testing uses canvas coordinates
+      ctx.translate(100, 100)
+      assert.matchesSubset(ctx.getClipBounds(), {left:0, top:0, right:50, bottom:50})
+      assert(ctx.isPointInClip(125, 125))
+
+      ctx.clipOut(new Path2D("M0,0 h25 v25 h-25 Z"))
+      assert(!ctx.isPointInClip(110, 110))
+      assert(ctx.isPointInClip(140, 140))
+
+      ctx.resetClip()
+      assert(ctx.isPointInClip(10, 10))
+    })
+
+    test('mask()', () => {
+      // left half opaque white, right half transparent
+      let stencil = new Canvas(100, 100),
+          sctx = stencil.getContext("2d")
+      sctx.fillStyle = 'white'
+      sctx.fillRect(0, 0, 50, 100)
+
+      ctx.save()
+      ctx.mask(stencil)
+      ctx.fillRect(0, 0, 100, 100)
+      assert.deepEqual(pixel(25, 50), BLACK)
+      assert.deepEqual(pixel(75, 50), CLEAR)
+      assert.deepEqual(pixel(150, 50), CLEAR) // areas outside the source are masked out
+
+      // the mask goes away with restore()
+      ctx.restore()
+      ctx.fillRect(0, 0, 100, 100)
+      assert.deepEqual(pixel(75, 50), BLACK)
+
+      // luminance masks use brightness rather than opacity
+      canvas.width = WIDTH
+      sctx.fillStyle = 'black'
+      sctx.fillRect(50, 0, 50, 100)
+      ctx.save()
+      ctx.mask(stencil, {mode:"luminance"})
+      ctx.fillRect(0, 0, 100, 100)
+      assert.deepEqual(pixel(25, 50), BLACK)
+      assert.deepEqual(pixel(75, 50), CLEAR)
+      ctx.restore()
+
+      // masks can be transformed & accept ImageData sources
+      canvas.width = WIDTH
+      ctx.save()
+      ctx.mask(sctx.getImageData(0, 0, 100, 100), {mode:"luminance", transform:new DOMMatrix().translate(100, 0)})
+      ctx.fillRect(0, 0, 200, 100)
+      assert.deepEqual(pixel(25, 50), CLEAR)
+      assert.deepEqual(pixel(125, 50), BLACK)
+      assert.deepEqual(pixel(175, 50), CLEAR)
+      ctx.restore()
+
+      assert.throws(() => ctx.mask(stencil, {mode:"invalid"}), /Expected mask mode/)
+      assert.throws(() => ctx.mask({}), /Expected an Image or a Canvas/)
+    })
+
+    test('drawMesh()', () => {
+      // flat fillStyle with a triangle strip
+      ctx.fillStyle = 'blue'
+      ctx.drawMesh([0,0, 20,0, 0,20, 20,20], {mode:'strip'})
+      assert.deepEqual(pixel(5, 5), [0,0,255,255])
+      assert.deepEqual(pixel(15, 15), [0,0,255,255])
+
+      // per-vertex colors with shared vertices
+      ctx.drawMesh([[100, 0], [200, 0], [200, 100], [100, 100]], {
+        colors: ['red', 'red', 'red', 'red'],
+        indices: [0, 1, 2, 0, 2, 3],
+      })
+      assert.deepEqual(pixel(150, 50), [255,0,0,255])
+
+      // texture coordinates map the fillStyle pattern onto the mesh
+      let src = new Canvas(2, 1),
+          srcCtx = src.getContext('2d')
+      srcCtx.fillStyle = 'lime'
+      srcCtx.fillRect(0, 0, 1, 1)
+      srcCtx.fillStyle = 'black'
+      srcCtx.fillRect(1, 0, 1, 1)
+      ctx.imageSmoothingEnabled = false
+      ctx.fillStyle = ctx.createPattern(src, 'no-repeat')
+      ctx.drawMesh([{x:0, y:200}, {x:200, y:200}, {x:200, y:300}, {x:0, y:300}], {
+        texCoords: [[0, 0], [2, 0], [2, 1], [0, 1]],
+        mode: 'fan',
+      })
+      assert.deepEqual(pixel(50, 250), [0,255,0,255])
+      assert.deepEqual(pixel(150, 250), BLACK)
+
+      assert.throws(() => ctx.drawMesh([0,0, 1,0, 0,1], {indices:[0, 1, 3]}), /between 0 and 2/)
+      assert.throws(() => ctx.drawMesh([0,0, 1,0, 0,1], {colors:['red']}), /one entry per vertex/)
+      assert.throws(() => ctx.drawMesh([0,0, 1,0, 0,1], {mode:'quads'}), /Expected `mode`/)
+    })
+
+    test('createMeshGradient() & drawPatch()', () => {
+      let square = (x, y, w) => [
+        [x, y], [x + w/3, y], [x + w*2/3, y],
+        [x + w, y], [x + w, y + w/3], [x + w, y + w*2/3],
+        [x + w, y + w], [x + w*2/3, y + w], [x + w/3, y + w],
+        [x, y + w], [x, y + w*2/3], [x, y + w/3],
+      ]
+
+      let mesh = ctx.createMeshGradient()
+      mesh.addPatch(square(0, 0, 100), ['red', 'red', 'red', 'red'])
+      ctx.fillStyle = mesh
+      ctx.fillRect(0, 0, 200, 200)
+      assert.deepEqual(pixel(50, 50), [255,0,0,255])
+      assert.deepEqual(pixel(150, 150), CLEAR) // transparent beyond the patch's edges
+
+      // corner colors are blended across the patch
+      ctx.drawPatch(square(200, 0, 100), {colors:['black', 'white', 'white', 'black']})
+      let [left] = pixel(210, 50),
+          [right] = pixel(290, 50)
+      assert.ok(left < 64 && right > 192)
+
+      // patches are drawn at the output's full resolution rather than via a fixed-scale bitmap
+      let edge = new Canvas(2, 1),
+          edgeCtx = edge.getContext('2d')
+      edgeCtx.fillStyle = 'white'
+      edgeCtx.fillRect(1, 0, 1, 1)
+      edgeCtx.fillStyle = 'black'
+      edgeCtx.fillRect(0, 0, 1, 1)
+
+      let sharp = new Canvas(20, 20),
+          sharpCtx = sharp.getContext('2d')
+      sharpCtx.imageSmoothingEnabled = false
+      sharpCtx.fillStyle = sharpCtx.createPattern(edge, 'no-repeat')
+      sharpCtx.drawPatch(square(0, 0, 20), {texCoords:[[0, 0], [2, 0], [2, 1], [0, 1]]})
+      let raw = sharp.toBufferSync('raw', {density:4}),
+          row = 40 * 80 * 4
+      assert.deepEqual([...raw.slice(row + 39*4, row + 39*4 + 3)], [0, 0, 0])
+      assert.deepEqual([...raw.slice(row + 40*4, row + 40*4 + 3)], [255, 255, 255])
+
+      assert.throws(() => mesh.addColorStop(0, 'red'), /addPatch/)
+      assert.throws(() => mesh.addPatch([0, 0, 10, 10], ['red', 'red', 'red', 'red']), /Expected 12 control points/)
+      assert.throws(() => mesh.addPatch(square(0, 0, 10), ['red']), /Expected 4 corner colors/)
+      assert.throws(() => ctx.createLinearGradient(0, 0, 1, 1).addPatch(square(0, 0, 10), []), /Only mesh gradients/)
+    })
+
+    test('createShader()', () => {
+      let solid = ctx.createShader(`
+        uniform half4 color;
+        half4 main(float2 coord){ return color; }
+      `)
+      assert.deepEqual(solid.uniforms, {color:'float4'})
+      assert.equal(solid.setUniform('color', 'lime'), solid)
+      ctx.fillStyle = solid
+      ctx.fillRect(0, 0, 10, 10)
+      assert.deepEqual(pixel(5, 5), [0,255,0,255])
+
+      // uniform changes don't affect a shader that has already been assigned
+      solid.setUniform('color', [0, 0, 1, 1])
+      ctx.fillRect(10, 0, 10, 10)
+      assert.deepEqual(pixel(15, 5), [0,255,0,255])
+      ctx.fillStyle = solid
+      ctx.fillRect(20, 0, 10, 10)
+      assert.deepEqual(pixel(25, 5), [0,0,255,255])
+
+      // shaders with a single child shader can be used as filters
+      ctx.filter = ctx.createShader(`
+        uniform shader image;
+        half4 main(float2 coord){ half4 c = image.eval(coord); return half4(c.a - c.rgb, c.a); }
+      `)
+      ctx.fillStyle = 'white'
+      ctx.fillRect(0, 20, 10, 10)
+      assert.deepEqual(pixel(5, 25), BLACK)
+
+      assert.throws(() => ctx.createShader('half4 main(float2 p){ return nope; }'), /Could not compile shader/)
+      assert.throws(() => solid.setUniform('colour', 'red'), /Unknown uniform "colour"/)
+      assert.throws(() => solid.setUniform('color', [1, 0]), /Expected 4 values/)
+      assert.throws(() => { ctx.filter = solid }, /exactly one `uniform shader`/)
+    })
+
     test("clip()", () => {
       ctx.fillStyle = 'white'
       ctx.fillRect(0, 0, 2, 2)
@@ -695,6 +1467,44 @@ describe("Context2D", ()=>{
       }
     })
 
+    test('getImageData() after partial reads', () => {
+      // small reads only bring their own region up to date, so make sure
+      // later reads see everything that was drawn in the meantime
+      const draw = (ctx, peek=()=>{}) => {
+        ctx.fillStyle = 'red'
+        ctx.fillRect(0, 0, 10, 10)
+        peek(5, 5)
+
+        ctx.fillStyle = 'blue'
+        ctx.fillRect(100, 100, 10, 10)
+        ctx.fillStyle = 'rgba(0,255,0,0.5)'
+        ctx.fillRect(5, 5, 100, 100)
+        peek(5, 5)
+        peek(50, 50)
+
+        ctx.shadowColor = 'black'
+        ctx.shadowOffsetX = 200
+        ctx.fillRect(300, 0, 10, 10)
+        peek(105, 105)
+        peek(505, 5)
+
+        ctx.shadowOffsetX = 0
+        ctx.clearRect(0, 0, 8, 8)
+        ctx.lineWidth = 20
+        ctx.strokeRect(200, 200, 50, 50)
+        peek(200, 200)
+      }
+
+      let reference = new Canvas(WIDTH, HEIGHT).getContext("2d")
+      draw(reference)
+      draw(ctx, pixel)
+
+      assert.deepEqual(pixel(2, 2), CLEAR)
+      assert.deepEqual(pixel(185, 200), CLEAR)
+      let [actual, expected] = [ctx, reference].map(c => Buffer.from(c.getImageData(0, 0, WIDTH, HEIGHT).data))
+      assert.ok(actual.equals(expected))
+    })
+
     test('putImageData()', () => {
       assert.throws(() => ctx.putImageData({}, 0, 0))
       assert.throws(() => ctx.putImageData(undefined, 0, 0))
@@ -768,6 +1578,50 @@ describe("Context2D", ()=>{
       assert.equal(ctx.isPointInStroke(path, ...inBoth), true)
     })
 
+    test("3D transforms", () => {
+      let terms = ['m11', 'm12', 'm13', 'm14', 'm21', 'm22', 'm23', 'm24',
+                   'm31', 'm32', 'm33', 'm34', 'm41', 'm42', 'm43', 'm44']
+
+      ctx.translate(100, 100)
+      ctx.rotate3d(0, 1, 0, Math.PI / 3)
+      let expected = new DOMMatrix().translate(100, 100).rotateAxisAngle(0, 1, 0, 60),
+          matrix = ctx.currentTransform
+      assert.equal(matrix.is2D, false)
+      for (const term of terms) assert.nearEqual(matrix[term], expected[term])
+
+      // the 4×4 matrix survives save/restore
+      ctx.save()
+      ctx.perspective(200)
+      assert.nearEqual(ctx.currentTransform.m34, -1/200)
+      ctx.restore()
+      assert.nearEqual(ctx.currentTransform.m34, 0)
+      for (const term of terms) assert.nearEqual(ctx.currentTransform[term], expected[term])
+
+      // rotating 60° around the y-axis halves the rect's apparent width
+      ctx.fillRect(-50, -50, 100, 100)
+      assert.deepEqual(pixel(120, 100), BLACK)
+      assert.deepEqual(pixel(130, 100), CLEAR)
+
+      ctx.beginPath()
+      ctx.rect(-50, -50, 100, 100)
+      assert.equal(ctx.isPointInPath(120, 100), true)
+      assert.equal(ctx.isPointInPath(130, 100), false)
+
+      // with perspective, the near edge appears taller than the far one
+      ctx.clearRect(0, 0, WIDTH, HEIGHT)
+      ctx.translate(100, 100)
+      ctx.perspective(200)
+      ctx.rotate3d(0, 1, 0, Math.PI / 4)
+      ctx.fillRect(-50, -50, 100, 100)
+      assert.deepEqual(pixel(62, 46), BLACK)
+      assert.deepEqual(pixel(125, 46), CLEAR)
+
+      ctx.setTransform3D(expected)
+      assert.equal(ctx.getTransform().is2D, false)
+      ctx.setTransform(expected)
+      assert.equal(ctx.getTransform().is2D, true)
+    })
+
     test("letterSpacing", () => {
         FontLibrary.use(`tests/assets/fonts/Monoton-Regular.woff`)
 
@@ -882,6 +1736,123 @@ describe("Context2D", ()=>{
       assert.deepEqual(pixel(0, y), CLEAR)
     })
 
+    test('drawAtlas()', async () => {
+      let sheet = new Canvas(2, 1),
+          sheetCtx = sheet.getContext('2d')
+      sheetCtx.fillStyle = 'red'
+      sheetCtx.fillRect(0, 0, 1, 1)
+      sheetCtx.fillStyle = 'blue'
+      sheetCtx.fillRect(1, 0, 1, 1)
+
+      ctx.imageSmoothingEnabled = false
+      ctx.drawAtlas(sheet, [[0, 0, 1, 1], {x:1, y:0, width:1, height:1}], [
+        {x:10, y:10, scale:10},
+        {x:35, y:15, scale:10, rotation:Math.PI/2, anchorX:.5, anchorY:.5},
+      ])
+      assert.deepEqual(pixel(15, 15), [255,0,0,255])
+      assert.deepEqual(pixel(35, 15), [0,0,255,255])
+      assert.deepEqual(pixel(45, 15), CLEAR)
+
+      // per-sprite colors are modulated with the image by default
+      ctx.drawAtlas(sheet, [[0, 0, 1, 1], [1, 0, 1, 1]], [[10, 0, 10, 50], [10, 0, 30, 50]], ['white', 'rgba(255,255,255,0.5)'])
+      assert.deepEqual(pixel(15, 55), [255,0,0,255])
+      let [r, g, b, a] = pixel(35, 55)
+      assert.ok(b == 255 && Math.abs(a - 128) <= 1)
+
+      assert.throws(() => ctx.drawAtlas(sheet, [[0, 0, 1, 1]], []), /one transform per sprite/)
+      assert.throws(() => ctx.drawAtlas(sheet, [[0, 0, 1, 1]], [[1, 0, 0, 0]], ['red', 'blue']), /one color per sprite/)
+      assert.throws(() => ctx.drawAtlas(sheet, [[0, 0, 1, 1]], [[1, 0, 0, 0]], ['red'], 'blend'), /Unknown blend mode/)
+    })
+
+    test('drawImageNine() & drawImageLattice()', async () => {
+      // a 3×3 image with red corners and a blue cross
+      let frame = new Canvas(3, 3),
+          frameCtx = frame.getContext('2d')
+      frameCtx.fillStyle = 'red'
+      frameCtx.fillRect(0, 0, 3, 3)
+      frameCtx.fillStyle = 'blue'
+      frameCtx.fillRect(1, 0, 1, 3)
+      frameCtx.fillRect(0, 1, 3, 1)
+
+      ctx.imageSmoothingEnabled = false
+      ctx.drawImageNine(frame, [1, 1, 1, 1], {x:10, y:10, width:30, height:30})
+      assert.deepEqual(pixel(10, 10), [255,0,0,255])
+      assert.deepEqual(pixel(39, 39), [255,0,0,255])
+      assert.deepEqual(pixel(11, 11), [0,0,255,255])
+      assert.deepEqual(pixel(25, 10), [0,0,255,255])
+      assert.deepEqual(pixel(25, 25), [0,0,255,255])
+
+      let flags = ['default', 'default', 'default', 'default', 'transparent', 'default', 'default', 'default', 'default']
+      ctx.drawImageLattice(frame, [1, 2], [1, 2], [50, 10, 30, 30], {flags})
+      assert.deepEqual(pixel(50, 10), [255,0,0,255])
+      assert.deepEqual(pixel(65, 10), [0,0,255,255])
+      assert.deepEqual(pixel(65, 25), CLEAR)
+
+      let colors = [null, null, null, null, 'lime', null, null, null, null]
+      ctx.drawImageLattice(frame, [1, 2], [1, 2], [90, 10, 30, 30], {colors})
+      assert.deepEqual(pixel(90, 10), [255,0,0,255])
+      assert.deepEqual(pixel(105, 25), [0,255,0,255])
+
+      assert.throws(() => ctx.drawImageNine(frame, [1, 1, 5, 5], [0, 0, 10, 10]), /within the image/)
+      assert.throws(() => ctx.drawImageLattice(frame, [2, 1], [1], [0, 0, 10, 10]), /increasing numbers/)
+      assert.throws(() => ctx.drawImageLattice(frame, [1], [1], [0, 0, 10, 10], {flags:['default']}), /one entry per cell/)
+      assert.throws(() => ctx.drawImageLattice(frame, [1], [1], [0, 0, 10, 10], {flags:['color', 'default', 'default', 'default']}), /no entry in `colors`/)
+    })
+
+    test('drawImageWarped()', async () => {
+      // a 2×2 image with red, lime, blue, and yellow quadrants
+      let src = new Canvas(2, 2),
+          srcCtx = src.getContext('2d')
+      ;[['red', 0, 0], ['lime', 1, 0], ['blue', 0, 1], ['yellow', 1, 1]].forEach(([color, x, y]) => {
+        srcCtx.fillStyle = color
+        srcCtx.fillRect(x, y, 1, 1)
+      })
+
+      ctx.imageSmoothingEnabled = false
+      ctx.drawImageWarped(src, null, [[10, 10], [50, 10], [50, 50], [10, 50]])
+      assert.deepEqual(pixel(20, 20), [255,0,0,255])
+      assert.deepEqual(pixel(40, 20), [0,255,0,255])
+      assert.deepEqual(pixel(20, 40), [0,0,255,255])
+      assert.deepEqual(pixel(40, 40), [255,255,0,255])
+      assert.deepEqual(pixel(55, 30), CLEAR)
+
+      // bilinear warps keep the midpoints of each edge aligned with the middle of the image
+      ctx.drawImageWarped(src, null, [70, 10, 110, 10, 150, 50, 70, 50])
+      assert.deepEqual(pixel(85, 25), [255,0,0,255])
+      assert.deepEqual(pixel(125, 45), [255,255,0,255])
+      assert.deepEqual(pixel(140, 20), CLEAR)
+
+      // grids stretch each cell independently and crops outside the image are left transparent
+      let grid = [
+        [{x:10, y:60}, {x:20, y:60}, {x:50, y:60}],
+        [{x:10, y:100}, {x:20, y:100}, {x:50, y:100}],
+      ]
+      ctx.drawImageWarped(src, [0, 0, 2, 2], grid)
+      assert.deepEqual(pixel(15, 80), [255,0,0,255])
+      assert.deepEqual(pixel(25, 80), [0,255,0,255])
+      ctx.drawImageWarped(src, {x:-2, y:0, width:4, height:2}, [[70, 60], [110, 60], [110, 100], [70, 100]])
+      assert.deepEqual(pixel(75, 70), CLEAR)
+      assert.deepEqual(pixel(95, 70), [255,0,0,255])
+      assert.deepEqual(pixel(105, 70), [0,255,0,255])
+
+      // grids large enough to hit the subdivision limit still place vertices on the cell boundaries
+      let stripes = new Canvas(3, 2),
+          stripesCtx = stripes.getContext('2d')
+      ;['red', 'lime', 'blue'].forEach((color, x) => {
+        stripesCtx.fillStyle = color
+        stripesCtx.fillRect(x, 0, 1, 2)
+      })
+      ctx.reset()
+      ctx.imageSmoothingEnabled = false
+      ctx.scale(4, 4)
+      ctx.drawImageWarped(stripes, null, [0, 50, 100].map(y => [0, 20, 500, 510].map(x => [x, y])))
+      assert.deepEqual(pixel(76, 200), [255,0,0,255])
+      assert.deepEqual(pixel(86, 200), [0,255,0,255])
+
+      assert.throws(() => ctx.drawImageWarped(src, null, [[0, 0], [10, 0], [10, 10]]), /Expected `dst` to be a quad/)
+      assert.throws(() => ctx.drawImageWarped(src, null, [[[0, 0], [10, 0]], [[0, 10]]]), /same number of points/)
+    })
+
     test('drawImage()', async () => {
       let image = await loadAsset('checkers.png')
       ctx.imageSmoothingEnabled = false
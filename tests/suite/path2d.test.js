@@ -585,6 +585,40 @@ describe("Path2D", ()=>{
       assert.matchesSubset(orig.bounds, {left:10, top:10, right:50, bottom:50})
     })
 
+    test("outline", () => {
+      let line = new Path2D()
+      line.moveTo(100, 100)
+      line.lineTo(200, 100)
+      line.lineTo(300, 100)
+
+      let rounded = ({left, top, right, bottom}) => (
+        {left:Math.round(left), top:Math.round(top), right:Math.round(right), bottom:Math.round(bottom)}
+      )
+
+      // constant width without a profile
+      let plain = line.outline(40)
+      assert.deepEqual(rounded(plain.bounds), {left:100, top:80, right:300, bottom:120})
+
+      // caps extend past the endpoints
+      let capped = line.outline(40, null, "round")
+      assert.deepEqual(rounded(capped.bounds), {left:80, top:80, right:320, bottom:120})
+
+      // t/width stops
+      ctx.fill(line.outline(40, [[0, 0], [1, 1]]))
+      assert.deepEqual(pixel(110, 90), CLEAR)
+      assert.deepEqual(pixel(290, 85), BLACK)
+      scrub()
+
+      // per-point pressure values
+      ctx.fill(line.outline(40, [1, 0, 1]))
+      assert.deepEqual(pixel(110, 90), BLACK)
+      assert.deepEqual(pixel(200, 95), CLEAR)
+      assert.deepEqual(pixel(290, 90), BLACK)
+
+      assert.throws(() => line.outline(40, "thick"), /Expected an array/)
+      assert.throws(() => line.outline(40, [[0, 1], 2]), /Expected an array/)
+    })
+
     test("transform", () => {
       let orig = new Path2D()
       orig.rect(-10, -10, 20, 20)